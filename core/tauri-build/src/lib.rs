@@ -65,7 +65,8 @@ impl Attributes {
 /// Run all build time helpers for your Tauri Application.
 ///
 /// The current helpers include the following:
-/// * Generates a Windows Resource file when targeting Windows.
+/// * Generates a Windows Resource file when targeting Windows, embedding the application manifest
+///   of the `TAURI_WINDOWS_MANIFEST` environment variable if set (`tauri build` sets it from `tauri > bundle > windows > manifest`).
 ///
 /// # Platforms
 ///
@@ -104,6 +105,13 @@ pub fn try_build(attributes: Attributes) -> Result<()> {
     if attributes.windows_attributes.window_icon_path.exists() {
       let mut res = WindowsResource::new();
       res.set_icon_with_id(&icon_path_string, "32512");
+      // the application manifest of `tauri > bundle > windows > manifest`, set by `tauri build`
+      println!("cargo:rerun-if-env-changed=TAURI_WINDOWS_MANIFEST");
+      if let Some(manifest) = std::env::var_os("TAURI_WINDOWS_MANIFEST") {
+        let manifest = PathBuf::from(manifest);
+        println!("cargo:rerun-if-changed={}", manifest.display());
+        res.set_manifest_file(&manifest.to_string_lossy());
+      }
       res.compile().with_context(|| {
         format!(
           "failed to compile `{}` into a Windows Resource file during tauri-build",
@@ -66,7 +66,8 @@ pub fn target_triple() -> crate::Result<String> {
 ///
 /// On Linux, when running in an AppImage the `APPDIR` variable will be set to
 /// the mounted location of the app, and the resource dir will be
-/// `${APPDIR}/usr/lib/${exe_name}`. If not running in an AppImage, the path is
+/// `${APPDIR}/usr/lib/${exe_name}`. When running in a Flatpak the path is
+/// `/app/lib/${exe_name}`. Otherwise the path is
 /// `/usr/lib/${exe_name}`.  When running the app from
 /// `src-tauri/target/(debug|release)/`, the path is
 /// `${exe_dir}/../lib/${exe_name}`.
@@ -89,6 +90,19 @@ pub fn resource_dir(package_info: &PackageInfo) -> crate::Result<PathBuf> {
     if curr_dir.ends_with("/data/usr/bin") {
       // running from the deb bundle dir
       Ok(exe_dir.join(format!("../lib/{}", package_info.package_name())))
+    } else if env::var_os("FLATPAK_ID").is_some() {
+      // running the Flatpak bundle, installed under the `/app` prefix
+      Ok(PathBuf::from(format!(
+        "/app/lib/{}",
+        package_info.package_name()
+      )))
+    } else if let Ok(snap) = env::var("SNAP") {
+      // running the Snap bundle, the deb data is dumped into the snap
+      Ok(PathBuf::from(format!(
+        "{}/usr/lib/{}",
+        snap,
+        package_info.package_name()
+      )))
     } else if let Ok(appdir) = env::var("APPDIR") {
       Ok(PathBuf::from(format!(
         "{}/usr/lib/{}",
@@ -18,17 +18,32 @@ mod windows;
 pub use self::{
   category::AppCategory,
   settings::{
-    BundleBinary, BundleSettings, DebianSettings, MacOsSettings, PackageSettings, PackageType,
-    Settings, SettingsBuilder, UpdaterSettings,
+    BundleBinary, BundleSettings, DebianSettings, FlatpakSettings, MacOsSettings, PackageSettings,
+    PackageType, RpmSettings, Settings, SettingsBuilder, SnapSettings, UpdaterSettings,
   },
 };
-pub use settings::{WindowsSettings, WixSettings};
+pub use settings::{
+  NsisSettings, UpdaterArchiveFormat, WindowsSettings, WixInstallScope, WixSettings,
+};
+
+use common::{print_finished, print_info, print_warning};
 
-use common::{print_finished, print_info};
+use std::{
+  collections::VecDeque,
+  panic::resume_unwind,
+  path::PathBuf,
+  sync::{mpsc::channel, Arc, Mutex},
+  thread::spawn,
+};
 
-use std::path::PathBuf;
+/// The maximum number of package types bundled at the same time.
+const MAX_PARALLEL_BUNDLES: usize = 4;
+
+/// The result of bundling a package type, `None` if the package type isn't supported on this platform.
+type BundleResult = Option<crate::Result<Vec<PathBuf>>>;
 
 /// Generated bundle metadata.
+#[derive(Debug)]
 pub struct Bundle {
   /// The package type.
   pub package_type: PackageType,
@@ -38,39 +53,28 @@ pub struct Bundle {
 
 /// Bundles the project.
 /// Returns the list of paths where the bundles can be found.
+///
+/// The package types that don't depend on other bundles write to their own directories, so they're bundled in parallel.
+/// The DMG and the updater archives are bundled after them, from their bundles.
+///
+/// If [`SettingsBuilder::keep_going`] is set, a failing package type doesn't stop the remaining ones
+/// and the failures are returned in a [`crate::Error::PartialBundle`] error.
 pub fn bundle_project(settings: Settings) -> crate::Result<Vec<Bundle>> {
   let mut bundles = Vec::new();
+  let mut failures = Vec::new();
   let package_types = settings.package_types()?;
+  let settings = Arc::new(settings);
 
-  for package_type in &package_types {
-    let bundle_paths = match package_type {
-      #[cfg(target_os = "macos")]
-      PackageType::MacOsBundle => macos::app::bundle_project(&settings)?,
-      #[cfg(target_os = "macos")]
-      PackageType::IosBundle => macos::ios::bundle_project(&settings)?,
-      #[cfg(target_os = "windows")]
-      PackageType::WindowsMsi => windows::msi::bundle_project(&settings)?,
-      #[cfg(target_os = "linux")]
-      PackageType::Deb => linux::debian::bundle_project(&settings)?,
-      #[cfg(target_os = "linux")]
-      PackageType::Rpm => linux::rpm::bundle_project(&settings)?,
-      #[cfg(target_os = "linux")]
-      PackageType::AppImage => linux::appimage::bundle_project(&settings)?,
-      // dmg is dependant of MacOsBundle, we send our bundles to prevent rebuilding
-      #[cfg(target_os = "macos")]
-      PackageType::Dmg => macos::dmg::bundle_project(&settings, &bundles)?,
-      // updater is dependant of multiple bundle, we send our bundles to prevent rebuilding
-      PackageType::Updater => updater_bundle::bundle_project(&settings, &bundles)?,
-      _ => {
-        print_info(&format!("ignoring {:?}", package_type))?;
-        continue;
-      }
-    };
-
-    bundles.push(Bundle {
-      package_type: package_type.to_owned(),
-      bundle_paths,
-    });
+  let (dependent_types, independent_types): (Vec<PackageType>, Vec<PackageType>) = package_types
+    .iter()
+    .partition(|package_type| depends_on_bundles(**package_type));
+  for (package_type, result) in bundle_in_parallel(&settings, &independent_types) {
+    collect_result(&settings, package_type, result, &mut bundles, &mut failures)?;
+  }
+  // the dependent types are bundled in order, since the updater archives may need the DMG
+  for package_type in dependent_types {
+    let result = bundle_package_type(&settings, package_type, &bundles);
+    collect_result(&settings, package_type, result, &mut bundles, &mut failures)?;
   }
 
   settings.copy_resources(settings.project_out_directory())?;
@@ -78,7 +82,138 @@ pub fn bundle_project(settings: Settings) -> crate::Result<Vec<Bundle>> {
 
   print_finished(&bundles)?;
 
-  Ok(bundles)
+  if failures.is_empty() {
+    Ok(bundles)
+  } else {
+    Err(crate::Error::PartialBundle { bundles, failures })
+  }
+}
+
+/// Whether the package type is bundled from the bundles of other package types.
+fn depends_on_bundles(package_type: PackageType) -> bool {
+  matches!(package_type, PackageType::Dmg | PackageType::Updater)
+}
+
+/// Adds the bundle of a package type to the bundles, or its error to the failures if [`SettingsBuilder::keep_going`] is set.
+fn collect_result(
+  settings: &Settings,
+  package_type: PackageType,
+  result: BundleResult,
+  bundles: &mut Vec<Bundle>,
+  failures: &mut Vec<(PackageType, crate::Error)>,
+) -> crate::Result<()> {
+  match result {
+    None => print_info(&format!("ignoring {:?}", package_type))?,
+    Some(Ok(bundle_paths)) => bundles.push(Bundle {
+      package_type,
+      bundle_paths,
+    }),
+    Some(Err(e)) if settings.keep_going() => {
+      print_warning(&format!(
+        "failed to bundle {}: {}",
+        package_type.short_name(),
+        e
+      ))?;
+      failures.push((package_type, e));
+    }
+    Some(Err(e)) => {
+      return Err(crate::Error::BundleFailed {
+        package_type,
+        source: Box::new(e),
+      })
+    }
+  }
+  Ok(())
+}
+
+/// Bundles the package types on a bounded pool of threads, returning their results in the order of `package_types`.
+///
+/// Every package type runs to completion, so the failures are reported once the running bundles are done.
+fn bundle_in_parallel(
+  settings: &Arc<Settings>,
+  package_types: &[PackageType],
+) -> Vec<(PackageType, BundleResult)> {
+  let queue = Arc::new(Mutex::new(
+    package_types
+      .iter()
+      .copied()
+      .enumerate()
+      .collect::<VecDeque<_>>(),
+  ));
+  let (tx, rx) = channel();
+  let workers: Vec<_> = (0..package_types.len().min(MAX_PARALLEL_BUNDLES))
+    .map(|_| {
+      let settings = settings.clone();
+      let queue = queue.clone();
+      let tx = tx.clone();
+      spawn(move || loop {
+        let next = queue.lock().unwrap().pop_front();
+        match next {
+          Some((index, package_type)) => {
+            let result = bundle_package_type(&settings, package_type, &[]);
+            let _ = tx.send((index, package_type, result));
+          }
+          None => break,
+        }
+      })
+    })
+    .collect();
+  drop(tx);
+
+  let mut results: Vec<_> = rx.iter().collect();
+  for worker in workers {
+    if let Err(panic) = worker.join() {
+      resume_unwind(panic);
+    }
+  }
+  results.sort_by_key(|(index, _, _)| *index);
+  results
+    .into_iter()
+    .map(|(_, package_type, result)| (package_type, result))
+    .collect()
+}
+
+/// Bundles a package type, given the bundles of the package types it depends on.
+fn bundle_package_type(
+  settings: &Settings,
+  package_type: PackageType,
+  #[allow(unused_variables)] bundles: &[Bundle],
+) -> BundleResult {
+  let bundle_paths = match package_type {
+    #[cfg(target_os = "macos")]
+    PackageType::MacOsBundle => macos::app::bundle_project(settings),
+    #[cfg(target_os = "macos")]
+    PackageType::IosBundle => macos::ios::bundle_project(settings),
+    #[cfg(target_os = "windows")]
+    PackageType::WindowsMsi => windows::msi::bundle_project(settings),
+    #[cfg(target_os = "windows")]
+    PackageType::Nsis => windows::nsis::bundle_project(settings),
+    #[cfg(target_os = "linux")]
+    PackageType::Deb => linux::debian::bundle_project(settings),
+    #[cfg(target_os = "linux")]
+    PackageType::Rpm => linux::rpm::bundle_project(settings),
+    #[cfg(target_os = "linux")]
+    PackageType::AppImage => linux::appimage::bundle_project(settings),
+    #[cfg(target_os = "linux")]
+    PackageType::TarGz => {
+      linux::archive::bundle_project(settings, linux::archive::ArchiveFormat::TarGz)
+    }
+    #[cfg(target_os = "linux")]
+    PackageType::TarXz => {
+      linux::archive::bundle_project(settings, linux::archive::ArchiveFormat::TarXz)
+    }
+    #[cfg(target_os = "linux")]
+    PackageType::Flatpak => linux::flatpak::bundle_project(settings),
+    #[cfg(target_os = "linux")]
+    PackageType::Snap => linux::snap::bundle_project(settings),
+    // dmg is dependant of MacOsBundle, we send our bundles to prevent rebuilding
+    #[cfg(target_os = "macos")]
+    PackageType::Dmg => macos::dmg::bundle_project(settings, bundles),
+    // updater is dependant of multiple bundle, we send our bundles to prevent rebuilding
+    PackageType::Updater => updater_bundle::bundle_project(settings, bundles),
+    _ => return None,
+  };
+  Some(bundle_paths)
 }
 
 /// Check to see if there are icons in the settings struct
@@ -172,7 +172,7 @@ pub fn print_finished(bundles: &[crate::bundle::Bundle]) -> crate::Result<()> {
       if bundle.package_type == crate::PackageType::Updater {
         note = " (updater)";
       }
-      println!("        {}{}", path.display(), note,);
+      eprintln!("        {}{}", path.display(), note,);
     }
   }
   Ok(())
@@ -231,9 +231,104 @@ pub fn execute_with_verbosity(cmd: &mut Command, settings: &Settings) -> crate::
   }
 }
 
+/// Appends the file or directory at `src` to the tarball as `dest`.
+///
+/// When the reproducible `mtime` is set, the entry gets it as modification time, a root owner
+/// and normalized permissions, so the archive only depends on the file contents.
+#[cfg(not(target_os = "windows"))]
+pub fn append_to_tar<W: Write>(
+  builder: &mut tar::Builder<W>,
+  src: &Path,
+  dest: &Path,
+  mtime: Option<u64>,
+) -> crate::Result<()> {
+  match mtime {
+    Some(mtime) => {
+      let metadata = fs::metadata(src)?;
+      let mut header = tar::Header::new_gnu();
+      header.set_metadata_in_mode(&metadata, tar::HeaderMode::Deterministic);
+      header.set_mtime(mtime);
+      if metadata.is_dir() {
+        builder.append_data(&mut header, dest, io::empty())?;
+      } else {
+        builder.append_data(&mut header, dest, File::open(src)?)?;
+      }
+    }
+    None => builder.append_path_with_name(src, dest)?,
+  }
+  Ok(())
+}
+
+/// Creates a gzip encoder, storing the reproducible `mtime` on the gzip header when set instead of the current time.
+#[cfg(not(target_os = "windows"))]
+pub fn gzip_encoder<W: Write>(
+  writer: W,
+  mtime: Option<u64>,
+) -> io::Result<libflate::gzip::Encoder<W>> {
+  match mtime {
+    Some(mtime) => {
+      let header = libflate::gzip::HeaderBuilder::new()
+        .modification_time(mtime as u32)
+        .finish();
+      libflate::gzip::Encoder::with_options(
+        writer,
+        libflate::gzip::EncodeOptions::new().header(header),
+      )
+    }
+    None => libflate::gzip::Encoder::new(writer),
+  }
+}
+
+/// A UTC date and time.
+#[allow(dead_code)]
+#[derive(Debug, PartialEq)]
+pub struct UtcDateTime {
+  pub year: i64,
+  pub month: u32,
+  pub day: u32,
+  pub hour: u32,
+  pub minute: u32,
+  pub second: u32,
+  /// The day of the week, from 0 (Sunday) to 6 (Saturday).
+  pub weekday: u32,
+}
+
+/// Converts a Unix timestamp to its UTC date and time.
+#[allow(dead_code)]
+pub fn utc_date_time(timestamp: u64) -> UtcDateTime {
+  // days since the Unix epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+  let days_since_epoch = (timestamp / 86400) as i64;
+  let seconds = (timestamp % 86400) as u32;
+  let days = days_since_epoch + 719468;
+  let era = days / 146097;
+  let day_of_era = days - era * 146097;
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  };
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+  UtcDateTime {
+    year,
+    month: month as u32,
+    day: day as u32,
+    hour: seconds / 3600,
+    minute: seconds % 3600 / 60,
+    second: seconds % 60,
+    // the Unix epoch was a Thursday
+    weekday: ((days_since_epoch + 4) % 7) as u32,
+  }
+}
+
 #[cfg(test)]
 mod tests {
-  use super::{create_file, is_retina, resource_relpath};
+  use super::{create_file, is_retina, resource_relpath, utc_date_time, UtcDateTime};
   use std::{io::Write, path::PathBuf};
 
   #[test]
@@ -321,4 +416,45 @@ mod tests {
       PathBuf::from("_root_/home/ferris/crab.png")
     );
   }
+
+  #[test]
+  fn utc_date_times() {
+    assert_eq!(
+      utc_date_time(0),
+      UtcDateTime {
+        year: 1970,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+        weekday: 4,
+      }
+    );
+    // leap day
+    assert_eq!(
+      utc_date_time(951782400),
+      UtcDateTime {
+        year: 2000,
+        month: 2,
+        day: 29,
+        hour: 0,
+        minute: 0,
+        second: 0,
+        weekday: 2,
+      }
+    );
+    assert_eq!(
+      utc_date_time(1700000000),
+      UtcDateTime {
+        year: 2023,
+        month: 11,
+        day: 14,
+        hour: 22,
+        minute: 13,
+        second: 20,
+        weekday: 2,
+      }
+    );
+  }
 }
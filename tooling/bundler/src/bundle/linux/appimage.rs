@@ -100,6 +100,10 @@ pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
   // execute the shell script to build the appimage.
   let mut cmd = Command::new(&sh_file);
   cmd.current_dir(output_path);
+  // mksquashfs uses it as the filesystem and file timestamps
+  if let Some(source_date_epoch) = settings.source_date_epoch() {
+    cmd.env("SOURCE_DATE_EPOCH", source_date_epoch.to_string());
+  }
 
   common::execute_with_verbosity(&mut cmd, &settings).map_err(|_| {
     crate::Error::ShellScriptError(format!(
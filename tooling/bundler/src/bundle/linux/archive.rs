@@ -0,0 +1,291 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+// A plain tarball of the app, for distros and custom installers that don't use the deb or AppImage formats.
+//
+// The archive contains a single `<product>_<version>_<arch>` folder with the
+// following layout, mirroring the project out directory so the app finds its
+// resources next to the binary:
+//
+// <product>_<version>_<arch>/
+//   <binaries>              # the app binaries and the external binaries
+//   <resources>             # the bundle resources, with their relative paths
+//   icons/<icon files>      # the bundle icons
+
+use super::super::common;
+use crate::Settings;
+
+use std::{
+  fs::remove_file,
+  io::Write,
+  path::{Path, PathBuf},
+};
+
+/// The compression of the archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+  /// A gzip compressed tarball (.tar.gz).
+  TarGz,
+  /// A xz compressed tarball (.tar.xz).
+  TarXz,
+}
+
+impl ArchiveFormat {
+  /// The file extension of the archive.
+  fn extension(self) -> &'static str {
+    match self {
+      Self::TarGz => "tar.gz",
+      Self::TarXz => "tar.xz",
+    }
+  }
+}
+
+/// Bundles the project.
+/// Returns a vector of PathBuf that shows where the archive was created.
+pub fn bundle_project(settings: &Settings, format: ArchiveFormat) -> crate::Result<Vec<PathBuf>> {
+  let root_dir_name = format!(
+    "{}_{}_{}",
+    settings.main_binary_name(),
+    settings.version_string(),
+    settings.binary_arch()
+  );
+  let archive_path = settings
+    .project_out_directory()
+    .join("bundle/archive")
+    .join(format!("{}.{}", root_dir_name, format.extension()));
+
+  common::print_bundling(format!("{:?}", archive_path).as_str())?;
+
+  if archive_path.exists() {
+    remove_file(&archive_path)?;
+  }
+  let archive_file = common::create_file(&archive_path)?;
+  let root_dir = Path::new(&root_dir_name);
+
+  let mut archive_file = match format {
+    ArchiveFormat::TarGz => {
+      let encoder = common::gzip_encoder(archive_file, settings.source_date_epoch())?;
+      write_archive(settings, root_dir, encoder)?
+        .finish()
+        .into_result()?
+    }
+    ArchiveFormat::TarXz => {
+      let encoder = xz2::write::XzEncoder::new(archive_file, 6);
+      write_archive(settings, root_dir, encoder)?.finish()?
+    }
+  };
+  archive_file.flush()?;
+
+  Ok(vec![archive_path])
+}
+
+/// Writes the binaries, resources and icons of the app to a tarball under `root_dir`.
+fn write_archive<W: Write>(settings: &Settings, root_dir: &Path, writer: W) -> crate::Result<W> {
+  let mut builder = tar::Builder::new(writer);
+  let mtime = settings.source_date_epoch();
+
+  for binary in settings.binaries() {
+    common::append_to_tar(
+      &mut builder,
+      &settings.binary_path(binary),
+      &root_dir.join(binary.name()),
+      mtime,
+    )?;
+  }
+  for src in settings.external_binaries() {
+    let src = src?;
+    let file_name = src
+      .file_name()
+      .expect("failed to extract external binary filename");
+    common::append_to_tar(&mut builder, &src, &root_dir.join(file_name), mtime)?;
+  }
+  for resource in settings.resources() {
+    let (src, dest) = resource?;
+    common::append_to_tar(&mut builder, &src, &root_dir.join(dest), mtime)?;
+  }
+  for src in settings.icon_files() {
+    let src = src?;
+    let file_name = src.file_name().expect("failed to extract icon filename");
+    common::append_to_tar(
+      &mut builder,
+      &src,
+      &root_dir.join("icons").join(file_name),
+      mtime,
+    )?;
+  }
+
+  Ok(builder.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{bundle_project, common, ArchiveFormat};
+  use crate::{
+    bundle::settings::test_package_settings, BundleBinary, BundleSettings, Settings,
+    SettingsBuilder,
+  };
+  use std::{
+    fs::{create_dir, read, write, File},
+    io::Read,
+    path::{Path, PathBuf},
+  };
+
+  fn settings(out_dir: &Path) -> Settings {
+    settings_builder(out_dir)
+      .build()
+      .expect("failed to build settings")
+  }
+
+  fn settings_builder(out_dir: &Path) -> SettingsBuilder {
+    write(out_dir.join("app"), b"binary").expect("failed to write binary");
+    write(out_dir.join("icon.png"), b"icon").expect("failed to write icon");
+    SettingsBuilder::new()
+      .project_out_directory(out_dir)
+      .package_settings(test_package_settings())
+      .bundle_settings(BundleSettings {
+        icon: Some(vec![out_dir.join("icon.png").display().to_string()]),
+        ..Default::default()
+      })
+      .binaries(vec![BundleBinary::new("app".into(), true)])
+  }
+
+  fn entries<R: Read>(reader: R) -> Vec<PathBuf> {
+    tar::Archive::new(reader)
+      .entries()
+      .expect("failed to read archive")
+      .map(|entry| {
+        entry
+          .expect("failed to read entry")
+          .path()
+          .expect("invalid entry path")
+          .into_owned()
+      })
+      .collect()
+  }
+
+  #[test]
+  fn tar_gz_contents() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    let paths = bundle_project(&settings(tmp.path()), ArchiveFormat::TarGz)
+      .expect("failed to bundle archive");
+    let expected_root = format!("app_1.0.0_{}", std::env::consts::ARCH);
+    assert_eq!(
+      paths,
+      vec![tmp
+        .path()
+        .join("bundle/archive")
+        .join(format!("{}.tar.gz", expected_root))]
+    );
+
+    assert_eq!(
+      &read(&paths[0]).expect("failed to read archive")[..2],
+      &[0x1f, 0x8b]
+    );
+    let decoder = libflate::gzip::Decoder::new(File::open(&paths[0]).expect("failed to open"))
+      .expect("invalid gzip archive");
+    let root = PathBuf::from(expected_root);
+    assert_eq!(
+      entries(decoder),
+      vec![root.join("app"), root.join("icons/icon.png")]
+    );
+  }
+
+  #[test]
+  fn excluded_resources() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    let assets = tmp.path().join("assets");
+    create_dir(&assets).expect("failed to create assets dir");
+    write(assets.join("index.js"), b"index").expect("failed to write resource");
+    write(assets.join("index.js.map"), b"map").expect("failed to write resource");
+    let settings = settings_builder(tmp.path())
+      .bundle_settings(BundleSettings {
+        icon: Some(vec![tmp.path().join("icon.png").display().to_string()]),
+        resources: Some(vec![assets.display().to_string()]),
+        resources_exclude: Some(vec!["**/*.map".into()]),
+        ..Default::default()
+      })
+      .build()
+      .expect("failed to build settings");
+    let paths = bundle_project(&settings, ArchiveFormat::TarGz).expect("failed to bundle archive");
+
+    let decoder = libflate::gzip::Decoder::new(File::open(&paths[0]).expect("failed to open"))
+      .expect("invalid gzip archive");
+    let root = PathBuf::from(format!("app_1.0.0_{}", std::env::consts::ARCH));
+    assert_eq!(
+      entries(decoder),
+      vec![
+        root.join("app"),
+        root.join(common::resource_relpath(&assets.join("index.js"))),
+        root.join("icons/icon.png")
+      ]
+    );
+  }
+
+  #[test]
+  fn resource_mappings() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    let database = tmp.path().join("app.db");
+    write(&database, b"db").expect("failed to write resource");
+    let settings = settings_builder(tmp.path())
+      .bundle_settings(BundleSettings {
+        icon: Some(vec![tmp.path().join("icon.png").display().to_string()]),
+        resource_mappings: Some(vec![(database, PathBuf::from("data/app.db"))]),
+        ..Default::default()
+      })
+      .build()
+      .expect("failed to build settings");
+    let paths = bundle_project(&settings, ArchiveFormat::TarGz).expect("failed to bundle archive");
+
+    let decoder = libflate::gzip::Decoder::new(File::open(&paths[0]).expect("failed to open"))
+      .expect("invalid gzip archive");
+    let root = PathBuf::from(format!("app_1.0.0_{}", std::env::consts::ARCH));
+    assert_eq!(
+      entries(decoder),
+      vec![
+        root.join("app"),
+        root.join("data/app.db"),
+        root.join("icons/icon.png")
+      ]
+    );
+  }
+
+  #[test]
+  fn tar_xz_contents() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    let paths = bundle_project(&settings(tmp.path()), ArchiveFormat::TarXz)
+      .expect("failed to bundle archive");
+    assert!(paths[0].to_string_lossy().ends_with(".tar.xz"));
+
+    assert_eq!(
+      &read(&paths[0]).expect("failed to read archive")[..6],
+      &[0xfd, b'7', b'z', b'X', b'Z', 0x00]
+    );
+    let decoder = xz2::read::XzDecoder::new(File::open(&paths[0]).expect("failed to open"));
+    let root = PathBuf::from(format!("app_1.0.0_{}", std::env::consts::ARCH));
+    assert_eq!(
+      entries(decoder),
+      vec![root.join("app"), root.join("icons/icon.png")]
+    );
+  }
+
+  #[test]
+  fn reproducible_tar_gz() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    let bundle = || {
+      // the inputs are written again so their modification times change between the builds
+      let settings = settings_builder(tmp.path())
+        .source_date_epoch(1_600_000_000)
+        .build()
+        .expect("failed to build settings");
+      let paths =
+        bundle_project(&settings, ArchiveFormat::TarGz).expect("failed to bundle archive");
+      md5::compute(read(&paths[0]).expect("failed to read archive"))
+    };
+
+    let first_checksum = bundle();
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let second_checksum = bundle();
+    assert_eq!(first_checksum, second_checksum);
+  }
+}
@@ -27,7 +27,7 @@ use crate::Settings;
 
 use anyhow::Context;
 use image::{self, png::PngDecoder, GenericImageView, ImageDecoder};
-use libflate::gzip;
+use sha2::Digest;
 use std::process::{Command, Stdio};
 use walkdir::WalkDir;
 
@@ -37,8 +37,14 @@ use std::{
   fs::{self, File},
   io::{self, Write},
   path::{Path, PathBuf},
+  time::{SystemTime, UNIX_EPOCH},
 };
 
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+  "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 pub struct DebIcon {
   pub width: u32,
@@ -79,6 +85,7 @@ pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
 
   // Generate control files.
   let control_dir = package_dir.join("control");
+  let control_path = control_dir.join("control");
   generate_control_file(settings, arch, &control_dir, &data_dir)
     .with_context(|| "Failed to create control file")?;
   generate_md5sums(&control_dir, &data_dir).with_context(|| "Failed to create md5sums file")?;
@@ -90,16 +97,175 @@ pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
     .with_context(|| "Failed to create debian-binary file")?;
 
   // Apply tar/gzip/ar to create the final package file.
+  let mtime = settings.source_date_epoch();
   let control_tar_gz_path =
-    tar_and_gzip_dir(control_dir).with_context(|| "Failed to tar/gzip control directory")?;
+    tar_and_gzip_dir(control_dir, mtime).with_context(|| "Failed to tar/gzip control directory")?;
   let data_tar_gz_path =
-    tar_and_gzip_dir(data_dir).with_context(|| "Failed to tar/gzip data directory")?;
+    tar_and_gzip_dir(data_dir, mtime).with_context(|| "Failed to tar/gzip data directory")?;
   create_archive(
     vec![debian_binary_path, control_tar_gz_path, data_tar_gz_path],
     &package_path,
+    mtime,
   )
   .with_context(|| "Failed to create package archive")?;
-  Ok(vec![package_path])
+
+  if let Some(key) = &settings.deb().signing_key {
+    sign_package(&package_path, key).with_context(|| "Failed to sign package")?;
+  }
+  let mut paths = Vec::new();
+  if settings.deb().repository_metadata {
+    paths = write_repository_metadata(settings, arch, &package_path, &control_path)
+      .with_context(|| "Failed to create repository metadata")?;
+  }
+  paths.insert(0, package_path);
+  Ok(paths)
+}
+
+/// Signs the package with `dpkg-sig` as its builder.
+fn sign_package(package_path: &Path, key: &str) -> crate::Result<()> {
+  common::print_info(&format!("signing the package with the GPG key {}", key))?;
+  run_tool(
+    Command::new("dpkg-sig")
+      .args(&["--sign", "builder", "-k", key])
+      .arg(package_path),
+    "dpkg-sig",
+  )
+}
+
+/// Writes the `Packages`, `Packages.gz` and `Release` files of a flat apt repository next to the package,
+/// so its directory can be served as the `./` suite of an apt source. Returns the paths of the written files.
+///
+/// The `Release` file is signed as `Release.gpg` and `InRelease` when the debian settings have a signing key.
+fn write_repository_metadata(
+  settings: &Settings,
+  arch: &str,
+  package_path: &Path,
+  control_path: &Path,
+) -> crate::Result<Vec<PathBuf>> {
+  let repository_dir = package_path
+    .parent()
+    .expect("the package has no parent directory");
+  // the sizes and checksums are read after signing, since `dpkg-sig` changes the package
+  let package = fs::read(package_path)?;
+  let control = fs::read_to_string(control_path)?;
+  let packages = format!(
+    "{}\nFilename: ./{}\nSize: {}\nMD5sum: {:x}\nSHA256: {}\n",
+    control.trim_end(),
+    package_path
+      .file_name()
+      .expect("the package has no file name")
+      .to_string_lossy(),
+    package.len(),
+    md5::compute(&package),
+    hex::encode(sha2::Sha256::digest(&package))
+  );
+
+  let packages_path = repository_dir.join("Packages");
+  create_file_with_data(&packages_path, &packages)?;
+  let packages_gz_path = repository_dir.join("Packages.gz");
+  let mut gzip_encoder = common::gzip_encoder(
+    common::create_file(&packages_gz_path)?,
+    settings.source_date_epoch(),
+  )?;
+  gzip_encoder.write_all(packages.as_bytes())?;
+  gzip_encoder.finish().into_result()?.flush()?;
+  let packages_gz = fs::read(&packages_gz_path)?;
+
+  let timestamp = settings.source_date_epoch().unwrap_or_else(|| {
+    SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|duration| duration.as_secs())
+      .unwrap_or_default()
+  });
+  let indexes = [
+    ("Packages", packages.as_bytes()),
+    ("Packages.gz", packages_gz.as_slice()),
+  ];
+  let mut release = format!(
+    "Date: {}\nArchitectures: {}\nMD5Sum:\n",
+    release_date(timestamp),
+    arch
+  );
+  for (name, contents) in &indexes {
+    release.push_str(&format!(
+      " {:x} {} {}\n",
+      md5::compute(contents),
+      contents.len(),
+      name
+    ));
+  }
+  release.push_str("SHA256:\n");
+  for (name, contents) in &indexes {
+    release.push_str(&format!(
+      " {} {} {}\n",
+      hex::encode(sha2::Sha256::digest(contents)),
+      contents.len(),
+      name
+    ));
+  }
+  let release_path = repository_dir.join("Release");
+  create_file_with_data(&release_path, &release)?;
+
+  let mut paths = vec![packages_path, packages_gz_path, release_path.clone()];
+  if let Some(key) = &settings.deb().signing_key {
+    let release_gpg_path = repository_dir.join("Release.gpg");
+    let in_release_path = repository_dir.join("InRelease");
+    run_tool(
+      Command::new("gpg")
+        .args(&["--batch", "--yes", "--local-user", key, "--armor"])
+        .arg("--detach-sign")
+        .arg("--output")
+        .arg(&release_gpg_path)
+        .arg(&release_path),
+      "gpg",
+    )?;
+    run_tool(
+      Command::new("gpg")
+        .args(&["--batch", "--yes", "--local-user", key])
+        .arg("--clearsign")
+        .arg("--output")
+        .arg(&in_release_path)
+        .arg(&release_path),
+      "gpg",
+    )?;
+    paths.push(release_gpg_path);
+    paths.push(in_release_path);
+  }
+  Ok(paths)
+}
+
+/// Formats the `Date` field of the `Release` file, e.g. `Tue, 14 Nov 2023 22:13:20 UTC`.
+fn release_date(timestamp: u64) -> String {
+  let date_time = common::utc_date_time(timestamp);
+  format!(
+    "{}, {:02} {} {} {:02}:{:02}:{:02} UTC",
+    WEEKDAYS[date_time.weekday as usize],
+    date_time.day,
+    MONTHS[date_time.month as usize - 1],
+    date_time.year,
+    date_time.hour,
+    date_time.minute,
+    date_time.second
+  )
+}
+
+/// Runs a signing tool, failing with its stderr if it fails or with [`crate::Error::ToolNotFound`] if it isn't installed.
+pub(super) fn run_tool(command: &mut Command, tool: &str) -> crate::Result<()> {
+  let output = command
+    .stdout(Stdio::inherit())
+    .output()
+    .map_err(|e| match e.kind() {
+      io::ErrorKind::NotFound => crate::Error::ToolNotFound(tool.into()),
+      _ => e.into(),
+    })?;
+  if !output.status.success() {
+    return Err(crate::Error::Sign(format!(
+      "`{}` failed: {}",
+      tool,
+      String::from_utf8_lossy(&output.stderr).trim()
+    )));
+  }
+  Ok(())
 }
 
 /// Generate the debian data folders and files.
@@ -452,23 +618,22 @@ fn total_dir_size(dir: &Path) -> crate::Result<u64> {
   Ok(total)
 }
 
-/// Writes a tar file to the given writer containing the given directory.
-fn create_tar_from_dir<P: AsRef<Path>, W: Write>(src_dir: P, dest_file: W) -> crate::Result<W> {
+/// Writes a tar file to the given writer containing the given directory, with its entries sorted by name.
+fn create_tar_from_dir<P: AsRef<Path>, W: Write>(
+  src_dir: P,
+  dest_file: W,
+  mtime: Option<u64>,
+) -> crate::Result<W> {
   let src_dir = src_dir.as_ref();
   let mut tar_builder = tar::Builder::new(dest_file);
-  for entry in WalkDir::new(&src_dir) {
+  for entry in WalkDir::new(&src_dir).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
     let entry = entry?;
     let src_path = entry.path();
     if src_path == src_dir {
       continue;
     }
     let dest_path = src_path.strip_prefix(&src_dir)?;
-    if entry.file_type().is_dir() {
-      tar_builder.append_dir(dest_path, src_path)?;
-    } else {
-      let mut src_file = fs::File::open(src_path)?;
-      tar_builder.append_file(dest_path, &mut src_file)?;
-    }
+    common::append_to_tar(&mut tar_builder, src_path, dest_path, mtime)?;
   }
   let dest_file = tar_builder.into_inner()?;
   Ok(dest_file)
@@ -477,12 +642,12 @@ fn create_tar_from_dir<P: AsRef<Path>, W: Write>(src_dir: P, dest_file: W) -> cr
 /// Creates a `.tar.gz` file from the given directory (placing the new file
 /// within the given directory's parent directory), then deletes the original
 /// directory and returns the path to the new file.
-fn tar_and_gzip_dir<P: AsRef<Path>>(src_dir: P) -> crate::Result<PathBuf> {
+fn tar_and_gzip_dir<P: AsRef<Path>>(src_dir: P, mtime: Option<u64>) -> crate::Result<PathBuf> {
   let src_dir = src_dir.as_ref();
   let dest_path = src_dir.with_extension("tar.gz");
   let dest_file = common::create_file(&dest_path)?;
-  let gzip_encoder = gzip::Encoder::new(dest_file)?;
-  let gzip_encoder = create_tar_from_dir(src_dir, gzip_encoder)?;
+  let gzip_encoder = common::gzip_encoder(dest_file, mtime)?;
+  let gzip_encoder = create_tar_from_dir(src_dir, gzip_encoder, mtime)?;
   let mut dest_file = gzip_encoder.finish().into_result()?;
   dest_file.flush()?;
   Ok(dest_path)
@@ -490,10 +655,24 @@ fn tar_and_gzip_dir<P: AsRef<Path>>(src_dir: P) -> crate::Result<PathBuf> {
 
 /// Creates an `ar` archive from the given source files and writes it to the
 /// given destination path.
-fn create_archive(srcs: Vec<PathBuf>, dest: &Path) -> crate::Result<()> {
+fn create_archive(srcs: Vec<PathBuf>, dest: &Path, mtime: Option<u64>) -> crate::Result<()> {
   let mut builder = ar::Builder::new(common::create_file(&dest)?);
   for path in &srcs {
-    builder.append_path(path)?;
+    match mtime {
+      Some(mtime) => {
+        let mut file = fs::File::open(path)?;
+        let identifier = path
+          .file_name()
+          .expect("failed to extract archive member filename")
+          .to_string_lossy()
+          .into_owned();
+        let mut header = ar::Header::new(identifier.into_bytes(), file.metadata()?.len());
+        header.set_mtime(mtime);
+        header.set_mode(0o100644);
+        builder.append(&header, &mut file)?;
+      }
+      None => builder.append_path(path)?,
+    }
   }
   builder.into_inner()?.flush()?;
   Ok(())
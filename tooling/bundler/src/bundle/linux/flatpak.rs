@@ -0,0 +1,206 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+// A Flatpak bundle of the app, built with `flatpak-builder` from a generated manifest.
+//
+// The bundle directory looks like:
+//
+// bundle/flatpak/
+//   <identifier>.json                      # the flatpak-builder manifest
+//   data/                                  # the deb data folder, installed under /app
+//   build/                                 # the flatpak-builder build directory
+//   repo/                                  # the local repository the app is exported to
+//   <product>_<version>_<arch>.flatpak     # the single-file bundle
+//
+// The app binary, resources, desktop file and icons are generated like the
+// debian package, then installed to the /app prefix of the sandbox.
+
+use super::{super::common, debian};
+use crate::Settings;
+
+use anyhow::Context;
+use serde_json::json;
+
+use std::{
+  fs::remove_dir_all,
+  io::{self, Write},
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+/// The branch the app is exported to and bundled from.
+const BRANCH: &str = "master";
+
+/// Bundles the project.
+/// Returns a vector of PathBuf that shows where the Flatpak was created.
+pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
+  let app_id = settings.bundle_identifier();
+  if app_id.is_empty() {
+    return Err(crate::Error::GenericError(
+      "the Flatpak bundle requires the bundle identifier, it is the Flatpak app ID".into(),
+    ));
+  }
+  check_flatpak_builder(settings)?;
+
+  let arch = match settings.binary_arch() {
+    "x86" => "i386",
+    other => other,
+  };
+  let bundle_name = format!(
+    "{}_{}_{}.flatpak",
+    settings.main_binary_name(),
+    settings.version_string(),
+    arch
+  );
+  common::print_bundling(&bundle_name)?;
+
+  let base_dir = settings.project_out_directory().join("bundle/flatpak");
+  let data_dir = base_dir.join("data");
+  if data_dir.exists() {
+    remove_dir_all(&data_dir).with_context(|| "Failed to remove old Flatpak data")?;
+  }
+  debian::generate_data(settings, &base_dir)
+    .with_context(|| "Failed to build data folders and files")?;
+  let manifest_path =
+    write_manifest(settings, &base_dir, &data_dir).with_context(|| "Failed to write manifest")?;
+
+  let repo_dir = base_dir.join("repo");
+  run_flatpak_tool(
+    Command::new("flatpak-builder")
+      .arg("--force-clean")
+      .arg(format!("--default-branch={}", BRANCH))
+      .arg("--repo")
+      .arg(&repo_dir)
+      .arg(base_dir.join("build"))
+      .arg(&manifest_path),
+    "flatpak-builder",
+  )?;
+
+  let bundle_path = base_dir.join(bundle_name);
+  run_flatpak_tool(
+    Command::new("flatpak")
+      .arg("build-bundle")
+      .arg(&repo_dir)
+      .arg(&bundle_path)
+      .arg(app_id)
+      .arg(BRANCH),
+    "flatpak",
+  )?;
+
+  Ok(vec![bundle_path])
+}
+
+/// Writes the flatpak-builder manifest of the app to `base_dir`, installing the files of `data_dir` to the sandbox.
+/// Returns the path of the manifest.
+fn write_manifest(settings: &Settings, base_dir: &Path, data_dir: &Path) -> crate::Result<PathBuf> {
+  let flatpak = settings.flatpak();
+  let bin_name = settings.main_binary_name();
+  let manifest = json!({
+    "app-id": settings.bundle_identifier(),
+    "runtime": flatpak.runtime,
+    "runtime-version": flatpak.runtime_version,
+    "sdk": flatpak.sdk,
+    "command": bin_name,
+    // the desktop file and icons of the deb data are named after the binary, flatpak requires the app ID
+    "rename-desktop-file": format!("{}.desktop", bin_name),
+    "rename-icon": bin_name,
+    "finish-args": flatpak.finish_args,
+    "modules": [{
+      "name": bin_name,
+      "buildsystem": "simple",
+      "sources": [{
+        "type": "dir",
+        "path": data_dir,
+      }],
+      "build-commands": ["cp -a usr/. /app/"],
+    }],
+  });
+
+  let manifest_path = base_dir.join(format!("{}.json", settings.bundle_identifier()));
+  let mut file = common::create_file(&manifest_path)?;
+  serde_json::to_writer_pretty(&mut file, &manifest)?;
+  file.flush()?;
+  Ok(manifest_path)
+}
+
+/// Checks that `flatpak-builder` is installed, so a missing tool is reported before generating the bundle files.
+fn check_flatpak_builder(settings: &Settings) -> crate::Result<()> {
+  match Command::new("flatpak-builder").arg("--version").output() {
+    Err(e) if e.kind() == io::ErrorKind::NotFound => {
+      let flatpak = settings.flatpak();
+      Err(crate::Error::GenericError(format!(
+        "`flatpak-builder` not found. Install it (e.g. `sudo apt install flatpak-builder` on Debian/Ubuntu) and the runtime with `flatpak install flathub {runtime}//{version} {sdk}//{version}`",
+        runtime = flatpak.runtime,
+        sdk = flatpak.sdk,
+        version = flatpak.runtime_version
+      )))
+    }
+    _ => Ok(()),
+  }
+}
+
+/// Runs a flatpak tool, failing with its stderr if it fails.
+fn run_flatpak_tool(command: &mut Command, tool: &str) -> crate::Result<()> {
+  let output = command.output().map_err(|e| match e.kind() {
+    io::ErrorKind::NotFound => crate::Error::ToolNotFound(tool.into()),
+    _ => e.into(),
+  })?;
+  if !output.status.success() {
+    return Err(crate::Error::GenericError(format!(
+      "`{}` failed: {}",
+      tool,
+      String::from_utf8_lossy(&output.stderr).trim()
+    )));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::write_manifest;
+  use crate::{
+    bundle::settings::test_package_settings, BundleBinary, BundleSettings, SettingsBuilder,
+  };
+  use std::fs::read_to_string;
+
+  #[test]
+  fn manifest_generation() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    let settings = SettingsBuilder::new()
+      .project_out_directory(tmp.path())
+      .package_settings(test_package_settings())
+      .bundle_settings(BundleSettings {
+        identifier: Some("com.tauri.app".into()),
+        ..Default::default()
+      })
+      .binaries(vec![BundleBinary::new("app".into(), true)])
+      .build()
+      .expect("failed to build settings");
+
+    // only writes the manifest, flatpak-builder doesn't need to be installed
+    let data_dir = tmp.path().join("data");
+    let manifest_path =
+      write_manifest(&settings, tmp.path(), &data_dir).expect("failed to write manifest");
+    assert_eq!(manifest_path, tmp.path().join("com.tauri.app.json"));
+
+    let manifest: serde_json::Value =
+      serde_json::from_str(&read_to_string(&manifest_path).expect("failed to read manifest"))
+        .expect("the manifest isn't valid JSON");
+    assert_eq!(manifest["app-id"], "com.tauri.app");
+    assert_eq!(manifest["runtime"], "org.gnome.Platform");
+    assert_eq!(manifest["runtime-version"], "40");
+    assert_eq!(manifest["sdk"], "org.gnome.Sdk");
+    assert_eq!(manifest["command"], "app");
+    assert_eq!(manifest["rename-desktop-file"], "app.desktop");
+    assert_eq!(manifest["rename-icon"], "app");
+    assert!(manifest["finish-args"]
+      .as_array()
+      .expect("finish-args isn't an array")
+      .contains(&"--socket=wayland".into()));
+    assert_eq!(
+      manifest["modules"][0]["sources"][0]["path"],
+      data_dir.display().to_string()
+    );
+  }
+}
@@ -1,3 +1,6 @@
 pub mod appimage;
+pub mod archive;
 pub mod debian;
+pub mod flatpak;
 pub mod rpm;
+pub mod snap;
@@ -2,12 +2,276 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+// An RPM package of the app, built with `rpmbuild` from a generated spec file.
+//
+// The bundle directory looks like this:
+//
+// bundle/rpm/
+//   <name>-<version>-1.<arch>.rpm      # the RPM package
+//   <name>-<version>-1.<arch>/
+//     <name>.spec                      # the generated spec file
+//     data/                            # the deb data folder, copied to the build root
+//     rpmbuild/                        # the rpmbuild top directory
+//
+// The app binary, resources, desktop file and icons are generated like the
+// debian package. The spec file doesn't build anything, its `%install`
+// section copies the data folder and its `%files` section lists each file.
+// The package is signed with `rpm --addsign` when a GPG key is configured.
+
+use super::{super::common, debian};
 use crate::Settings;
 
-use std::path::PathBuf;
+use anyhow::Context;
+use walkdir::WalkDir;
+
+use std::{
+  env::var,
+  fs::{create_dir_all, remove_dir_all, write},
+  io::{self, Write},
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+/// The environment variable holding the passphrase of the RPM signing key.
+const GPG_PASSWORD_ENV: &str = "TAURI_RPM_GPG_PASSWORD";
 
 /// Bundles the project.
-/// Not implemented yet.
-pub fn bundle_project(_settings: &Settings) -> crate::Result<Vec<PathBuf>> {
-  unimplemented!();
+/// Returns a vector of PathBuf that shows where the RPM was created.
+pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
+  let arch = match settings.binary_arch() {
+    "x86" => "i686",
+    "arm" => "armv7hl",
+    other => other,
+  };
+  let package_base_name = format!(
+    "{}-{}-1.{}",
+    settings.main_binary_name(),
+    rpm_version(settings.version_string()),
+    arch
+  );
+  let package_name = format!("{}.rpm", package_base_name);
+  common::print_bundling(&package_name)?;
+
+  let base_dir = settings.project_out_directory().join("bundle/rpm");
+  let package_dir = base_dir.join(&package_base_name);
+  if package_dir.exists() {
+    remove_dir_all(&package_dir)
+      .with_context(|| format!("Failed to remove old {}", package_base_name))?;
+  }
+  let package_path = base_dir.join(&package_name);
+
+  let (data_dir, _) = debian::generate_data(settings, &package_dir)
+    .with_context(|| "Failed to build data folders and files")?;
+  let spec = generate_spec(settings, &data_dir).with_context(|| "Failed to create spec file")?;
+  let spec_path = package_dir.join(format!("{}.spec", settings.main_binary_name()));
+  write(&spec_path, spec).with_context(|| "Failed to write spec file")?;
+
+  let top_dir = package_dir.join("rpmbuild");
+  create_dir_all(&top_dir)?;
+  let output = Command::new("rpmbuild")
+    .arg("-bb")
+    .arg("--target")
+    .arg(arch)
+    .arg("--define")
+    .arg(format!("_topdir {}", top_dir.display()))
+    .arg("--define")
+    .arg(format!("_rpmdir {}", base_dir.display()))
+    .arg("--define")
+    .arg(format!("_rpmfilename {}", package_name))
+    .arg(&spec_path)
+    .output()
+    .map_err(|e| match e.kind() {
+      io::ErrorKind::NotFound => crate::Error::ToolNotFound("rpmbuild".into()),
+      _ => e.into(),
+    })?;
+  if !output.status.success() {
+    return Err(crate::Error::GenericError(format!(
+      "`rpmbuild` failed: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    )));
+  }
+
+  if let Some(key) = &settings.rpm().gpg_key {
+    sign_package(&package_path, key).with_context(|| "Failed to sign package")?;
+  }
+  Ok(vec![package_path])
+}
+
+/// Signs the package with `rpm --addsign`.
+///
+/// The key passphrase is read from the `TAURI_RPM_GPG_PASSWORD` environment variable if it's set,
+/// otherwise it's requested by the GPG agent.
+fn sign_package(package_path: &Path, key: &str) -> crate::Result<()> {
+  common::print_info(&format!("signing the package with the GPG key {}", key))?;
+  let mut command = Command::new("rpm");
+  command
+    .arg("--addsign")
+    .arg("--define")
+    .arg(format!("%_gpg_name {}", key));
+  // the passphrase file is removed when dropped, so it must outlive the `rpm` process
+  let _passphrase_file = match var(GPG_PASSWORD_ENV) {
+    Ok(password) => {
+      let mut file = tempfile::NamedTempFile::new()?;
+      file.write_all(password.as_bytes())?;
+      file.flush()?;
+      command.arg("--define").arg(format!(
+        "_gpg_sign_cmd_extra_args --batch --pinentry-mode loopback --passphrase-file {}",
+        file.path().display()
+      ));
+      Some(file)
+    }
+    Err(_) => None,
+  };
+  command.arg(package_path);
+  debian::run_tool(&mut command, "rpm")
+}
+
+/// Generates the spec file of the package, listing the files of the `data_dir`.
+fn generate_spec(settings: &Settings, data_dir: &Path) -> crate::Result<String> {
+  let mut summary = settings.short_description().trim();
+  if summary.is_empty() {
+    summary = settings.product_name();
+  }
+  // the summary is a single line
+  let summary = summary.lines().next().unwrap_or_default();
+  let description = settings
+    .long_description()
+    .map(str::trim)
+    .filter(|description| !description.is_empty())
+    .unwrap_or(summary);
+
+  let mut spec = String::new();
+  spec.push_str(&format!("Name: {}\n", settings.main_binary_name()));
+  spec.push_str(&format!(
+    "Version: {}\n",
+    rpm_version(settings.version_string())
+  ));
+  spec.push_str("Release: 1\n");
+  spec.push_str(&format!("Summary: {}\n", escape(summary)));
+  spec.push_str("License: Unknown\n");
+  if !settings.homepage_url().is_empty() {
+    spec.push_str(&format!("URL: {}\n", escape(settings.homepage_url())));
+  }
+  if let Some(authors) = settings.authors_comma_separated() {
+    spec.push_str(&format!("Packager: {}\n", escape(&authors)));
+  }
+  // the data folder is already built, rpmbuild must neither strip nor scan it
+  spec.push_str("AutoReqProv: no\n");
+  spec.push_str("%define __os_install_post %{nil}\n");
+  spec.push_str("%define debug_package %{nil}\n");
+  spec.push_str("%define _build_id_links none\n");
+  spec.push_str(&format!("\n%description\n{}\n", escape(description)));
+  spec.push_str("\n%install\n");
+  spec.push_str("mkdir -p %{buildroot}\n");
+  spec.push_str(&format!(
+    "cp -a {} %{{buildroot}}/\n",
+    escape(&shell_quote(&format!("{}/.", data_dir.display())))
+  ));
+  spec.push_str("\n%files\n");
+  // the resource directory belongs to the app, the other directories are shared with the system
+  let resource_dir = Path::new("usr/lib").join(settings.main_binary_name());
+  for entry in WalkDir::new(data_dir)
+    .min_depth(1)
+    .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+  {
+    let entry = entry?;
+    let path = entry.path().strip_prefix(data_dir)?;
+    let file = escape(&format!("\"/{}\"", path.display()));
+    if !entry.file_type().is_dir() {
+      spec.push_str(&format!("{}\n", file));
+    } else if path.starts_with(&resource_dir) {
+      spec.push_str(&format!("%dir {}\n", file));
+    }
+  }
+  Ok(spec)
+}
+
+/// Converts the version to an RPM version, which can't contain `-`.
+///
+/// The `~` separator sorts a pre-release before its release, e.g. `1.0.0~beta.1` before `1.0.0`.
+fn rpm_version(version: &str) -> String {
+  version.replace('-', "~")
+}
+
+/// Escapes the `%` macro character of a spec file value.
+fn escape(value: &str) -> String {
+  value.replace('%', "%%")
+}
+
+/// Quotes a shell argument of the `%install` script.
+fn shell_quote(value: &str) -> String {
+  format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{generate_spec, rpm_version};
+  use crate::{
+    bundle::settings::test_package_settings, BundleBinary, BundleSettings, PackageSettings,
+    SettingsBuilder,
+  };
+  use std::fs::{create_dir_all, write};
+
+  #[test]
+  fn spec_generation() {
+    let settings = SettingsBuilder::new()
+      .project_out_directory("target/release")
+      .package_settings(PackageSettings {
+        version: "1.2.0-beta.1".into(),
+        homepage: Some("https://tauri.studio".into()),
+        ..test_package_settings()
+      })
+      .bundle_settings(BundleSettings {
+        short_description: Some("100% Rust".into()),
+        ..Default::default()
+      })
+      .binaries(vec![BundleBinary::new("app".into(), true)])
+      .build()
+      .expect("failed to build settings");
+
+    let data_dir = tempfile::tempdir().expect("failed to create data dir");
+    let data_dir = data_dir.path();
+    create_dir_all(data_dir.join("usr/bin")).unwrap();
+    write(data_dir.join("usr/bin/app"), "").unwrap();
+    create_dir_all(data_dir.join("usr/lib/app/assets")).unwrap();
+    write(data_dir.join("usr/lib/app/assets/my file.txt"), "").unwrap();
+
+    let spec = generate_spec(&settings, data_dir).expect("failed to generate spec");
+    assert_eq!(
+      spec,
+      format!(
+        r#"Name: app
+Version: 1.2.0~beta.1
+Release: 1
+Summary: 100%% Rust
+License: Unknown
+URL: https://tauri.studio
+AutoReqProv: no
+%define __os_install_post %{{nil}}
+%define debug_package %{{nil}}
+%define _build_id_links none
+
+%description
+100%% Rust
+
+%install
+mkdir -p %{{buildroot}}
+cp -a '{}/.' %{{buildroot}}/
+
+%files
+"/usr/bin/app"
+%dir "/usr/lib/app"
+%dir "/usr/lib/app/assets"
+"/usr/lib/app/assets/my file.txt"
+"#,
+        data_dir.display()
+      )
+    );
+  }
+
+  #[test]
+  fn rpm_versions() {
+    assert_eq!(rpm_version("1.0.0"), "1.0.0");
+    assert_eq!(rpm_version("1.0.0-beta.1"), "1.0.0~beta.1");
+  }
 }
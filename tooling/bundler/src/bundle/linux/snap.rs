@@ -0,0 +1,311 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+// A Snap bundle of the app, built with `snapcraft` from a generated `snapcraft.yaml`.
+//
+// The bundle directory is the snapcraft project:
+//
+// bundle/snap/
+//   snapcraft.yaml                     # the generated snapcraft project file
+//   data/                              # the deb data folder, dumped into the snap
+//   <name>_<version>_<arch>.snap       # the snap package
+//
+// The app binary, resources, desktop file and icons are generated like the
+// debian package. The paths of the project file are relative to the bundle
+// directory, so snapcraft can build it in its build VM.
+
+use super::{super::common, debian};
+use crate::Settings;
+
+use anyhow::Context;
+
+use std::{
+  fs::{remove_dir_all, write},
+  io,
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+/// The base snap, i.e. the Ubuntu release the app runs on.
+const BASE: &str = "core20";
+/// The snapcraft extension providing GTK and the desktop integration of the base.
+const EXTENSION: &str = "gnome-3-38";
+/// The WebKitGTK package of the base, staged into the snap since the extension doesn't provide it.
+const WEBKIT_PACKAGE: &str = "libwebkit2gtk-4.0-37";
+/// The maximum length of the snap summary.
+const MAX_SUMMARY_LEN: usize = 78;
+const CONFINEMENTS: &[&str] = &["strict", "classic", "devmode"];
+const GRADES: &[&str] = &["stable", "devel"];
+
+/// Bundles the project.
+/// Returns a vector of PathBuf that shows where the Snap was created.
+pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
+  check_snapcraft()?;
+
+  let arch = match settings.binary_arch() {
+    "x86" => "i386",
+    "x86_64" => "amd64",
+    "aarch64" => "arm64",
+    "arm" => "armhf",
+    other => other,
+  };
+  let bundle_name = format!(
+    "{}_{}_{}.snap",
+    snap_name(settings.product_name()),
+    settings.version_string(),
+    arch
+  );
+  common::print_bundling(&bundle_name)?;
+
+  let base_dir = settings.project_out_directory().join("bundle/snap");
+  let data_dir = base_dir.join("data");
+  if data_dir.exists() {
+    remove_dir_all(&data_dir).with_context(|| "Failed to remove old Snap data")?;
+  }
+  let (_, icons) = debian::generate_data(settings, &base_dir)
+    .with_context(|| "Failed to build data folders and files")?;
+  // the largest icon is the snap icon
+  let icon = icons
+    .iter()
+    .last()
+    .and_then(|icon| icon.path.strip_prefix(&base_dir).ok());
+  let snapcraft_yaml = generate_snapcraft_yaml(settings, icon)?;
+  write(base_dir.join("snapcraft.yaml"), snapcraft_yaml)
+    .with_context(|| "Failed to write snapcraft.yaml")?;
+
+  let bundle_path = base_dir.join(bundle_name);
+  let output = Command::new("snapcraft")
+    .arg("snap")
+    .arg("--output")
+    .arg(&bundle_path)
+    .current_dir(&base_dir)
+    .output()
+    .map_err(|e| match e.kind() {
+      io::ErrorKind::NotFound => crate::Error::ToolNotFound("snapcraft".into()),
+      _ => e.into(),
+    })?;
+  if !output.status.success() {
+    return Err(crate::Error::GenericError(format!(
+      "`snapcraft` failed: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    )));
+  }
+
+  Ok(vec![bundle_path])
+}
+
+/// Generates the `snapcraft.yaml` of the app, with `icon` relative to the bundle directory.
+///
+/// The output only depends on the settings, the strings are written as double-quoted scalars.
+fn generate_snapcraft_yaml(settings: &Settings, icon: Option<&Path>) -> crate::Result<String> {
+  let snap = settings.snap();
+  if !CONFINEMENTS.contains(&snap.confinement.as_str()) {
+    return Err(crate::Error::GenericError(format!(
+      "invalid snap confinement `{}`, expected one of {}",
+      snap.confinement,
+      CONFINEMENTS.join(", ")
+    )));
+  }
+  if !GRADES.contains(&snap.grade.as_str()) {
+    return Err(crate::Error::GenericError(format!(
+      "invalid snap grade `{}`, expected one of {}",
+      snap.grade,
+      GRADES.join(", ")
+    )));
+  }
+
+  let name = snap_name(settings.product_name());
+  if name.is_empty() {
+    return Err(crate::Error::GenericError(format!(
+      "the product name `{}` can't be used as a snap name, it requires a letter or a digit",
+      settings.product_name()
+    )));
+  }
+  let bin_name = settings.main_binary_name();
+  let mut summary = settings.short_description().trim();
+  if summary.is_empty() {
+    summary = settings.product_name();
+  }
+  let summary: String = summary.chars().take(MAX_SUMMARY_LEN).collect();
+  let description = settings
+    .long_description()
+    .map(str::trim)
+    .filter(|description| !description.is_empty())
+    .unwrap_or(&summary);
+
+  let mut stage_packages = vec![WEBKIT_PACKAGE.to_string()];
+  for package in settings.deb().depends.iter().flatten() {
+    if !stage_packages.contains(package) {
+      stage_packages.push(package.clone());
+    }
+  }
+
+  let mut yaml = String::new();
+  yaml.push_str(&format!("name: {}\n", quote(&name)));
+  yaml.push_str(&format!("base: {}\n", BASE));
+  yaml.push_str(&format!("version: {}\n", quote(settings.version_string())));
+  yaml.push_str(&format!("summary: {}\n", quote(&summary)));
+  yaml.push_str(&format!("description: {}\n", quote(description)));
+  yaml.push_str(&format!("grade: {}\n", snap.grade));
+  yaml.push_str(&format!("confinement: {}\n", snap.confinement));
+  if let Some(icon) = icon {
+    yaml.push_str(&format!("icon: {}\n", quote(&icon.display().to_string())));
+  }
+  yaml.push_str("\napps:\n");
+  yaml.push_str(&format!("  {}:\n", name));
+  yaml.push_str(&format!(
+    "    command: {}\n",
+    quote(&format!("usr/bin/{}", bin_name))
+  ));
+  yaml.push_str(&format!(
+    "    desktop: {}\n",
+    quote(&format!("usr/share/applications/{}.desktop", bin_name))
+  ));
+  yaml.push_str(&format!("    extensions: [{}]\n", EXTENSION));
+  if !snap.plugs.is_empty() {
+    yaml.push_str("    plugs:\n");
+    for plug in &snap.plugs {
+      yaml.push_str(&format!("      - {}\n", quote(plug)));
+    }
+  }
+  yaml.push_str("\nparts:\n");
+  yaml.push_str(&format!("  {}:\n", name));
+  yaml.push_str("    plugin: dump\n");
+  yaml.push_str("    source: data\n");
+  yaml.push_str("    stage-packages:\n");
+  for package in &stage_packages {
+    yaml.push_str(&format!("      - {}\n", quote(package)));
+  }
+  Ok(yaml)
+}
+
+/// Converts the product name to a snap name: lowercase letters, digits and single hyphens.
+fn snap_name(product_name: &str) -> String {
+  let mut name = String::new();
+  for c in product_name.chars() {
+    if c.is_ascii_alphanumeric() {
+      name.push(c.to_ascii_lowercase());
+    } else if !name.is_empty() && !name.ends_with('-') {
+      name.push('-');
+    }
+  }
+  name.trim_end_matches('-').to_string()
+}
+
+/// Quotes a YAML string scalar. JSON strings are valid YAML double-quoted scalars.
+fn quote(value: &str) -> String {
+  serde_json::Value::String(value.into()).to_string()
+}
+
+/// Checks that `snapcraft` is installed, so a missing tool is reported before generating the bundle files.
+fn check_snapcraft() -> crate::Result<()> {
+  match Command::new("snapcraft").arg("--version").output() {
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Err(crate::Error::GenericError(
+      "`snapcraft` not found. Install it with `sudo snap install snapcraft --classic`".into(),
+    )),
+    _ => Ok(()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{generate_snapcraft_yaml, snap_name};
+  use crate::{
+    bundle::settings::test_package_settings, BundleBinary, BundleSettings, DebianSettings,
+    PackageSettings, SettingsBuilder, SnapSettings,
+  };
+  use std::path::Path;
+
+  #[test]
+  fn snapcraft_yaml_generation() {
+    let settings = SettingsBuilder::new()
+      .project_out_directory("target/release")
+      .package_settings(PackageSettings {
+        product_name: "My App".into(),
+        ..test_package_settings()
+      })
+      .bundle_settings(BundleSettings {
+        short_description: Some("An \"app\"".into()),
+        deb: DebianSettings {
+          depends: Some(vec![
+            "libwebkit2gtk-4.0-37".into(),
+            "libappindicator3-1".into(),
+          ]),
+          ..Default::default()
+        },
+        snap: SnapSettings {
+          grade: "devel".into(),
+          ..Default::default()
+        },
+        ..Default::default()
+      })
+      .binaries(vec![BundleBinary::new("my-app".into(), true)])
+      .build()
+      .expect("failed to build settings");
+
+    // only generates the project file, snapcraft doesn't need to be installed
+    let yaml = generate_snapcraft_yaml(
+      &settings,
+      Some(Path::new(
+        "data/usr/share/icons/hicolor/128x128/apps/my-app.png",
+      )),
+    )
+    .expect("failed to generate snapcraft.yaml");
+    assert_eq!(
+      yaml,
+      r#"name: "my-app"
+base: core20
+version: "1.0.0"
+summary: "An \"app\""
+description: "An \"app\""
+grade: devel
+confinement: strict
+icon: "data/usr/share/icons/hicolor/128x128/apps/my-app.png"
+
+apps:
+  my-app:
+    command: "usr/bin/my-app"
+    desktop: "usr/share/applications/my-app.desktop"
+    extensions: [gnome-3-38]
+    plugs:
+      - "desktop"
+      - "desktop-legacy"
+      - "wayland"
+      - "x11"
+      - "opengl"
+      - "network"
+
+parts:
+  my-app:
+    plugin: dump
+    source: data
+    stage-packages:
+      - "libwebkit2gtk-4.0-37"
+      - "libappindicator3-1"
+"#
+    );
+
+    let settings = SettingsBuilder::new()
+      .project_out_directory("target/release")
+      .package_settings(test_package_settings())
+      .bundle_settings(BundleSettings {
+        snap: SnapSettings {
+          confinement: "unconfined".into(),
+          ..Default::default()
+        },
+        ..Default::default()
+      })
+      .binaries(vec![BundleBinary::new("app".into(), true)])
+      .build()
+      .expect("failed to build settings");
+    assert!(generate_snapcraft_yaml(&settings, None).is_err());
+  }
+
+  #[test]
+  fn snap_names() {
+    assert_eq!(snap_name("My App"), "my-app");
+    assert_eq!(snap_name("  Tauri -- App 2 "), "tauri-app-2");
+    assert_eq!(snap_name("!!!"), "");
+  }
+}
@@ -24,7 +24,7 @@
 use super::{
   super::common,
   icon::create_icns_file,
-  sign::{notarize, notarize_auth_args, setup_keychain_if_needed, sign},
+  sign::{notarize_if_enabled, setup_keychain_if_needed, sign},
 };
 use crate::Settings;
 
@@ -92,16 +92,9 @@ pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
     setup_keychain_if_needed()?;
     // sign application
     sign(app_bundle_path.clone(), identity, &settings, true)?;
-    // notarization is required for distribution
-    match notarize_auth_args() {
-      Ok(args) => {
-        notarize(app_bundle_path.clone(), args, settings)?;
-      }
-      Err(e) => {
-        common::print_info(format!("skipping app notarization, {}", e.to_string()).as_str())?;
-      }
-    }
   }
+  // notarization is required for distribution
+  notarize_if_enabled(app_bundle_path.clone(), settings)?;
 
   Ok(vec![app_bundle_path])
 }
@@ -183,6 +176,21 @@ fn create_info_plist(
   let build_number = chrono::Utc::now().format("%Y%m%d.%H%M%S");
   let file = &mut common::create_file(&bundle_dir.join("Info.plist"))?;
   let use_bootstrapper = settings.macos().use_bootstrapper.unwrap_or_default();
+  // the keys written by the bundler, which the `info_plist` settings can't override
+  let mut generated_keys = vec![
+    "CFBundleDevelopmentRegion",
+    "CFBundleDisplayName",
+    "CFBundleExecutable",
+    "CFBundleIdentifier",
+    "CFBundleInfoDictionaryVersion",
+    "CFBundleName",
+    "CFBundlePackageType",
+    "CFBundleShortVersionString",
+    "CFBundleVersion",
+    "CSResourcesFileMapped",
+    "LSRequiresCarbon",
+    "NSHighResolutionCapable",
+  ];
   write!(
     file,
     "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
@@ -211,6 +219,7 @@ fn create_info_plist(
     }
   )?;
   if let Some(path) = bundle_icon_file {
+    generated_keys.push("CFBundleIconFile");
     write!(
       file,
       "  <key>CFBundleIconFile</key>\n  <string>{}</string>\n",
@@ -248,6 +257,7 @@ fn create_info_plist(
   )?;
   write!(file, "  <key>CSResourcesFileMapped</key>\n  <true/>\n")?;
   if let Some(category) = settings.app_category() {
+    generated_keys.push("LSApplicationCategoryType");
     write!(
       file,
       "  <key>LSApplicationCategoryType</key>\n  \
@@ -256,6 +266,7 @@ fn create_info_plist(
     )?;
   }
   if let Some(version) = &settings.macos().minimum_system_version {
+    generated_keys.push("LSMinimumSystemVersion");
     write!(
       file,
       "  <key>LSMinimumSystemVersion</key>\n  \
@@ -266,6 +277,7 @@ fn create_info_plist(
   write!(file, "  <key>LSRequiresCarbon</key>\n  <true/>\n")?;
   write!(file, "  <key>NSHighResolutionCapable</key>\n  <true/>\n")?;
   if let Some(copyright) = settings.copyright_string() {
+    generated_keys.push("NSHumanReadableCopyright");
     write!(
       file,
       "  <key>NSHumanReadableCopyright</key>\n  \
@@ -275,6 +287,7 @@ fn create_info_plist(
   }
 
   if let Some(exception_domain) = &settings.macos().exception_domain {
+    generated_keys.push("NSAppTransportSecurity");
     write!(
       file,
       "  <key>NSAppTransportSecurity</key>\n  \
@@ -289,16 +302,81 @@ fn create_info_plist(
                   <true/>\n  \
               </dict>\n  \
           </dict>\n  \
-      </dict>",
+      </dict>\n",
       exception_domain
     )?;
   }
 
+  let mut info_plist: Vec<_> = settings.macos().info_plist.iter().collect();
+  info_plist.sort_by(|(a, _), (b, _)| a.cmp(b));
+  for (key, value) in info_plist {
+    if generated_keys.contains(&key.as_str()) {
+      return Err(crate::Error::GenericError(format!(
+        "the Info.plist key {} is already set by the bundler",
+        key
+      )));
+    }
+    write!(file, "  <key>{}</key>\n  ", escape_xml(key))?;
+    write_plist_value(file, value, 1)?;
+  }
+
   write!(file, "</dict>\n</plist>\n")?;
   file.flush()?;
   Ok(())
 }
 
+/// Writes a JSON value as its plist equivalent, indented by `depth` levels.
+fn write_plist_value<W: Write>(
+  file: &mut W,
+  value: &serde_json::Value,
+  depth: usize,
+) -> crate::Result<()> {
+  let indent = "  ".repeat(depth);
+  match value {
+    serde_json::Value::Null => {
+      return Err(crate::Error::GenericError(
+        "null isn't a valid Info.plist value".into(),
+      ))
+    }
+    serde_json::Value::Bool(value) => writeln!(file, "<{}/>", value)?,
+    serde_json::Value::Number(number) if number.is_f64() => {
+      writeln!(file, "<real>{}</real>", number)?
+    }
+    serde_json::Value::Number(number) => writeln!(file, "<integer>{}</integer>", number)?,
+    serde_json::Value::String(value) => writeln!(file, "<string>{}</string>", escape_xml(value))?,
+    serde_json::Value::Array(values) => {
+      writeln!(file, "<array>")?;
+      for value in values {
+        write!(file, "{}  ", indent)?;
+        write_plist_value(file, value, depth + 1)?;
+      }
+      writeln!(file, "{}</array>", indent)?;
+    }
+    serde_json::Value::Object(entries) => {
+      writeln!(file, "<dict>")?;
+      for (key, value) in entries {
+        write!(
+          file,
+          "{}  <key>{}</key>\n{}  ",
+          indent,
+          escape_xml(key),
+          indent
+        )?;
+        write_plist_value(file, value, depth + 1)?;
+      }
+      writeln!(file, "{}</dict>", indent)?;
+    }
+  }
+  Ok(())
+}
+
+fn escape_xml(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+}
+
 // Copies the framework under `{src_dir}/{framework}.framework` to `{dest_dir}/{framework}.framework`.
 fn copy_framework_from(dest_dir: &Path, framework: &str, src_dir: &Path) -> crate::Result<bool> {
   let src_name = format!("{}.framework", framework);
@@ -154,5 +154,6 @@ pub fn bundle_project(settings: &Settings, bundles: &[Bundle]) -> crate::Result<
   if let Some(identity) = &settings.macos().signing_identity {
     super::sign::sign(dmg_path.clone(), identity, &settings, false)?;
   }
+  super::sign::notarize_if_enabled(dmg_path.clone(), settings)?;
   Ok(vec![dmg_path])
 }
@@ -6,7 +6,6 @@ use std::{
 };
 
 use crate::{bundle::common, Settings};
-use regex::Regex;
 
 // Import certificate from ENV variables.
 // APPLE_CERTIFICATE is the p12 certificate base64 encoded.
@@ -184,6 +183,15 @@ pub fn sign(
   common::print_info(format!(r#"signing with identity "{}""#, identity).as_str())?;
   let mut args = vec!["--force", "-s", identity];
   if let Some(entitlements_path) = &settings.macos().entitlements {
+    if !std::path::Path::new(entitlements_path).exists() {
+      return Err(
+        anyhow::anyhow!(format!(
+          "entitlements file not found at {}",
+          entitlements_path
+        ))
+        .into(),
+      );
+    }
     common::print_info(format!("using entitlements file at {}", entitlements_path).as_str())?;
     args.push("--entitlements");
     args.push(entitlements_path);
@@ -198,102 +206,126 @@ pub fn sign(
     args.push("--deep");
   }
 
-  let status = Command::new("codesign")
+  let output = Command::new("codesign")
     .args(args)
     .arg(path_to_sign.to_string_lossy().to_string())
-    .status()?;
+    .stdout(Stdio::inherit())
+    .output()?;
 
-  if !status.success() {
-    return Err(anyhow::anyhow!("failed to sign app").into());
+  if !output.status.success() {
+    return Err(
+      anyhow::anyhow!(format!(
+        "failed to sign {}. {}",
+        path_to_sign.display(),
+        String::from_utf8_lossy(&output.stderr).trim()
+      ))
+      .into(),
+    );
   }
 
   Ok(())
 }
 
+/// Notarizes and staples the given app or DMG when `notarize` is enabled on the macOS settings.
+pub fn notarize_if_enabled(path: PathBuf, settings: &Settings) -> crate::Result<()> {
+  if !settings.macos().notarize {
+    return Ok(());
+  }
+  if settings.macos().signing_identity.is_none() {
+    return Err(anyhow::anyhow!("notarization requires a signing identity").into());
+  }
+  notarize(path, notarize_auth_args()?, settings)
+}
+
 pub fn notarize(
-  app_bundle_path: PathBuf,
+  path_to_notarize: PathBuf,
   auth_args: Vec<String>,
   settings: &Settings,
 ) -> crate::Result<()> {
-  let identifier = settings.bundle_identifier();
-
-  let bundle_stem = app_bundle_path
+  let file_stem = path_to_notarize
     .file_stem()
     .expect("failed to get bundle filename");
 
   let tmp_dir = tempfile::tempdir()?;
-  let zip_path = tmp_dir
-    .path()
-    .join(format!("{}.zip", bundle_stem.to_string_lossy()));
-  let zip_args = vec![
-    "-c",
-    "-k",
-    "--keepParent",
-    "--sequesterRsrc",
-    app_bundle_path
-      .to_str()
-      .expect("failed to convert bundle_path to string"),
-    zip_path
-      .to_str()
-      .expect("failed to convert zip_path to string"),
-  ];
-
-  // use ditto to create a PKZip almost identical to Finder
-  // this remove almost 99% of false alarm in notarization
-  let zip_app = Command::new("ditto")
-    .args(zip_args)
-    .stderr(Stdio::inherit())
-    .status()?;
-
-  if !zip_app.success() {
-    return Err(anyhow::anyhow!("failed to zip app with ditto").into());
-  }
+  // the notary service only accepts zip archives, disk images and flat packages
+  let submission_path = if path_to_notarize.is_dir() {
+    let zip_path = tmp_dir
+      .path()
+      .join(format!("{}.zip", file_stem.to_string_lossy()));
+    let zip_args = vec![
+      "-c",
+      "-k",
+      "--keepParent",
+      "--sequesterRsrc",
+      path_to_notarize
+        .to_str()
+        .expect("failed to convert bundle_path to string"),
+      zip_path
+        .to_str()
+        .expect("failed to convert zip_path to string"),
+    ];
+
+    // use ditto to create a PKZip almost identical to Finder
+    // this remove almost 99% of false alarm in notarization
+    let zip_app = Command::new("ditto")
+      .args(zip_args)
+      .stderr(Stdio::inherit())
+      .status()?;
+
+    if !zip_app.success() {
+      return Err(anyhow::anyhow!("failed to zip app with ditto").into());
+    }
 
-  // sign the zip file
-  if let Some(identity) = &settings.macos().signing_identity {
-    sign(zip_path.clone(), identity, &settings, false)?;
+    // sign the zip file
+    if let Some(identity) = &settings.macos().signing_identity {
+      sign(zip_path.clone(), identity, &settings, false)?;
+    };
+    zip_path
+  } else {
+    path_to_notarize.clone()
   };
 
   let notarize_args = vec![
-    "altool",
-    "--notarize-app",
-    "-f",
-    zip_path
+    "notarytool",
+    "submit",
+    submission_path
       .to_str()
-      .expect("failed to convert zip_path to string"),
-    "--primary-bundle-id",
-    identifier,
+      .expect("failed to convert submission_path to string"),
+    "--output-format",
+    "json",
   ];
-  common::print_info("notarizing app")?;
+  common::print_info(format!("notarizing {}", path_to_notarize.display()).as_str())?;
   let output = Command::new("xcrun")
     .args(notarize_args)
     .args(auth_args.clone())
-    .stderr(Stdio::inherit())
     .output()?;
 
   if !output.status.success() {
     return Err(
       anyhow::anyhow!(format!(
-        "failed to upload app to Apple's notarization servers. {}",
-        std::str::from_utf8(&output.stdout)?
+        "failed to upload {} to Apple's notary service. {}",
+        path_to_notarize.display(),
+        String::from_utf8_lossy(&output.stderr).trim()
       ))
       .into(),
     );
   }
 
   let stdout = std::str::from_utf8(&output.stdout)?;
-  if let Some(uuid) = Regex::new(r"\nRequestUUID = (.+?)\n")?
-    .captures_iter(stdout)
-    .next()
-  {
+  let submission: serde_json::Value = serde_json::from_str(stdout).map_err(|_| {
+    anyhow::anyhow!(format!(
+      "failed to parse the submission id from the notarytool output. {}",
+      stdout
+    ))
+  })?;
+  if let Some(id) = submission["id"].as_str() {
     common::print_info("notarization started; waiting for Apple response...")?;
-    let uuid = uuid[1].to_string();
-    get_notarization_status(uuid, auth_args)?;
-    staple_app(app_bundle_path.clone())?;
+    get_notarization_status(id, &auth_args)?;
+    staple(path_to_notarize)?;
   } else {
     return Err(
       anyhow::anyhow!(format!(
-        "failed to parse RequestUUID from upload output. {}",
+        "failed to parse the submission id from the notarytool output. {}",
         stdout
       ))
       .into(),
@@ -303,26 +335,27 @@ pub fn notarize(
   Ok(())
 }
 
-fn staple_app(mut app_bundle_path: PathBuf) -> crate::Result<()> {
-  let app_bundle_path_clone = app_bundle_path.clone();
-  let filename = app_bundle_path_clone
+fn staple(mut path_to_staple: PathBuf) -> crate::Result<()> {
+  let path_to_staple_clone = path_to_staple.clone();
+  let filename = path_to_staple_clone
     .file_name()
     .expect("failed to get bundle filename")
     .to_str()
     .expect("failed to convert bundle filename to string");
 
-  app_bundle_path.pop();
+  path_to_staple.pop();
 
   let output = Command::new("xcrun")
     .args(vec!["stapler", "staple", "-v", filename])
-    .current_dir(app_bundle_path)
+    .current_dir(path_to_staple)
     .stderr(Stdio::inherit())
     .output()?;
 
   if !output.status.success() {
     Err(
       anyhow::anyhow!(format!(
-        "failed to staple app. {}",
+        "failed to staple {}. {}",
+        filename,
         std::str::from_utf8(&output.stdout)?
       ))
       .into(),
@@ -332,35 +365,39 @@ fn staple_app(mut app_bundle_path: PathBuf) -> crate::Result<()> {
   }
 }
 
-fn get_notarization_status(uuid: String, auth_args: Vec<String>) -> crate::Result<()> {
-  std::thread::sleep(std::time::Duration::from_secs(10));
-  let output = Command::new("xcrun")
-    .args(vec!["altool", "--notarization-info", &uuid])
-    .args(auth_args.clone())
-    .stderr(Stdio::inherit())
-    .output()?;
-
-  if !output.status.success() {
-    get_notarization_status(uuid, auth_args)
-  } else {
-    let stdout = std::str::from_utf8(&output.stdout)?;
-    if let Some(status) = Regex::new(r"\n *Status: (.+?)\n")?
-      .captures_iter(stdout)
-      .next()
-    {
-      let status = status[1].to_string();
-      if status == "in progress" {
-        get_notarization_status(uuid, auth_args)
-      } else if status == "invalid" {
-        Err(
+/// Polls the notary service until the submission is processed.
+fn get_notarization_status(id: &str, auth_args: &[String]) -> crate::Result<()> {
+  loop {
+    std::thread::sleep(std::time::Duration::from_secs(10));
+    let output = Command::new("xcrun")
+      .args(vec!["notarytool", "info", id, "--output-format", "json"])
+      .args(auth_args)
+      .stderr(Stdio::inherit())
+      .output()?;
+
+    // the submission may not be queryable right after the upload
+    if !output.status.success() {
+      continue;
+    }
+    let info: serde_json::Value = match serde_json::from_slice(&output.stdout) {
+      Ok(info) => info,
+      Err(_) => continue,
+    };
+    match info["status"].as_str() {
+      Some("In Progress") | None => continue,
+      Some("Accepted") => return Ok(()),
+      Some(status @ "Invalid") | Some(status @ "Rejected") => {
+        return Err(
           anyhow::anyhow!(format!(
-            "Apple failed to notarize your app. {}",
-            std::str::from_utf8(&output.stdout)?
+            "Apple failed to notarize your app (status: {}). Notary log:\n{}",
+            status,
+            notarization_log(id, auth_args)
           ))
           .into(),
         )
-      } else if status != "success" {
-        Err(
+      }
+      Some(status) => {
+        return Err(
           anyhow::anyhow!(format!(
             "Unknown notarize status {}. {}",
             status,
@@ -368,21 +405,34 @@ fn get_notarization_status(uuid: String, auth_args: Vec<String>) -> crate::Resul
           ))
           .into(),
         )
-      } else {
-        Ok(())
       }
-    } else {
-      get_notarization_status(uuid, auth_args)
     }
   }
 }
 
+/// Fetches the notary log of a submission, describing why it was rejected.
+fn notarization_log(id: &str, auth_args: &[String]) -> String {
+  match Command::new("xcrun")
+    .args(vec!["notarytool", "log", id])
+    .args(auth_args)
+    .output()
+  {
+    Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).into_owned(),
+    Ok(output) => format!(
+      "failed to fetch the notary log. {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ),
+    Err(e) => format!("failed to fetch the notary log. {}", e),
+  }
+}
+
 pub fn notarize_auth_args() -> crate::Result<Vec<String>> {
   match (
     std::env::var_os("APPLE_ID"),
     std::env::var_os("APPLE_PASSWORD"),
+    std::env::var_os("APPLE_TEAM_ID"),
   ) {
-    (Some(apple_id), Some(apple_password)) => {
+    (Some(apple_id), Some(apple_password), Some(team_id)) => {
       let apple_id = apple_id
         .to_str()
         .expect("failed to convert APPLE_ID to string")
@@ -391,11 +441,17 @@ pub fn notarize_auth_args() -> crate::Result<Vec<String>> {
         .to_str()
         .expect("failed to convert APPLE_PASSWORD to string")
         .to_string();
+      let team_id = team_id
+        .to_str()
+        .expect("failed to convert APPLE_TEAM_ID to string")
+        .to_string();
       Ok(vec![
-        "-u".to_string(),
+        "--apple-id".to_string(),
         apple_id,
-        "-p".to_string(),
+        "--password".to_string(),
         apple_password,
+        "--team-id".to_string(),
+        team_id,
       ])
     }
     _ => {
@@ -403,9 +459,24 @@ pub fn notarize_auth_args() -> crate::Result<Vec<String>> {
         (Some(api_key), Some(api_issuer)) => {
           let api_key = api_key.to_str().expect("failed to convert APPLE_API_KEY to string").to_string();
           let api_issuer = api_issuer.to_str().expect("failed to convert APPLE_API_ISSUER to string").to_string();
-          Ok(vec!["--apiKey".to_string(), api_key, "--apiIssuer".to_string(), api_issuer])
+          // notarytool needs the path to the key, defaulting to the location used by altool
+          let api_key_path = match std::env::var_os("APPLE_API_KEY_PATH") {
+            Some(path) => PathBuf::from(path),
+            None => dirs_next::home_dir()
+              .expect("failed to get the home directory")
+              .join(".private_keys")
+              .join(format!("AuthKey_{}.p8", api_key)),
+          };
+          Ok(vec![
+            "--key".to_string(),
+            api_key_path.to_string_lossy().to_string(),
+            "--key-id".to_string(),
+            api_key,
+            "--issuer".to_string(),
+            api_issuer,
+          ])
         },
-        _ => Err(anyhow::anyhow!("no APPLE_ID & APPLE_PASSWORD or APPLE_API_KEY & APPLE_API_ISSUER environment variables found").into())
+        _ => Err(anyhow::anyhow!("no APPLE_ID, APPLE_PASSWORD & APPLE_TEAM_ID or APPLE_API_KEY & APPLE_API_ISSUER environment variables found").into())
       }
     }
   }
@@ -7,7 +7,7 @@ use crate::bundle::{common, platform::target_triple};
 
 use std::{
   collections::HashMap,
-  path::{Path, PathBuf},
+  path::{Component, Path, PathBuf},
 };
 
 /// The type of the package we're bundling.
@@ -20,6 +20,8 @@ pub enum PackageType {
   IosBundle,
   /// The Windows bundle (.msi).
   WindowsMsi,
+  /// The Windows NSIS installer (.exe).
+  Nsis,
   /// The Linux Debian package bundle (.deb).
   Deb,
   /// The Linux RPM bundle (.rpm).
@@ -28,23 +30,36 @@ pub enum PackageType {
   AppImage,
   /// The macOS DMG bundle (.dmg).
   Dmg,
+  /// The Linux gzip compressed tarball (.tar.gz).
+  TarGz,
+  /// The Linux xz compressed tarball (.tar.xz).
+  TarXz,
+  /// The Linux Flatpak bundle (.flatpak).
+  Flatpak,
+  /// The Linux Snap bundle (.snap).
+  Snap,
   /// The Updater bundle.
   Updater,
 }
 
 impl PackageType {
   /// Maps a short name to a PackageType.
-  /// Possible values are "deb", "ios", "msi", "app", "rpm", "appimage", "dmg", "updater".
+  /// Possible values are "deb", "ios", "msi", "nsis", "app", "rpm", "appimage", "dmg", "targz", "tarxz", "flatpak", "snap", "updater".
   pub fn from_short_name(name: &str) -> Option<PackageType> {
     // Other types we may eventually want to support: apk.
     match name {
       "deb" => Some(PackageType::Deb),
       "ios" => Some(PackageType::IosBundle),
       "msi" => Some(PackageType::WindowsMsi),
+      "nsis" => Some(PackageType::Nsis),
       "app" => Some(PackageType::MacOsBundle),
       "rpm" => Some(PackageType::Rpm),
       "appimage" => Some(PackageType::AppImage),
       "dmg" => Some(PackageType::Dmg),
+      "targz" => Some(PackageType::TarGz),
+      "tarxz" => Some(PackageType::TarXz),
+      "flatpak" => Some(PackageType::Flatpak),
+      "snap" => Some(PackageType::Snap),
       "updater" => Some(PackageType::Updater),
       _ => None,
     }
@@ -57,27 +72,66 @@ impl PackageType {
       PackageType::Deb => "deb",
       PackageType::IosBundle => "ios",
       PackageType::WindowsMsi => "msi",
+      PackageType::Nsis => "nsis",
       PackageType::MacOsBundle => "app",
       PackageType::Rpm => "rpm",
       PackageType::AppImage => "appimage",
       PackageType::Dmg => "dmg",
+      PackageType::TarGz => "targz",
+      PackageType::TarXz => "tarxz",
+      PackageType::Flatpak => "flatpak",
+      PackageType::Snap => "snap",
       PackageType::Updater => "updater",
     }
   }
 
-  /// Gets the list of the possible package types.
+  /// Gets a short description of this PackageType.
+  #[allow(clippy::trivially_copy_pass_by_ref)]
+  pub fn description(&self) -> &'static str {
+    match *self {
+      PackageType::Deb => "Debian package (.deb)",
+      PackageType::IosBundle => "iOS app bundle",
+      PackageType::WindowsMsi => "Windows installer (.msi)",
+      PackageType::Nsis => "NSIS Windows installer (.exe)",
+      PackageType::MacOsBundle => "macOS application bundle (.app)",
+      PackageType::Rpm => "RPM package (.rpm)",
+      PackageType::AppImage => "AppImage (.AppImage)",
+      PackageType::Dmg => "macOS disk image (.dmg)",
+      PackageType::TarGz => "gzip compressed tarball (.tar.gz)",
+      PackageType::TarXz => "xz compressed tarball (.tar.xz)",
+      PackageType::Flatpak => "Flatpak bundle (.flatpak)",
+      PackageType::Snap => "Snap package (.snap)",
+      PackageType::Updater => "updater archives of the other bundles",
+    }
+  }
+
+  /// Gets the list of the package types that can be bundled on the host platform.
   pub fn all() -> &'static [PackageType] {
     ALL_PACKAGE_TYPES
   }
+
+  /// Gets the package types bundled on the host platform when none is requested, without the updater.
+  pub fn platform_defaults() -> crate::Result<Vec<PackageType>> {
+    match std::env::consts::OS {
+      "macos" => Ok(vec![PackageType::MacOsBundle, PackageType::Dmg]),
+      "ios" => Ok(vec![PackageType::IosBundle]),
+      "linux" => Ok(vec![PackageType::Deb, PackageType::AppImage]),
+      "windows" => Ok(vec![PackageType::WindowsMsi]),
+      os => Err(crate::Error::GenericError(format!(
+        "Native {} bundles not yet supported.",
+        os
+      ))),
+    }
+  }
 }
 
 const ALL_PACKAGE_TYPES: &[PackageType] = &[
   #[cfg(target_os = "linux")]
   PackageType::Deb,
-  #[cfg(target_os = "macos")]
-  PackageType::IosBundle,
   #[cfg(target_os = "windows")]
   PackageType::WindowsMsi,
+  #[cfg(target_os = "windows")]
+  PackageType::Nsis,
   #[cfg(target_os = "macos")]
   PackageType::MacOsBundle,
   #[cfg(target_os = "linux")]
@@ -86,6 +140,14 @@ const ALL_PACKAGE_TYPES: &[PackageType] = &[
   PackageType::Dmg,
   #[cfg(target_os = "linux")]
   PackageType::AppImage,
+  #[cfg(target_os = "linux")]
+  PackageType::TarGz,
+  #[cfg(target_os = "linux")]
+  PackageType::TarXz,
+  #[cfg(target_os = "linux")]
+  PackageType::Flatpak,
+  #[cfg(target_os = "linux")]
+  PackageType::Snap,
   PackageType::Updater,
 ];
 
@@ -106,6 +168,35 @@ pub struct PackageSettings {
   pub default_run: Option<String>,
 }
 
+/// The compression format of the updater archives.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpdaterArchiveFormat {
+  /// gzip: a `.tar.gz` of the app on macOS and Linux, a `.msi.gz` of the installer on Windows.
+  Gz,
+  /// zip, deflate compressed. The updater only extracts the Windows installer from zip archives.
+  Zip,
+}
+
+impl UpdaterArchiveFormat {
+  /// Maps a short name to an UpdaterArchiveFormat.
+  /// Possible values are "gz" and "zip".
+  pub fn from_short_name(name: &str) -> Option<Self> {
+    match name {
+      "gz" => Some(UpdaterArchiveFormat::Gz),
+      "zip" => Some(UpdaterArchiveFormat::Zip),
+      _ => None,
+    }
+  }
+
+  /// Gets the short name of this UpdaterArchiveFormat.
+  pub fn short_name(&self) -> &'static str {
+    match self {
+      UpdaterArchiveFormat::Gz => "gz",
+      UpdaterArchiveFormat::Zip => "zip",
+    }
+  }
+}
+
 /// The updater settings.
 #[derive(Debug, Clone)]
 pub struct UpdaterSettings {
@@ -117,6 +208,8 @@ pub struct UpdaterSettings {
   pub pubkey: Option<String>,
   /// Display built-in dialog or use event system if disabled.
   pub dialog: bool,
+  /// The archive format, defaults to a `.tar.gz` on macOS and Linux and an uncompressed `.zip` on Windows.
+  pub archive_format: Option<UpdaterArchiveFormat>,
 }
 
 /// The Linux debian bundle settings.
@@ -134,6 +227,75 @@ pub struct DebianSettings {
   /// List of custom files to add to the deb package.
   /// Maps the path on the debian package to the path of the file to include (relative to the current working directory).
   pub files: HashMap<PathBuf, PathBuf>,
+  /// the GPG key used to sign the package with `dpkg-sig`.
+  pub signing_key: Option<String>,
+  /// whether to generate the `Packages` and `Release` files of a flat apt repository next to the package.
+  pub repository_metadata: bool,
+}
+
+/// The Linux Flatpak bundle settings.
+#[derive(Clone, Debug)]
+pub struct FlatpakSettings {
+  /// the Flatpak runtime of the app, it must provide WebKitGTK.
+  pub runtime: String,
+  /// the version of the runtime and the SDK.
+  pub runtime_version: String,
+  /// the SDK matching the runtime.
+  pub sdk: String,
+  /// the sandbox permissions of the app, i.e. the `finish-args` of the manifest.
+  pub finish_args: Vec<String>,
+}
+
+impl Default for FlatpakSettings {
+  fn default() -> Self {
+    Self {
+      runtime: "org.gnome.Platform".into(),
+      runtime_version: "40".into(),
+      sdk: "org.gnome.Sdk".into(),
+      finish_args: vec![
+        "--share=ipc".into(),
+        "--share=network".into(),
+        "--socket=x11".into(),
+        "--socket=wayland".into(),
+        "--device=dri".into(),
+      ],
+    }
+  }
+}
+
+/// The Linux RPM bundle settings.
+#[derive(Clone, Debug, Default)]
+pub struct RpmSettings {
+  /// the GPG key used to sign the package with `rpm --addsign`.
+  pub gpg_key: Option<String>,
+}
+
+/// The Linux Snap bundle settings.
+#[derive(Clone, Debug)]
+pub struct SnapSettings {
+  /// the snap confinement: `strict`, `classic` or `devmode`.
+  pub confinement: String,
+  /// the snap grade: `stable` or `devel`.
+  pub grade: String,
+  /// the interfaces the app connects to.
+  pub plugs: Vec<String>,
+}
+
+impl Default for SnapSettings {
+  fn default() -> Self {
+    Self {
+      confinement: "strict".into(),
+      grade: "stable".into(),
+      plugs: vec![
+        "desktop".into(),
+        "desktop-legacy".into(),
+        "wayland".into(),
+        "x11".into(),
+        "opengl".into(),
+        "network".into(),
+      ],
+    }
+  }
 }
 
 /// The macOS bundle settings.
@@ -170,6 +332,49 @@ pub struct MacOsSettings {
   pub signing_identity: Option<String>,
   /// Path to the entitlements.plist file.
   pub entitlements: Option<String>,
+  /// Whether the app and DMG should be notarized and stapled after signing.
+  ///
+  /// Requires a signing identity and the `APPLE_ID`, `APPLE_PASSWORD` and `APPLE_TEAM_ID` environment variables.
+  pub notarize: bool,
+  /// Additional `Info.plist` keys, written after the keys generated by the bundler.
+  ///
+  /// The JSON values are converted to their plist equivalent: objects to `<dict>`, arrays to `<array>`, numbers to `<integer>` or `<real>`.
+  pub info_plist: HashMap<String, serde_json::Value>,
+}
+
+/// The install scope of the MSI installer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WixInstallScope {
+  /// Installs the app for every user on `Program Files`, which requires elevation.
+  PerMachine,
+  /// Installs the app for the current user on `%LOCALAPPDATA%\Programs`, without elevation.
+  PerUser,
+}
+
+impl Default for WixInstallScope {
+  fn default() -> Self {
+    WixInstallScope::PerMachine
+  }
+}
+
+impl WixInstallScope {
+  /// Maps a name to a WixInstallScope.
+  /// Possible values are "perMachine" and "perUser".
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "perMachine" => Some(WixInstallScope::PerMachine),
+      "perUser" => Some(WixInstallScope::PerUser),
+      _ => None,
+    }
+  }
+
+  /// Gets the name of this WixInstallScope, the `InstallScope` of the WiX `Package` element.
+  pub fn name(&self) -> &'static str {
+    match self {
+      WixInstallScope::PerMachine => "perMachine",
+      WixInstallScope::PerUser => "perUser",
+    }
+  }
 }
 
 /// Settings specific to the WiX implementation.
@@ -192,6 +397,32 @@ pub struct WixSettings {
   pub merge_refs: Vec<String>,
   /// Disables the Webview2 runtime installation after app install.
   pub skip_webview_install: bool,
+  /// Whether the app is installed for every user or only for the current one.
+  pub install_scope: WixInstallScope,
+}
+
+/// The NSIS installer settings.
+#[derive(Clone, Debug)]
+pub struct NsisSettings {
+  /// the default install directory, `$PROGRAMFILES64\<product name>` by default. The NSIS constants such as `$LOCALAPPDATA` are expanded.
+  pub install_dir: Option<String>,
+  /// whether the installer creates a desktop shortcut.
+  pub desktop_shortcut: bool,
+  /// whether the installer creates a start menu shortcut.
+  pub start_menu_shortcut: bool,
+  /// whether the uninstaller removes the app data directory, `$APPDATA\<identifier>`.
+  pub delete_app_data_on_uninstall: bool,
+}
+
+impl Default for NsisSettings {
+  fn default() -> Self {
+    Self {
+      install_dir: None,
+      desktop_shortcut: false,
+      start_menu_shortcut: true,
+      delete_app_data_on_uninstall: false,
+    }
+  }
 }
 
 /// The Windows bundle settings.
@@ -205,6 +436,8 @@ pub struct WindowsSettings {
   pub timestamp_url: Option<String>,
   /// WiX configuration.
   pub wix: Option<WixSettings>,
+  /// NSIS configuration.
+  pub nsis: NsisSettings,
   /// The path to the application icon. Defaults to `./icons/icon.ico`.
   pub icon_path: PathBuf,
 }
@@ -216,6 +449,7 @@ impl Default for WindowsSettings {
       certificate_thumbprint: None,
       timestamp_url: None,
       wix: None,
+      nsis: NsisSettings::default(),
       icon_path: PathBuf::from("icons/icon.ico"),
     }
   }
@@ -234,6 +468,14 @@ pub struct BundleSettings {
   ///
   /// supports glob patterns.
   pub resources: Option<Vec<String>>,
+  /// glob patterns of the resource files to leave out of the bundle,
+  /// e.g. `**/*.map` to skip the source maps of a resource folder.
+  pub resources_exclude: Option<Vec<String>>,
+  /// the resource files placed on an explicit destination, as `(source, destination)` pairs.
+  ///
+  /// the destination is relative to the bundle resources directory.
+  /// they're bundled in addition to `resources`.
+  pub resource_mappings: Option<Vec<(PathBuf, PathBuf)>>,
   /// the app's copyright.
   pub copyright: Option<String>,
   /// the app's category.
@@ -256,8 +498,14 @@ pub struct BundleSettings {
   pub external_bin: Option<Vec<String>>,
   /// Debian-specific settings.
   pub deb: DebianSettings,
+  /// Flatpak-specific settings.
+  pub flatpak: FlatpakSettings,
   /// MacOS-specific settings.
   pub macos: MacOsSettings,
+  /// RPM-specific settings.
+  pub rpm: RpmSettings,
+  /// Snap-specific settings.
+  pub snap: SnapSettings,
   /// Updater configuration.
   pub updater: Option<UpdaterSettings>,
   /// Windows-specific settings.
@@ -331,8 +579,14 @@ pub struct Settings {
   project_out_directory: PathBuf,
   /// whether or not to enable verbose logging
   is_verbose: bool,
+  /// whether to keep bundling the remaining package types when one of them fails.
+  keep_going: bool,
+  /// the modification time of the archive entries, for reproducible archives.
+  source_date_epoch: Option<u64>,
   /// the bundle settings.
   bundle_settings: BundleSettings,
+  /// the compiled `resources_exclude` patterns.
+  resources_exclude: Vec<glob::Pattern>,
   /// the binaries to bundle.
   binaries: Vec<BundleBinary>,
 }
@@ -342,10 +596,13 @@ pub struct Settings {
 pub struct SettingsBuilder {
   project_out_directory: Option<PathBuf>,
   verbose: bool,
+  keep_going: bool,
   package_types: Option<Vec<PackageType>>,
   package_settings: Option<PackageSettings>,
   bundle_settings: BundleSettings,
   binaries: Vec<BundleBinary>,
+  target: Option<String>,
+  source_date_epoch: Option<u64>,
 }
 
 impl SettingsBuilder {
@@ -368,6 +625,12 @@ impl SettingsBuilder {
     self
   }
 
+  /// Keeps bundling the remaining package types when one of them fails.
+  pub fn keep_going(mut self) -> Self {
+    self.keep_going = true;
+    self
+  }
+
   /// Sets the package types to create.
   pub fn package_types(mut self, package_types: Vec<PackageType>) -> Self {
     self.package_types = Some(package_types);
@@ -392,23 +655,50 @@ impl SettingsBuilder {
     self
   }
 
+  /// Makes the archives reproducible, using the given Unix timestamp as the modification time of their entries.
+  pub fn source_date_epoch(mut self, source_date_epoch: u64) -> Self {
+    self.source_date_epoch.replace(source_date_epoch);
+    self
+  }
+
+  /// Sets the target triple the app was built for, used to resolve the external binaries.
+  ///
+  /// Defaults to the host triple.
+  pub fn target(mut self, target: String) -> Self {
+    self.target.replace(target);
+    self
+  }
+
   /// Builds a Settings from the CLI args.
   ///
   /// Package settings will be read from Cargo.toml.
   ///
   /// Bundle settings will be read from from $TAURI_DIR/tauri.conf.json if it exists and fallback to Cargo.toml's [package.metadata.bundle].
   pub fn build(self) -> crate::Result<Settings> {
-    let bundle_settings = parse_external_bin(self.bundle_settings)?;
+    let target_triple = match self.target {
+      Some(target) => target,
+      None => target_triple()?,
+    };
+    let bundle_settings = parse_external_bin(self.bundle_settings, &target_triple)?;
+    let resources_exclude = bundle_settings
+      .resources_exclude
+      .iter()
+      .flatten()
+      .map(|pattern| glob::Pattern::new(pattern))
+      .collect::<Result<Vec<_>, _>>()?;
 
     Ok(Settings {
       package: self.package_settings.expect("package settings is required"),
       package_types: self.package_types,
       is_verbose: self.verbose,
+      keep_going: self.keep_going,
+      source_date_epoch: self.source_date_epoch,
       project_out_directory: self
         .project_out_directory
         .expect("out directory is required"),
       binaries: self.binaries,
       bundle_settings,
+      resources_exclude,
     })
   }
 }
@@ -448,7 +738,8 @@ impl Settings {
   }
 
   /// If a list of package types was specified by the command-line, returns
-  /// that list filtered by the current target OS available targets.
+  /// that list, failing if one of them can't be bundled on the current target OS.
+  /// The updater is left out when it isn't active.
   ///
   /// If a target triple was specified by the
   /// command-line, returns the native package type(s) for that target.
@@ -458,18 +749,7 @@ impl Settings {
   /// Fails if the host/target's native package type is not supported.
   pub fn package_types(&self) -> crate::Result<Vec<PackageType>> {
     let target_os = std::env::consts::OS;
-    let mut platform_types = match target_os {
-      "macos" => vec![PackageType::MacOsBundle, PackageType::Dmg],
-      "ios" => vec![PackageType::IosBundle],
-      "linux" => vec![PackageType::Deb, PackageType::AppImage],
-      "windows" => vec![PackageType::WindowsMsi],
-      os => {
-        return Err(crate::Error::GenericError(format!(
-          "Native {} bundles not yet supported.",
-          os
-        )))
-      }
-    };
+    let mut platform_types = PackageType::platform_defaults()?;
 
     // add updater if needed
     if self.is_update_enabled() {
@@ -477,17 +757,38 @@ impl Settings {
     }
 
     if let Some(package_types) = &self.package_types {
+      // the archives, the RPM, the Flatpak, the Snap and the NSIS installer are only built when explicitly requested
+      let mut supported_types = platform_types.clone();
+      if target_os == "windows" {
+        supported_types.push(PackageType::Nsis);
+      }
+      if target_os == "linux" {
+        supported_types.extend(&[
+          PackageType::Rpm,
+          PackageType::TarGz,
+          PackageType::TarXz,
+          PackageType::Flatpak,
+          PackageType::Snap,
+        ]);
+      }
       let mut types = vec![];
       for package_type in package_types {
         let package_type = *package_type;
-        if platform_types
-          .clone()
-          .into_iter()
-          .any(|t| t == package_type)
-        {
+        if supported_types.iter().any(|t| *t == package_type) {
           types.push(package_type);
+        } else if package_type != PackageType::Updater {
+          return Err(crate::Error::GenericError(format!(
+            "The `{}` bundle is not supported on {}.",
+            package_type.short_name(),
+            target_os
+          )));
         }
       }
+      if types.is_empty() && !package_types.is_empty() {
+        return Err(crate::Error::GenericError(
+          "The updater bundle requires the updater to be active.".into(),
+        ));
+      }
       Ok(types)
     } else {
       Ok(platform_types)
@@ -499,6 +800,16 @@ impl Settings {
     self.is_verbose
   }
 
+  /// Returns true if the remaining package types are bundled when one of them fails.
+  pub fn keep_going(&self) -> bool {
+    self.keep_going
+  }
+
+  /// Returns the modification time of the archive entries if the archives must be reproducible.
+  pub fn source_date_epoch(&self) -> Option<u64> {
+    self.source_date_epoch
+  }
+
   /// Returns the product name.
   pub fn product_name(&self) -> &str {
     &self.package.product_name
@@ -518,12 +829,45 @@ impl Settings {
   }
 
   /// Returns an iterator over the resource files to be included in this
-  /// bundle.
+  /// bundle, skipping the files matching the `resources_exclude` patterns.
   pub fn resource_files(&self) -> ResourcePaths<'_> {
-    match self.bundle_settings.resources {
+    let paths = match self.bundle_settings.resources {
       Some(ref paths) => ResourcePaths::new(paths.as_slice(), true),
       None => ResourcePaths::new(&[], true),
-    }
+    };
+    paths.exclude(&self.resources_exclude)
+  }
+
+  /// Returns the resource files placed on an explicit destination, as `(source, destination)` pairs.
+  pub fn resource_mappings(&self) -> &[(PathBuf, PathBuf)] {
+    self
+      .bundle_settings
+      .resource_mappings
+      .as_deref()
+      .unwrap_or(&[])
+  }
+
+  /// Returns an iterator over all the resources to bundle as `(source, destination)` pairs,
+  /// where the destination is relative to the bundle resources directory.
+  ///
+  /// The resource files keep their path, with `_up_` for the parent directories and `_root_` for the root, followed by the resource mappings.
+  pub fn resources(&self) -> impl Iterator<Item = crate::Result<(PathBuf, PathBuf)>> + '_ {
+    self
+      .resource_files()
+      .map(|src| {
+        src.map(|src| {
+          let dest = common::resource_relpath(&src);
+          (src, dest)
+        })
+      })
+      .chain(self.resource_mappings().iter().cloned().map(Ok))
+  }
+
+  /// Returns the number of resource files left out of the bundle by the `resources_exclude` patterns.
+  pub fn excluded_resources_count(&self) -> usize {
+    let mut paths = self.resource_files();
+    paths.by_ref().for_each(drop);
+    paths.excluded_count
   }
 
   /// Returns an iterator over the external binaries to be included in this
@@ -551,10 +895,9 @@ impl Settings {
 
   /// Copies resources to a path.
   pub fn copy_resources(&self, path: &Path) -> crate::Result<()> {
-    for src in self.resource_files() {
-      let src = src?;
-      let dest = path.join(common::resource_relpath(&src));
-      common::copy_file(&src, &dest)?;
+    for resource in self.resources() {
+      let (src, dest) = resource?;
+      common::copy_file(&src, &path.join(dest))?;
     }
     Ok(())
   }
@@ -616,6 +959,21 @@ impl Settings {
     &self.bundle_settings.deb
   }
 
+  /// Returns the Flatpak settings.
+  pub fn flatpak(&self) -> &FlatpakSettings {
+    &self.bundle_settings.flatpak
+  }
+
+  /// Returns the RPM settings.
+  pub fn rpm(&self) -> &RpmSettings {
+    &self.bundle_settings.rpm
+  }
+
+  /// Returns the Snap settings.
+  pub fn snap(&self) -> &SnapSettings {
+    &self.bundle_settings.snap
+  }
+
   /// Returns the MacOS settings.
   pub fn macos(&self) -> &MacOsSettings {
     &self.bundle_settings.macos
@@ -634,6 +992,15 @@ impl Settings {
     }
   }
 
+  /// Returns the updater archive format, if it's set.
+  pub fn updater_archive_format(&self) -> Option<UpdaterArchiveFormat> {
+    self
+      .bundle_settings
+      .updater
+      .as_ref()
+      .and_then(|updater| updater.archive_format)
+  }
+
   /// Is pubkey provided?
   pub fn is_updater_pubkey(&self) -> bool {
     match &self.bundle_settings.updater {
@@ -656,18 +1023,33 @@ impl Settings {
 }
 
 /// Parses the external binaries to bundle, adding the target triple suffix to each of them.
-fn parse_external_bin(bundle_settings: BundleSettings) -> crate::Result<BundleSettings> {
-  let target_triple = target_triple()?;
+///
+/// Fails if a binary doesn't exist for the target, so the error is reported before any package is bundled.
+fn parse_external_bin(
+  bundle_settings: BundleSettings,
+  target_triple: &str,
+) -> crate::Result<BundleSettings> {
   let mut win_paths = Vec::new();
   let external_bin = match bundle_settings.external_bin {
     Some(paths) => {
       for curr_path in paths.iter() {
-        win_paths.push(format!(
+        let path = format!(
           "{}-{}{}",
           curr_path,
           target_triple,
-          if cfg!(windows) { ".exe" } else { "" }
-        ));
+          if target_triple.contains("windows") {
+            ".exe"
+          } else {
+            ""
+          }
+        );
+        if glob::glob(&path)?.next().is_none() {
+          return Err(crate::Error::ExternalBinaryNotFound {
+            name: curr_path.clone(),
+            path,
+          });
+        }
+        win_paths.push(path);
       }
       Some(win_paths)
     }
@@ -694,6 +1076,10 @@ pub struct ResourcePaths<'a> {
   current_pattern: Option<String>,
   /// whether the current pattern is valid or not.
   current_pattern_is_valid: bool,
+  /// the patterns of the paths to skip.
+  exclude: &'a [glob::Pattern],
+  /// the number of paths skipped so far.
+  excluded_count: usize,
 }
 
 impl<'a> ResourcePaths<'a> {
@@ -706,7 +1092,30 @@ impl<'a> ResourcePaths<'a> {
       allow_walk,
       current_pattern: None,
       current_pattern_is_valid: false,
+      exclude: &[],
+      excluded_count: 0,
+    }
+  }
+
+  /// Skips the paths matching one of the patterns.
+  fn exclude(mut self, patterns: &'a [glob::Pattern]) -> ResourcePaths<'a> {
+    self.exclude = patterns;
+    self
+  }
+
+  /// Whether the path matches one of the exclude patterns, ignoring its `./` components.
+  fn is_excluded(&self, path: &Path) -> bool {
+    if self.exclude.is_empty() {
+      return false;
     }
+    let path: PathBuf = path
+      .components()
+      .filter(|component| *component != Component::CurDir)
+      .collect();
+    self
+      .exclude
+      .iter()
+      .any(|pattern| pattern.matches_path(&path))
   }
 }
 
@@ -725,7 +1134,12 @@ impl<'a> Iterator for ResourcePaths<'a> {
           if path.is_dir() {
             continue;
           }
+          // an excluded path still counts as a match of the pattern
           self.current_pattern_is_valid = true;
+          if self.is_excluded(path) {
+            self.excluded_count += 1;
+            continue;
+          }
           return Some(Ok(path.to_path_buf()));
         }
       }
@@ -747,6 +1161,10 @@ impl<'a> Iterator for ResourcePaths<'a> {
             }
           }
           self.current_pattern_is_valid = true;
+          if self.is_excluded(&path) {
+            self.excluded_count += 1;
+            continue;
+          }
           return Some(Ok(path));
         } else if let Some(current_path) = &self.current_pattern {
           if !self.current_pattern_is_valid {
@@ -772,3 +1190,153 @@ impl<'a> Iterator for ResourcePaths<'a> {
     }
   }
 }
+
+/// The package settings of the bundler tests, an `app` at version `1.0.0`.
+#[cfg(test)]
+pub(crate) fn test_package_settings() -> PackageSettings {
+  PackageSettings {
+    product_name: "app".into(),
+    version: "1.0.0".into(),
+    description: "".into(),
+    homepage: None,
+    authors: None,
+    default_run: None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{test_package_settings, BundleSettings, PackageType, Settings, SettingsBuilder};
+  use std::{
+    fs::{create_dir, write},
+    path::Path,
+  };
+
+  fn settings(out_dir: &Path, external_bin: &str, target: &str) -> crate::Result<Settings> {
+    SettingsBuilder::new()
+      .project_out_directory(out_dir)
+      .package_settings(test_package_settings())
+      .bundle_settings(BundleSettings {
+        external_bin: Some(vec![out_dir.join(external_bin).display().to_string()]),
+        ..Default::default()
+      })
+      .target(target.into())
+      .build()
+  }
+
+  #[test]
+  fn external_bin_target_suffix() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    let linux_sidecar = tmp.path().join("sidecar-x86_64-unknown-linux-gnu");
+    let windows_sidecar = tmp.path().join("sidecar-x86_64-pc-windows-msvc.exe");
+    write(&linux_sidecar, b"sidecar").expect("failed to write sidecar");
+    write(&windows_sidecar, b"sidecar").expect("failed to write sidecar");
+
+    let linux_settings = settings(tmp.path(), "sidecar", "x86_64-unknown-linux-gnu")
+      .expect("failed to build settings");
+    assert_eq!(
+      linux_settings
+        .external_binaries()
+        .map(|path| path.expect("invalid external binary"))
+        .collect::<Vec<_>>(),
+      vec![linux_sidecar]
+    );
+
+    let windows_settings =
+      settings(tmp.path(), "sidecar", "x86_64-pc-windows-msvc").expect("failed to build settings");
+    assert_eq!(
+      windows_settings
+        .external_binaries()
+        .map(|path| path.expect("invalid external binary"))
+        .collect::<Vec<_>>(),
+      vec![windows_sidecar]
+    );
+  }
+
+  #[test]
+  fn missing_external_bin() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    // the binary exists, but not for the requested target
+    write(
+      tmp.path().join("sidecar-x86_64-unknown-linux-gnu"),
+      b"sidecar",
+    )
+    .expect("failed to write sidecar");
+
+    match settings(tmp.path(), "sidecar", "aarch64-unknown-linux-gnu") {
+      Err(crate::Error::ExternalBinaryNotFound { name, path }) => {
+        assert_eq!(name, tmp.path().join("sidecar").display().to_string());
+        assert_eq!(
+          path,
+          tmp
+            .path()
+            .join("sidecar-aarch64-unknown-linux-gnu")
+            .display()
+            .to_string()
+        );
+      }
+      Err(e) => panic!("unexpected error: {}", e),
+      Ok(_) => panic!("the missing external binary was not reported"),
+    }
+  }
+
+  #[test]
+  fn resources_exclude() {
+    let tmp = tempfile::tempdir().expect("unable to create temp dir");
+    let assets = tmp.path().join("assets");
+    create_dir(&assets).expect("failed to create assets dir");
+    write(assets.join("app.js"), b"app").expect("failed to write resource");
+    write(assets.join("app.js.map"), b"map").expect("failed to write resource");
+    write(assets.join(".DS_Store"), b"").expect("failed to write resource");
+    write(tmp.path().join("only.map"), b"map").expect("failed to write resource");
+
+    let settings = SettingsBuilder::new()
+      .project_out_directory(tmp.path())
+      .package_settings(test_package_settings())
+      .bundle_settings(BundleSettings {
+        resources: Some(vec![
+          assets.display().to_string(),
+          // every match is excluded, it must not be reported as missing
+          tmp.path().join("*.map").display().to_string(),
+        ]),
+        resources_exclude: Some(vec!["**/*.map".into(), "**/.DS_Store".into()]),
+        ..Default::default()
+      })
+      .build()
+      .expect("failed to build settings");
+
+    assert_eq!(
+      settings
+        .resource_files()
+        .map(|path| path.expect("invalid resource"))
+        .collect::<Vec<_>>(),
+      vec![assets.join("app.js")]
+    );
+    assert_eq!(settings.excluded_resources_count(), 3);
+  }
+
+  #[test]
+  fn invalid_resources_exclude() {
+    let result = SettingsBuilder::new()
+      .project_out_directory("target/release")
+      .package_settings(test_package_settings())
+      .bundle_settings(BundleSettings {
+        resources_exclude: Some(vec!["assets/***".into()]),
+        ..Default::default()
+      })
+      .build();
+    assert!(matches!(result, Err(crate::Error::GlobPatternError(_))));
+  }
+
+  #[test]
+  fn unsupported_package_type() {
+    let settings = SettingsBuilder::new()
+      .project_out_directory("target/release")
+      .package_settings(test_package_settings())
+      .package_types(vec![PackageType::IosBundle])
+      .build()
+      .expect("failed to build settings");
+    // the requested type isn't silently dropped, which would bundle nothing
+    assert!(settings.package_types().is_err());
+  }
+}
@@ -17,7 +17,7 @@ use std::{fs::File, io::prelude::*};
 #[cfg(target_os = "windows")]
 use zip::write::FileOptions;
 
-use crate::{bundle::Bundle, Settings};
+use crate::{bundle::Bundle, Settings, UpdaterArchiveFormat};
 use std::{
   ffi::OsStr,
   fs::{self},
@@ -43,6 +43,8 @@ pub fn bundle_project(settings: &Settings, bundles: &[Bundle]) -> crate::Result<
 // This is the Mac OS App packaged
 #[cfg(target_os = "macos")]
 fn bundle_update(settings: &Settings, bundles: &[Bundle]) -> crate::Result<Vec<PathBuf>> {
+  check_tar_gz_format(settings)?;
+
   // find our .app or rebuild our bundle
   let bundle_path = match bundles
     .iter()
@@ -70,8 +72,12 @@ fn bundle_update(settings: &Settings, bundles: &[Bundle]) -> crate::Result<Vec<P
 
   // Create our gzip file (need to send parent)
   // as we walk the source directory (source isnt added)
-  create_tar(&source_path, &osx_archived_path)
-    .with_context(|| "Failed to tar.gz update directory")?;
+  create_tar(
+    &source_path,
+    &osx_archived_path,
+    settings.source_date_epoch(),
+  )
+  .with_context(|| "Failed to tar.gz update directory")?;
 
   common::print_bundling(format!("{:?}", &osx_archived_path).as_str())?;
   Ok(vec![osx_archived_path])
@@ -83,6 +89,8 @@ fn bundle_update(settings: &Settings, bundles: &[Bundle]) -> crate::Result<Vec<P
 // No assets are replaced
 #[cfg(target_os = "linux")]
 fn bundle_update(settings: &Settings, bundles: &[Bundle]) -> crate::Result<Vec<PathBuf>> {
+  check_tar_gz_format(settings)?;
+
   // build our app actually we support only appimage on linux
   let bundle_path = match bundles
     .iter()
@@ -109,8 +117,12 @@ fn bundle_update(settings: &Settings, bundles: &[Bundle]) -> crate::Result<Vec<P
   let appimage_archived_path = PathBuf::from(&appimage_archived);
 
   // Create our gzip file
-  create_tar(&source_path, &appimage_archived_path)
-    .with_context(|| "Failed to tar.gz update directory")?;
+  create_tar(
+    &source_path,
+    &appimage_archived_path,
+    settings.source_date_epoch(),
+  )
+  .with_context(|| "Failed to tar.gz update directory")?;
 
   common::print_bundling(format!("{:?}", &appimage_archived_path).as_str())?;
   Ok(vec![appimage_archived_path])
@@ -143,19 +155,49 @@ fn bundle_update(settings: &Settings, bundles: &[Bundle]) -> crate::Result<Vec<P
 
   let source_path = &bundle_path[0];
 
-  // add .tar.gz to our path
-  let msi_archived = format!("{}.zip", source_path.display());
-  let msi_archived_path = PathBuf::from(&msi_archived);
-
-  // Create our gzip file
-  create_zip(&source_path, &msi_archived_path).with_context(|| "Failed to zip update MSI")?;
+  let msi_archived_path = match settings.updater_archive_format() {
+    Some(UpdaterArchiveFormat::Gz) => {
+      // add .gz to our path
+      let msi_archived_path = PathBuf::from(format!("{}.gz", source_path.display()));
+      create_gz(
+        &source_path,
+        &msi_archived_path,
+        settings.source_date_epoch(),
+      )
+      .with_context(|| "Failed to gzip update MSI")?;
+      msi_archived_path
+    }
+    format => {
+      // add .zip to our path
+      let msi_archived_path = PathBuf::from(format!("{}.zip", source_path.display()));
+      // the default archive stores the MSI uncompressed
+      let compression = if format == Some(UpdaterArchiveFormat::Zip) {
+        zip::CompressionMethod::Deflated
+      } else {
+        zip::CompressionMethod::Stored
+      };
+      create_zip(
+        &source_path,
+        &msi_archived_path,
+        settings.source_date_epoch(),
+        compression,
+      )
+      .with_context(|| "Failed to zip update MSI")?;
+      msi_archived_path
+    }
+  };
 
   common::print_bundling(format!("{:?}", &msi_archived_path).as_str())?;
   Ok(vec![msi_archived_path])
 }
 
 #[cfg(target_os = "windows")]
-pub fn create_zip(src_file: &Path, dst_file: &Path) -> crate::Result<PathBuf> {
+pub fn create_zip(
+  src_file: &Path,
+  dst_file: &Path,
+  mtime: Option<u64>,
+  compression: zip::CompressionMethod,
+) -> crate::Result<PathBuf> {
   let parent_dir = dst_file.parent().expect("No data in parent");
   fs::create_dir_all(parent_dir)?;
   let writer = common::create_file(dst_file)?;
@@ -165,9 +207,12 @@ pub fn create_zip(src_file: &Path, dst_file: &Path) -> crate::Result<PathBuf> {
     .expect("Can't extract file name from path");
 
   let mut zip = zip::ZipWriter::new(writer);
-  let options = FileOptions::default()
-    .compression_method(zip::CompressionMethod::Stored)
+  let mut options = FileOptions::default()
+    .compression_method(compression)
     .unix_permissions(0o755);
+  if let Some(mtime) = mtime {
+    options = options.last_modified_time(zip_date_time(mtime));
+  }
 
   zip.start_file(file_name.to_string_lossy(), options)?;
   let mut f = File::open(src_file)?;
@@ -179,19 +224,65 @@ pub fn create_zip(src_file: &Path, dst_file: &Path) -> crate::Result<PathBuf> {
   Ok(dst_file.to_owned())
 }
 
+/// Compresses `src_file` to the gzip file `dst_file`.
+#[cfg(target_os = "windows")]
+fn create_gz(src_file: &Path, dst_file: &Path, mtime: Option<u64>) -> crate::Result<PathBuf> {
+  let dest_file = common::create_file(dst_file)?;
+  let mut gzip_encoder = common::gzip_encoder(dest_file, mtime)?;
+  let mut f = File::open(src_file)?;
+  std::io::copy(&mut f, &mut gzip_encoder)?;
+  let mut dest_file = gzip_encoder.finish().into_result()?;
+  dest_file.flush()?;
+  Ok(dst_file.to_owned())
+}
+
+/// Converts a Unix timestamp to the MS-DOS date and time of the zip entries, clamped to the 1980-2107 range it supports.
+#[cfg(target_os = "windows")]
+fn zip_date_time(timestamp: u64) -> zip::DateTime {
+  let date_time = common::utc_date_time(timestamp);
+  if date_time.year < 1980 {
+    return zip::DateTime::default();
+  }
+  zip::DateTime::from_date_and_time(
+    date_time.year.min(2107) as u16,
+    date_time.month as u8,
+    date_time.day as u8,
+    date_time.hour as u8,
+    date_time.minute as u8,
+    date_time.second as u8,
+  )
+  .unwrap_or_default()
+}
+
+/// Checks that the updater archive format is `gz`, the updater can't extract the macOS and Linux apps from zip archives
+/// since they lose their executable permissions.
 #[cfg(not(target_os = "windows"))]
-fn create_tar(src_dir: &Path, dest_path: &Path) -> crate::Result<PathBuf> {
+fn check_tar_gz_format(settings: &Settings) -> crate::Result<()> {
+  match settings.updater_archive_format() {
+    Some(UpdaterArchiveFormat::Zip) => Err(crate::Error::GenericError(
+      "the `zip` updater archive format is only supported on Windows, use `gz`".into(),
+    )),
+    _ => Ok(()),
+  }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn create_tar(src_dir: &Path, dest_path: &Path, mtime: Option<u64>) -> crate::Result<PathBuf> {
   let dest_file = common::create_file(&dest_path)?;
-  let gzip_encoder = libflate::gzip::Encoder::new(dest_file)?;
+  let gzip_encoder = common::gzip_encoder(dest_file, mtime)?;
 
-  let gzip_encoder = create_tar_from_src(src_dir, gzip_encoder)?;
+  let gzip_encoder = create_tar_from_src(src_dir, gzip_encoder, mtime)?;
   let mut dest_file = gzip_encoder.finish().into_result()?;
   dest_file.flush()?;
   Ok(dest_path.to_owned())
 }
 
 #[cfg(not(target_os = "windows"))]
-fn create_tar_from_src<P: AsRef<Path>, W: Write>(src_dir: P, dest_file: W) -> crate::Result<W> {
+fn create_tar_from_src<P: AsRef<Path>, W: Write>(
+  src_dir: P,
+  dest_file: W,
+  mtime: Option<u64>,
+) -> crate::Result<W> {
   let src_dir = src_dir.as_ref();
   let mut tar_builder = tar::Builder::new(dest_file);
 
@@ -199,14 +290,13 @@ fn create_tar_from_src<P: AsRef<Path>, W: Write>(src_dir: P, dest_file: W) -> cr
   let file_type = fs::metadata(src_dir).expect("Can't read source directory");
   // if it's a file don't need to walkdir
   if file_type.is_file() {
-    let mut src_file = fs::File::open(src_dir)?;
     let file_name = src_dir
       .file_name()
       .expect("Can't extract file name from path");
 
-    tar_builder.append_file(file_name, &mut src_file)?;
+    common::append_to_tar(&mut tar_builder, src_dir, Path::new(file_name), mtime)?;
   } else {
-    for entry in walkdir::WalkDir::new(&src_dir) {
+    for entry in walkdir::WalkDir::new(&src_dir).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
       let entry = entry?;
       let src_path = entry.path();
       if src_path == src_dir {
@@ -218,12 +308,7 @@ fn create_tar_from_src<P: AsRef<Path>, W: Write>(src_dir: P, dest_file: W) -> cr
       // We need a tar with app.app/<...> (source root folder should be included)
       // safe to unwrap: the path has a parent
       let dest_path = src_path.strip_prefix(&src_dir.parent().unwrap())?;
-      if entry.file_type().is_dir() {
-        tar_builder.append_dir(dest_path, src_path)?;
-      } else {
-        let mut src_file = fs::File::open(src_path)?;
-        tar_builder.append_file(dest_path, &mut src_file)?;
-      }
+      common::append_to_tar(&mut tar_builder, src_path, dest_path, mtime)?;
     }
   }
   let dest_file = tar_builder.into_inner()?;
@@ -1,2 +1,3 @@
 pub mod msi;
+pub mod nsis;
 pub mod sign;
@@ -6,7 +6,7 @@ use super::super::sign::{sign, SignParams};
 use crate::bundle::{
   common,
   path_utils::{copy_file, FileOpts},
-  settings::Settings,
+  settings::{Settings, WixInstallScope, WixSettings},
 };
 
 use handlebars::{to_json, Handlebars};
@@ -73,6 +73,8 @@ struct ResourceFile {
   guid: String,
   /// the id to use on the WIX XML.
   id: String,
+  /// the installed file name.
+  name: String,
   /// the file path.
   path: String,
 }
@@ -103,9 +105,10 @@ impl ResourceDirectory {
       file_ids.push(file.id.clone());
       files.push_str(
         format!(
-          r#"<Component Id="{id}" Guid="{guid}" Win64="$(var.Win64)" KeyPath="yes"><File Id="PathFile_{id}" Source="{path}" /></Component>"#,
+          r#"<Component Id="{id}" Guid="{guid}" Win64="$(var.Win64)" KeyPath="yes"><File Id="PathFile_{id}" Name="{name}" Source="{path}" /></Component>"#,
           id = file.id,
           guid = file.guid,
+          name = file.name,
           path = file.path
         ).as_str()
       );
@@ -323,6 +326,14 @@ fn run_light(
     output_path.display().to_string(),
   ];
 
+  // the per-user files are keyed by the files themselves instead of HKCU registry values,
+  // which the ICEs written for the per-machine installers reject
+  if install_scope(settings) == WixInstallScope::PerUser {
+    for ice in &["ICE38", "ICE64", "ICE91"] {
+      args.push(format!("-sice:{}", ice));
+    }
+  }
+
   for p in wixobjs {
     args.push((*p).to_string());
   }
@@ -348,6 +359,27 @@ fn run_light(
     })
 }
 
+/// Adds the element ids referenced from the WiX fragments to the template data.
+///
+/// The fragments are compiled by candle along with the generated source, and light links the referenced elements.
+fn insert_fragment_refs(data: &mut BTreeMap<&str, serde_json::Value>, wix: &WixSettings) {
+  data.insert("component_group_refs", to_json(&wix.component_group_refs));
+  data.insert("component_refs", to_json(&wix.component_refs));
+  data.insert("feature_group_refs", to_json(&wix.feature_group_refs));
+  data.insert("feature_refs", to_json(&wix.feature_refs));
+  data.insert("merge_refs", to_json(&wix.merge_refs));
+}
+
+/// Gets the install scope of the WiX settings, per machine by default.
+fn install_scope(settings: &Settings) -> WixInstallScope {
+  settings
+    .windows()
+    .wix
+    .as_ref()
+    .map(|wix| wix.install_scope)
+    .unwrap_or_default()
+}
+
 // fn get_icon_data() -> crate::Result<()> {
 //   Ok(())
 // }
@@ -458,11 +490,7 @@ pub fn build_wix_app_installer(
   let mut install_webview = true;
 
   if let Some(wix) = &settings.windows().wix {
-    data.insert("component_group_refs", to_json(&wix.component_group_refs));
-    data.insert("component_refs", to_json(&wix.component_refs));
-    data.insert("feature_group_refs", to_json(&wix.feature_group_refs));
-    data.insert("feature_refs", to_json(&wix.feature_refs));
-    data.insert("merge_refs", to_json(&wix.merge_refs));
+    insert_fragment_refs(&mut data, wix);
     fragment_paths = wix.fragment_paths.clone();
     install_webview = !wix.skip_webview_install;
 
@@ -487,6 +515,12 @@ pub fn build_wix_app_installer(
     data.insert("install_webview", to_json(true));
   }
 
+  let install_scope = install_scope(settings);
+  data.insert("install_scope", to_json(install_scope.name()));
+  if install_scope == WixInstallScope::PerUser {
+    data.insert("per_user", to_json(true));
+  }
+
   if output_path.exists() {
     remove_dir_all(&output_path)?;
   }
@@ -500,7 +534,14 @@ pub fn build_wix_app_installer(
 
   let current_dir = std::env::current_dir()?;
   for fragment_path in fragment_paths {
-    candle_inputs.push(current_dir.join(fragment_path));
+    let fragment_path = current_dir.join(fragment_path);
+    if !fragment_path.is_file() {
+      return Err(crate::Error::GenericError(format!(
+        "WiX fragment {} not found",
+        fragment_path.display()
+      )));
+    }
+    candle_inputs.push(fragment_path);
   }
 
   for wxs in &candle_inputs {
@@ -623,6 +664,7 @@ fn generate_resource_data(settings: &Settings) -> crate::Result<ResourceMap> {
       guid: generate_guid(filename.as_bytes()).to_string(),
       path: path.to_string_lossy().to_string(),
       id: regex.replace_all(&filename, "").to_string(),
+      name: filename,
     });
   }
   if !dlls.is_empty() {
@@ -636,10 +678,15 @@ fn generate_resource_data(settings: &Settings) -> crate::Result<ResourceMap> {
     );
   }
 
-  for src in settings.resource_files() {
-    let src = src?;
+  // the resource files keep their source directories, the mapped ones are installed on their destination
+  let resource_layouts = settings
+    .resource_files()
+    .map(|src| src.map(|src| (src.clone(), src)))
+    .chain(settings.resource_mappings().iter().cloned().map(Ok));
+  for resource in resource_layouts {
+    let (src, layout) = resource?;
 
-    let filename = src
+    let filename = layout
       .file_name()
       .expect("failed to extract resource filename")
       .to_os_string()
@@ -656,10 +703,11 @@ fn generate_resource_data(settings: &Settings) -> crate::Result<ResourceMap> {
       guid: generate_guid(filename.as_bytes()).to_string(),
       path: resource_path,
       id: regex.replace_all(&filename, "").to_string(),
+      name: filename,
     };
 
     // split the resource path directories
-    let mut directories = src
+    let mut directories = layout
       .components()
       .filter(|component| {
         let comp = component.as_os_str();
@@ -667,6 +715,18 @@ fn generate_resource_data(settings: &Settings) -> crate::Result<ResourceMap> {
       })
       .collect::<Vec<_>>();
     directories.truncate(directories.len() - 1);
+    // the files without a directory are installed next to the app
+    if directories.is_empty() {
+      resources
+        .entry("".to_string())
+        .or_insert_with(|| ResourceDirectory {
+          name: "".to_string(),
+          directories: vec![],
+          files: vec![],
+        })
+        .add_file(resource_entry);
+      continue;
+    }
     // transform the directory structure to a chained vec structure
     for directory in directories {
       let directory_name = directory
@@ -719,3 +779,30 @@ fn generate_resource_data(settings: &Settings) -> crate::Result<ResourceMap> {
 
   Ok(resources)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::insert_fragment_refs;
+  use crate::WixSettings;
+  use handlebars::Handlebars;
+  use std::collections::BTreeMap;
+
+  #[test]
+  fn fragment_refs() {
+    let wix = WixSettings {
+      component_group_refs: vec!["RegistryEntries".into()],
+      component_refs: vec!["FirewallRule".into()],
+      feature_refs: vec!["Extras".into()],
+      ..Default::default()
+    };
+    let mut data = BTreeMap::new();
+    insert_fragment_refs(&mut data, &wix);
+    let source = Handlebars::new()
+      .render_template(include_str!("../templates/main.wxs"), &data)
+      .unwrap();
+
+    assert!(source.contains(r#"<ComponentGroupRef Id="RegistryEntries"/>"#));
+    assert!(source.contains(r#"<ComponentRef Id="FirewallRule"/>"#));
+    assert!(source.contains(r#"<FeatureRef Id="Extras"/>"#));
+  }
+}
@@ -0,0 +1,546 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+// An NSIS installer of the app, built with `makensis` from a generated script.
+//
+// The bundle directory holds the script and the installer:
+//
+// bundle/nsis/
+//   installer.nsi                      # the generated NSIS script
+//   <name>_<version>_<arch>-setup.exe  # the installer
+//
+// The script references the app files on their build location, so nothing is
+// copied. The installer writes an uninstaller and registers it on the
+// "Apps & features" list, so it installs per machine and requires elevation.
+
+use super::sign::{sign, SignParams};
+use crate::{bundle::common, Settings};
+
+use anyhow::Context;
+
+use std::{
+  collections::BTreeSet,
+  fs::{create_dir_all, write},
+  io,
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+/// The name of the uninstaller written on the install directory.
+const UNINSTALLER_NAME: &str = "uninstall.exe";
+
+/// A file installed by the installer.
+struct InstallFile {
+  /// the absolute path of the file on the build machine.
+  source: PathBuf,
+  /// the path of the file relative to the install directory.
+  destination: PathBuf,
+}
+
+/// Bundles the project.
+/// Returns a vector of PathBuf that shows where the NSIS installer was created.
+pub fn bundle_project(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
+  check_makensis()?;
+
+  let arch = match settings.binary_arch() {
+    "x86_64" => "x64",
+    "x86" => "x86",
+    target => {
+      return Err(crate::Error::ArchError(format!(
+        "unsupported target: {}",
+        target
+      )))
+    }
+  };
+  let bundle_name = format!(
+    "{}_{}_{}-setup.exe",
+    settings.main_binary_name().replace(".exe", ""),
+    settings.version_string(),
+    arch
+  );
+  common::print_bundling(&bundle_name)?;
+
+  let main_binary = settings
+    .binaries()
+    .iter()
+    .find(|bin| bin.main())
+    .ok_or_else(|| anyhow::anyhow!("Failed to get main binary"))?;
+  let app_exe_source = settings.binary_path(main_binary);
+  if let Some(certificate_thumbprint) = &settings.windows().certificate_thumbprint {
+    common::print_info("signing app")?;
+    sign(
+      &app_exe_source,
+      &SignParams {
+        digest_algorithm: settings
+          .windows()
+          .digest_algorithm
+          .clone()
+          .unwrap_or_else(|| "sha256".to_string()),
+        certificate_thumbprint: certificate_thumbprint.to_string(),
+        timestamp_url: settings.windows().timestamp_url.clone(),
+      },
+    )?;
+  }
+
+  let base_dir = settings.project_out_directory().join("bundle/nsis");
+  create_dir_all(&base_dir).with_context(|| "Failed to create the NSIS bundle directory")?;
+  let files = install_files(settings)?;
+  // makensis resolves the relative paths from the script directory
+  let cwd = std::env::current_dir()?;
+  let icon = cwd.join(&settings.windows().icon_path);
+  let bundle_path = base_dir.join(&bundle_name);
+  let script = generate_nsis_script(settings, arch, &files, &icon, &cwd.join(&bundle_path))?;
+  let script_path = base_dir.join("installer.nsi");
+  write(&script_path, script).with_context(|| "Failed to write the NSIS script")?;
+
+  let output = Command::new("makensis")
+    .arg("/V2")
+    .arg(&script_path)
+    .output()
+    .map_err(|e| match e.kind() {
+      io::ErrorKind::NotFound => crate::Error::ToolNotFound("makensis".into()),
+      _ => e.into(),
+    })?;
+  if !output.status.success() {
+    return Err(crate::Error::GenericError(format!(
+      "`makensis` failed: {}",
+      String::from_utf8_lossy(&output.stdout).trim()
+    )));
+  }
+
+  Ok(vec![bundle_path])
+}
+
+/// Gets the app binaries, the external binaries and the resources, with their path on the install directory.
+fn install_files(settings: &Settings) -> crate::Result<Vec<InstallFile>> {
+  let cwd = std::env::current_dir()?;
+  let mut files = Vec::new();
+  for bin in settings.binaries() {
+    let source = cwd.join(settings.binary_path(bin));
+    let destination = PathBuf::from(source.file_name().expect("failed to get binary file name"));
+    files.push(InstallFile {
+      source,
+      destination,
+    });
+  }
+  for src in settings.external_binaries() {
+    let src = src?;
+    files.push(InstallFile {
+      destination: PathBuf::from(
+        src
+          .file_name()
+          .expect("failed to extract external binary filename"),
+      ),
+      source: cwd.join(src),
+    });
+  }
+  for resource in settings.resources() {
+    let (src, dest) = resource?;
+    files.push(InstallFile {
+      destination: dest,
+      source: cwd.join(src),
+    });
+  }
+  Ok(files)
+}
+
+/// Generates the NSIS script of the installer.
+///
+/// The output only depends on the arguments, so the script can be checked without `makensis`.
+fn generate_nsis_script(
+  settings: &Settings,
+  arch: &str,
+  files: &[InstallFile],
+  icon: &Path,
+  output: &Path,
+) -> crate::Result<String> {
+  let nsis = &settings.windows().nsis;
+  let product_name = settings.product_name();
+  let identifier = settings.bundle_identifier();
+  if identifier.is_empty() {
+    return Err(crate::Error::GenericError(
+      "the NSIS installer requires a bundle identifier, its uninstaller is registered with it"
+        .into(),
+    ));
+  }
+  let main_binary = settings
+    .binaries()
+    .iter()
+    .find(|bin| bin.main())
+    .ok_or_else(|| anyhow::anyhow!("Failed to get main binary"))?;
+  let main_exe = settings
+    .binary_path(main_binary)
+    .file_name()
+    .expect("failed to get binary file name")
+    .to_string_lossy()
+    .to_string();
+  let install_dir = match &nsis.install_dir {
+    // the NSIS constants of the configured directory are kept
+    Some(install_dir) => format!("\"{}\"", install_dir.replace('"', "$\\\"")),
+    None => quote_with_prefix(
+      if arch == "x64" {
+        "$PROGRAMFILES64\\"
+      } else {
+        "$PROGRAMFILES\\"
+      },
+      product_name,
+    ),
+  };
+  let shortcut_name = format!("{}.lnk", product_name);
+
+  let mut script = String::new();
+  script.push_str("; generated by the Tauri bundler\n");
+  script.push_str("Unicode true\n");
+  script.push_str("!include \"MUI2.nsh\"\n\n");
+  script.push_str(&format!("Name {}\n", quote(product_name)));
+  script.push_str(&format!("OutFile {}\n", quote_path(output)));
+  script.push_str(&format!("InstallDir {}\n", install_dir));
+  script.push_str("RequestExecutionLevel admin\n");
+  script.push_str("SetCompressor /SOLID lzma\n\n");
+  script.push_str(&format!(
+    "VIProductVersion {}\n",
+    quote(&product_version(settings.version_string()))
+  ));
+  script.push_str(&format!(
+    "VIAddVersionKey \"ProductName\" {}\n",
+    quote(product_name)
+  ));
+  script.push_str(&format!(
+    "VIAddVersionKey \"ProductVersion\" {}\n",
+    quote(settings.version_string())
+  ));
+  script.push_str(&format!(
+    "VIAddVersionKey \"FileVersion\" {}\n",
+    quote(settings.version_string())
+  ));
+  script.push_str(&format!(
+    "VIAddVersionKey \"FileDescription\" {}\n",
+    quote(&format!("{} installer", product_name))
+  ));
+  if let Some(copyright) = settings.copyright_string() {
+    script.push_str(&format!(
+      "VIAddVersionKey \"LegalCopyright\" {}\n",
+      quote(copyright)
+    ));
+  }
+  script.push('\n');
+  script.push_str(&format!("!define MUI_ICON {}\n", quote_path(icon)));
+  script.push_str(&format!("!define MUI_UNICON {}\n", quote_path(icon)));
+  script.push_str("!insertmacro MUI_PAGE_DIRECTORY\n");
+  script.push_str("!insertmacro MUI_PAGE_INSTFILES\n");
+  script.push_str("!insertmacro MUI_UNPAGE_CONFIRM\n");
+  script.push_str("!insertmacro MUI_UNPAGE_INSTFILES\n");
+  script.push_str("!insertmacro MUI_LANGUAGE \"English\"\n\n");
+  script.push_str(&format!(
+    "!define UNINSTALL_KEY {}\n\n",
+    quote_with_prefix(
+      "Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\",
+      identifier
+    )
+  ));
+
+  // the files are grouped by directory since `File` writes to the current output path
+  let directories: BTreeSet<PathBuf> = files
+    .iter()
+    .map(|file| {
+      file
+        .destination
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+    })
+    .collect();
+
+  script.push_str("Section \"Install\"\n");
+  script.push_str("  SetShellVarContext all\n");
+  for directory in &directories {
+    script.push_str(&format!(
+      "  SetOutPath {}\n",
+      install_path((!directory.as_os_str().is_empty()).then(|| directory.as_path()))
+    ));
+    for file in files
+      .iter()
+      .filter(|file| file.destination.parent().unwrap_or_else(|| Path::new("")) == directory)
+    {
+      script.push_str(&format!("  File {}\n", quote_path(&file.source)));
+    }
+  }
+  script.push_str("  SetOutPath \"$INSTDIR\"\n");
+  script.push_str(&format!(
+    "  WriteUninstaller \"$INSTDIR\\{}\"\n",
+    UNINSTALLER_NAME
+  ));
+  let uninstall_values = [
+    ("DisplayName", quote(product_name)),
+    ("DisplayVersion", quote(settings.version_string())),
+    ("Publisher", quote(identifier)),
+    ("DisplayIcon", quote_with_prefix("$INSTDIR\\", &main_exe)),
+    (
+      "UninstallString",
+      format!("\"$\\\"$INSTDIR\\{}$\\\"\"", UNINSTALLER_NAME),
+    ),
+    ("InstallLocation", "\"$INSTDIR\"".to_string()),
+  ];
+  for (name, value) in &uninstall_values {
+    script.push_str(&format!(
+      "  WriteRegStr HKLM \"${{UNINSTALL_KEY}}\" \"{}\" {}\n",
+      name, value
+    ));
+  }
+  script.push_str("  WriteRegDWORD HKLM \"${UNINSTALL_KEY}\" \"NoModify\" 1\n");
+  script.push_str("  WriteRegDWORD HKLM \"${UNINSTALL_KEY}\" \"NoRepair\" 1\n");
+  if nsis.start_menu_shortcut {
+    script.push_str(&format!(
+      "  CreateShortCut {} {}\n",
+      quote_with_prefix("$SMPROGRAMS\\", &shortcut_name),
+      quote_with_prefix("$INSTDIR\\", &main_exe)
+    ));
+  }
+  if nsis.desktop_shortcut {
+    script.push_str(&format!(
+      "  CreateShortCut {} {}\n",
+      quote_with_prefix("$DESKTOP\\", &shortcut_name),
+      quote_with_prefix("$INSTDIR\\", &main_exe)
+    ));
+  }
+  script.push_str("SectionEnd\n\n");
+
+  // only the installed files are removed, so the files the app wrote to its install directory are kept
+  script.push_str("Section \"Uninstall\"\n");
+  script.push_str("  SetShellVarContext all\n");
+  for file in files {
+    script.push_str(&format!(
+      "  Delete {}\n",
+      install_path(Some(&file.destination))
+    ));
+  }
+  script.push_str(&format!("  Delete \"$INSTDIR\\{}\"\n", UNINSTALLER_NAME));
+  // the deepest directories are removed first, `RMDir` skips the directories that aren't empty
+  let mut subdirectories: BTreeSet<PathBuf> = BTreeSet::new();
+  for directory in &directories {
+    for ancestor in directory.ancestors() {
+      if !ancestor.as_os_str().is_empty() {
+        subdirectories.insert(ancestor.to_path_buf());
+      }
+    }
+  }
+  for directory in subdirectories.iter().rev() {
+    script.push_str(&format!("  RMDir {}\n", install_path(Some(directory))));
+  }
+  script.push_str("  RMDir \"$INSTDIR\"\n");
+  if nsis.start_menu_shortcut {
+    script.push_str(&format!(
+      "  Delete {}\n",
+      quote_with_prefix("$SMPROGRAMS\\", &shortcut_name)
+    ));
+  }
+  if nsis.desktop_shortcut {
+    script.push_str(&format!(
+      "  Delete {}\n",
+      quote_with_prefix("$DESKTOP\\", &shortcut_name)
+    ));
+  }
+  if nsis.delete_app_data_on_uninstall {
+    // the app data is written for the current user, see `tauri::api::path::app_dir`
+    script.push_str("  SetShellVarContext current\n");
+    script.push_str(&format!(
+      "  RMDir /r {}\n",
+      quote_with_prefix("$APPDATA\\", identifier)
+    ));
+  }
+  script.push_str("  DeleteRegKey HKLM \"${UNINSTALL_KEY}\"\n");
+  script.push_str("SectionEnd\n");
+  Ok(script)
+}
+
+/// Converts the app version to the `X.X.X.X` version of the installer metadata, dropping the prerelease and build metadata.
+fn product_version(version: &str) -> String {
+  let mut parts: Vec<&str> = version
+    .split(|c| c == '-' || c == '+')
+    .next()
+    .unwrap_or_default()
+    .split('.')
+    .collect();
+  parts.resize(4, "0");
+  parts
+    .iter()
+    .map(|part| if part.is_empty() { "0" } else { part })
+    .collect::<Vec<_>>()
+    .join(".")
+}
+
+/// Quotes a path relative to the install directory, or the install directory itself.
+fn install_path(path: Option<&Path>) -> String {
+  match path {
+    Some(path) => quote_with_prefix("$INSTDIR\\", &windows_path(path)),
+    None => "\"$INSTDIR\"".into(),
+  }
+}
+
+/// Quotes a path of the build machine.
+fn quote_path(path: &Path) -> String {
+  quote(&windows_path(path))
+}
+
+/// Joins the path components with backslashes, regardless of the host separator.
+fn windows_path(path: &Path) -> String {
+  path
+    .components()
+    .map(|component| component.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("\\")
+    .replace("\\\\", "\\")
+}
+
+/// Quotes an NSIS string: `$` starts a variable and `$\"` is a literal double quote.
+fn quote(value: &str) -> String {
+  quote_with_prefix("", value)
+}
+
+/// Quotes an NSIS string made of a prefix with NSIS constants, kept as is, and a literal value.
+fn quote_with_prefix(prefix: &str, value: &str) -> String {
+  format!(
+    "\"{}{}\"",
+    prefix,
+    value.replace('$', "$$").replace('"', "$\\\"")
+  )
+}
+
+/// Checks that `makensis` is installed, so a missing tool is reported before generating the bundle files.
+fn check_makensis() -> crate::Result<()> {
+  match Command::new("makensis").arg("/VERSION").output() {
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Err(crate::Error::GenericError(
+      "`makensis` not found. Install NSIS from https://nsis.sourceforge.io or with `choco install nsis`, and add its directory to the PATH".into(),
+    )),
+    _ => Ok(()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{generate_nsis_script, product_version, InstallFile};
+  use crate::{
+    bundle::settings::test_package_settings, BundleBinary, BundleSettings, NsisSettings,
+    PackageSettings, SettingsBuilder, WindowsSettings,
+  };
+  use std::path::{Path, PathBuf};
+
+  #[test]
+  fn nsis_script_generation() {
+    let settings = SettingsBuilder::new()
+      .project_out_directory("target/release")
+      .package_settings(PackageSettings {
+        product_name: "My App".into(),
+        version: "1.2.0-beta.1".into(),
+        ..test_package_settings()
+      })
+      .bundle_settings(BundleSettings {
+        identifier: Some("com.tauri.app".into()),
+        copyright: Some("Copyright \"Tauri\"".into()),
+        windows: WindowsSettings {
+          nsis: NsisSettings {
+            desktop_shortcut: true,
+            delete_app_data_on_uninstall: true,
+            ..Default::default()
+          },
+          ..Default::default()
+        },
+        ..Default::default()
+      })
+      .binaries(vec![BundleBinary::new("my-app.exe".into(), true)])
+      .build()
+      .expect("failed to build settings");
+    let files = vec![
+      InstallFile {
+        source: PathBuf::from("C:\\app\\target\\release\\my-app.exe"),
+        destination: PathBuf::from("my-app.exe"),
+      },
+      InstallFile {
+        source: PathBuf::from("C:\\app\\assets\\$data.json"),
+        destination: PathBuf::from("assets\\$data.json"),
+      },
+    ];
+
+    // only generates the script, makensis doesn't need to be installed
+    let script = generate_nsis_script(
+      &settings,
+      "x64",
+      &files,
+      Path::new("C:\\app\\icons\\icon.ico"),
+      Path::new("C:\\app\\target\\release\\bundle\\nsis\\my-app_1.2.0-beta.1_x64-setup.exe"),
+    )
+    .expect("failed to generate the NSIS script");
+    assert_eq!(
+      script,
+      r#"; generated by the Tauri bundler
+Unicode true
+!include "MUI2.nsh"
+
+Name "My App"
+OutFile "C:\app\target\release\bundle\nsis\my-app_1.2.0-beta.1_x64-setup.exe"
+InstallDir "$PROGRAMFILES64\My App"
+RequestExecutionLevel admin
+SetCompressor /SOLID lzma
+
+VIProductVersion "1.2.0.0"
+VIAddVersionKey "ProductName" "My App"
+VIAddVersionKey "ProductVersion" "1.2.0-beta.1"
+VIAddVersionKey "FileVersion" "1.2.0-beta.1"
+VIAddVersionKey "FileDescription" "My App installer"
+VIAddVersionKey "LegalCopyright" "Copyright $\"Tauri$\""
+
+!define MUI_ICON "C:\app\icons\icon.ico"
+!define MUI_UNICON "C:\app\icons\icon.ico"
+!insertmacro MUI_PAGE_DIRECTORY
+!insertmacro MUI_PAGE_INSTFILES
+!insertmacro MUI_UNPAGE_CONFIRM
+!insertmacro MUI_UNPAGE_INSTFILES
+!insertmacro MUI_LANGUAGE "English"
+
+!define UNINSTALL_KEY "Software\Microsoft\Windows\CurrentVersion\Uninstall\com.tauri.app"
+
+Section "Install"
+  SetShellVarContext all
+  SetOutPath "$INSTDIR"
+  File "C:\app\target\release\my-app.exe"
+  SetOutPath "$INSTDIR\assets"
+  File "C:\app\assets\$$data.json"
+  SetOutPath "$INSTDIR"
+  WriteUninstaller "$INSTDIR\uninstall.exe"
+  WriteRegStr HKLM "${UNINSTALL_KEY}" "DisplayName" "My App"
+  WriteRegStr HKLM "${UNINSTALL_KEY}" "DisplayVersion" "1.2.0-beta.1"
+  WriteRegStr HKLM "${UNINSTALL_KEY}" "Publisher" "com.tauri.app"
+  WriteRegStr HKLM "${UNINSTALL_KEY}" "DisplayIcon" "$INSTDIR\my-app.exe"
+  WriteRegStr HKLM "${UNINSTALL_KEY}" "UninstallString" "$\"$INSTDIR\uninstall.exe$\""
+  WriteRegStr HKLM "${UNINSTALL_KEY}" "InstallLocation" "$INSTDIR"
+  WriteRegDWORD HKLM "${UNINSTALL_KEY}" "NoModify" 1
+  WriteRegDWORD HKLM "${UNINSTALL_KEY}" "NoRepair" 1
+  CreateShortCut "$SMPROGRAMS\My App.lnk" "$INSTDIR\my-app.exe"
+  CreateShortCut "$DESKTOP\My App.lnk" "$INSTDIR\my-app.exe"
+SectionEnd
+
+Section "Uninstall"
+  SetShellVarContext all
+  Delete "$INSTDIR\my-app.exe"
+  Delete "$INSTDIR\assets\$$data.json"
+  Delete "$INSTDIR\uninstall.exe"
+  RMDir "$INSTDIR\assets"
+  RMDir "$INSTDIR"
+  Delete "$SMPROGRAMS\My App.lnk"
+  Delete "$DESKTOP\My App.lnk"
+  SetShellVarContext current
+  RMDir /r "$APPDATA\com.tauri.app"
+  DeleteRegKey HKLM "${UNINSTALL_KEY}"
+SectionEnd
+"#
+    );
+  }
+
+  #[test]
+  fn product_versions() {
+    assert_eq!(product_version("1.2.3"), "1.2.3.0");
+    assert_eq!(product_version("1.0.0-beta.1+build.5"), "1.0.0.0");
+    assert_eq!(product_version("2"), "2.0.0.0");
+  }
+}
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+use crate::bundle::{Bundle, PackageType};
+
 use std::{io, num, path};
 use thiserror::Error as DeriveError;
 
@@ -95,6 +97,42 @@ pub enum Error {
   /// Failed to sign application.
   #[error("failed to sign app: {0}")]
   Sign(String),
+  /// A tool required by the bundle settings isn't installed.
+  #[error("`{0}` not found, make sure it is installed and on the PATH")]
+  ToolNotFound(String),
+  /// An external binary of the bundle settings doesn't exist for the target.
+  #[error("external binary {name} not found, expected it at {path}")]
+  ExternalBinaryNotFound {
+    /// The external binary as configured, without the target triple suffix.
+    name: String,
+    /// The expected path of the binary, with the target triple suffix.
+    path: String,
+  },
+  /// A package type failed to bundle.
+  #[error("failed to bundle {}", .package_type.short_name())]
+  BundleFailed {
+    /// The package type that failed.
+    package_type: PackageType,
+    /// The error of the package type.
+    #[source]
+    source: Box<Error>,
+  },
+  /// Some package types failed to bundle with [`crate::SettingsBuilder::keep_going`] set.
+  #[error("failed to bundle {}", package_type_names(.failures))]
+  PartialBundle {
+    /// The bundles of the package types that succeeded.
+    bundles: Vec<Bundle>,
+    /// The package types that failed and their errors.
+    failures: Vec<(PackageType, Error)>,
+  },
+}
+
+fn package_type_names(failures: &[(PackageType, Error)]) -> String {
+  failures
+    .iter()
+    .map(|(package_type, _)| package_type.short_name())
+    .collect::<Vec<&str>>()
+    .join(", ")
 }
 
 /// Convenient type alias of Result type.
@@ -27,6 +27,137 @@ pub struct DebConfig {
   pub use_bootstrapper: bool,
   #[serde(default)]
   pub files: HashMap<PathBuf, PathBuf>,
+  /// The GPG key used to sign the `.deb` with `dpkg-sig`, e.g. its fingerprint or email address.
+  ///
+  /// The key must be on the keyring of the user running the build, its passphrase is requested by the GPG agent.
+  pub signing_key: Option<String>,
+  /// Whether to generate the `Packages` and `Release` files of a flat apt repository next to the `.deb`.
+  ///
+  /// The `Release` file is also signed as `Release.gpg` and `InRelease` when `signingKey` is set.
+  #[serde(default)]
+  pub repository_metadata: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FlatpakConfig {
+  /// The Flatpak runtime of the app, it must provide WebKitGTK.
+  #[serde(default = "default_flatpak_runtime")]
+  pub runtime: String,
+  /// The version of the runtime and the SDK.
+  #[serde(default = "default_flatpak_runtime_version")]
+  pub runtime_version: String,
+  /// The SDK matching the runtime.
+  #[serde(default = "default_flatpak_sdk")]
+  pub sdk: String,
+  /// The sandbox permissions of the app, i.e. the `finish-args` of the Flatpak manifest.
+  ///
+  /// Defaults to the IPC, network, X11, Wayland and GPU access.
+  #[serde(default = "default_flatpak_finish_args")]
+  pub finish_args: Vec<String>,
+}
+
+impl Default for FlatpakConfig {
+  fn default() -> Self {
+    Self {
+      runtime: default_flatpak_runtime(),
+      runtime_version: default_flatpak_runtime_version(),
+      sdk: default_flatpak_sdk(),
+      finish_args: default_flatpak_finish_args(),
+    }
+  }
+}
+
+fn default_flatpak_runtime() -> String {
+  "org.gnome.Platform".into()
+}
+
+fn default_flatpak_runtime_version() -> String {
+  "40".into()
+}
+
+fn default_flatpak_sdk() -> String {
+  "org.gnome.Sdk".into()
+}
+
+fn default_flatpak_finish_args() -> Vec<String> {
+  vec![
+    "--share=ipc".into(),
+    "--share=network".into(),
+    "--socket=x11".into(),
+    "--socket=wayland".into(),
+    "--device=dri".into(),
+  ]
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SnapConfig {
+  /// The snap confinement: `strict`, `classic` or `devmode`.
+  #[serde(default = "default_snap_confinement")]
+  pub confinement: String,
+  /// The snap grade: `stable` or `devel`. Only `stable` snaps can be released to the stable and candidate channels.
+  #[serde(default = "default_snap_grade")]
+  pub grade: String,
+  /// The interfaces the app connects to.
+  ///
+  /// Defaults to the desktop, display server, OpenGL and network access.
+  #[serde(default = "default_snap_plugs")]
+  pub plugs: Vec<String>,
+}
+
+impl Default for SnapConfig {
+  fn default() -> Self {
+    Self {
+      confinement: default_snap_confinement(),
+      grade: default_snap_grade(),
+      plugs: default_snap_plugs(),
+    }
+  }
+}
+
+fn default_snap_confinement() -> String {
+  "strict".into()
+}
+
+fn default_snap_grade() -> String {
+  "stable".into()
+}
+
+fn default_snap_plugs() -> Vec<String> {
+  vec![
+    "desktop".into(),
+    "desktop-legacy".into(),
+    "wayland".into(),
+    "x11".into(),
+    "opengl".into(),
+    "network".into(),
+  ]
+}
+
+#[skip_serializing_none]
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RpmConfig {
+  /// The GPG key used to sign the `.rpm` with `rpm --addsign`, e.g. its fingerprint or email address.
+  ///
+  /// The key must be on the keyring of the user running the build.
+  /// Its passphrase is read from the `TAURI_RPM_GPG_PASSWORD` environment variable if it's set, otherwise it's requested by the GPG agent.
+  pub gpg_key: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct LinuxConfig {
+  /// The Flatpak bundle settings.
+  #[serde(default)]
+  pub flatpak: FlatpakConfig,
+  /// The RPM bundle settings.
+  #[serde(default)]
+  pub rpm: RpmConfig,
+  /// The Snap bundle settings.
+  #[serde(default)]
+  pub snap: SnapConfig,
 }
 
 #[skip_serializing_none]
@@ -41,45 +172,152 @@ pub struct MacConfig {
   pub use_bootstrapper: bool,
   pub signing_identity: Option<String>,
   pub entitlements: Option<String>,
+  /// Whether the signed app and DMG should be submitted to Apple's notary service and stapled.
+  ///
+  /// The credentials are read from the `APPLE_ID`, `APPLE_PASSWORD` and `APPLE_TEAM_ID` environment variables.
+  #[serde(default)]
+  pub notarize: bool,
+  /// Additional `Info.plist` keys of the app, e.g. `CFBundleURLTypes` or `UIBackgroundModes`.
+  ///
+  /// The JSON values are converted to their plist equivalent, `null` isn't supported.
+  #[serde(default)]
+  pub info_plist: HashMap<String, JsonValue>,
 }
 
 #[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct WixConfig {
   pub template: Option<PathBuf>,
+  /// Paths to `.wxs` files with WiX fragments, relative to the `src-tauri` directory. They're compiled with the generated source, so their elements can be referenced with the `*Refs` options.
   #[serde(default)]
   pub fragment_paths: Vec<PathBuf>,
+  /// The ComponentGroup element ids to reference from the fragments.
   #[serde(default)]
   pub component_group_refs: Vec<String>,
+  /// The Component element ids to reference from the fragments, e.g. registry keys, firewall rules or shortcuts.
   #[serde(default)]
   pub component_refs: Vec<String>,
+  /// The FeatureGroup element ids to reference from the fragments.
   #[serde(default)]
   pub feature_group_refs: Vec<String>,
+  /// The Feature element ids to reference from the fragments.
   #[serde(default)]
   pub feature_refs: Vec<String>,
+  /// The Merge element ids to reference from the fragments.
   #[serde(default)]
   pub merge_refs: Vec<String>,
   #[serde(default)]
   pub skip_webview_install: bool,
+  /// The install scope of the MSI installer: `perMachine` or `perUser`.
+  ///
+  /// Defaults to `perMachine`, installing the app for every user on `Program Files`, which requires elevation. `perUser` installs it for the current user on `%LOCALAPPDATA%\Programs` without elevation, so it can't include the Visual C++ runtime of `includeVcRuntime`, which is installed per machine. Custom templates get the scope on the `install_scope` and `per_user` variables.
+  pub install_scope: Option<String>,
 }
 
-#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct NsisConfig {
+  /// The default install directory.
+  ///
+  /// Defaults to `$PROGRAMFILES64\<productName>` on 64-bit targets and `$PROGRAMFILES\<productName>` on 32-bit targets. The NSIS constants such as `$LOCALAPPDATA` are expanded when the installer runs. The installer registers its uninstaller for every user, so it always requires elevation.
+  pub install_dir: Option<String>,
+  /// Whether the installer creates a desktop shortcut.
+  #[serde(default)]
+  pub desktop_shortcut: bool,
+  /// Whether the installer creates a start menu shortcut.
+  #[serde(default = "default_start_menu_shortcut")]
+  pub start_menu_shortcut: bool,
+  /// Whether the uninstaller removes the app data directory, `$APPDATA\<identifier>`.
+  ///
+  /// The files the app writes to its install directory are kept either way.
+  #[serde(default)]
+  pub delete_app_data_on_uninstall: bool,
+}
+
+impl Default for NsisConfig {
+  fn default() -> Self {
+    Self {
+      install_dir: None,
+      desktop_shortcut: false,
+      start_menu_shortcut: default_start_menu_shortcut(),
+      delete_app_data_on_uninstall: false,
+    }
+  }
+}
+
+fn default_start_menu_shortcut() -> bool {
+  true
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct WindowsConfig {
   pub digest_algorithm: Option<String>,
   pub certificate_thumbprint: Option<String>,
   pub timestamp_url: Option<String>,
   pub wix: Option<WixConfig>,
+  /// The NSIS installer settings, used by the `nsis` bundle target.
+  #[serde(default)]
+  pub nsis: NsisConfig,
+  /// Whether the Visual C++ runtime merge module should be included on the MSI installer.
+  ///
+  /// Disable it if your app statically links the CRT or ships its own redistributable.
+  #[serde(default = "default_include_vc_runtime")]
+  pub include_vc_runtime: bool,
+  /// Paths to additional merge modules (`.msm` files) to include on the MSI installer, relative to the `src-tauri` directory.
+  #[serde(default)]
+  pub merge_modules: Vec<PathBuf>,
+  /// Path to the application manifest embedded in the executable, relative to the `src-tauri` directory.
+  ///
+  /// Use it to declare e.g. the DPI awareness or `longPathAware`. Requires `tauri_build::build()` on the app build script.
+  pub manifest: Option<PathBuf>,
+}
+
+impl Default for WindowsConfig {
+  fn default() -> Self {
+    Self {
+      digest_algorithm: None,
+      certificate_thumbprint: None,
+      timestamp_url: None,
+      wix: None,
+      nsis: NsisConfig::default(),
+      include_vc_runtime: default_include_vc_runtime(),
+      merge_modules: Vec::new(),
+      manifest: None,
+    }
+  }
+}
+
+fn default_include_vc_runtime() -> bool {
+  true
 }
 
 #[skip_serializing_none]
-#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct PackageConfig {
   /// App name. Automatically converted to kebab-case on Linux.
   pub product_name: Option<String>,
   /// App version.
   pub version: Option<String>,
+  /// Whether the compiled executable should be renamed to the product name or keep the cargo binary name.
+  #[serde(default = "default_rename_executable")]
+  pub rename_executable: bool,
+}
+
+impl Default for PackageConfig {
+  fn default() -> Self {
+    Self {
+      product_name: None,
+      version: None,
+      rename_executable: default_rename_executable(),
+    }
+  }
+}
+
+fn default_rename_executable() -> bool {
+  true
 }
 
 #[skip_serializing_none]
@@ -89,27 +327,47 @@ pub struct BundleConfig {
   /// Whether we should build your app with tauri-bundler or plain `cargo build`
   #[serde(default)]
   pub active: bool,
-  /// The bundle targets, currently supports ["deb", "app", "msi", "appimage", "dmg"] or "all"
+  /// The bundle targets, currently supports ["deb", "app", "msi", "nsis", "appimage", "dmg", "rpm", "targz", "tarxz", "flatpak", "snap"] or "all"
   pub targets: Option<BundleTarget>,
   /// The app's identifier
   pub identifier: Option<String>,
   /// The app's icons
   pub icon: Option<Vec<String>>,
-  /// App resources to bundle.
+  /// App resources to bundle, for all targets or keyed by target triple.
   /// Each resource is a path to a file or directory.
   /// Glob patterns are supported.
-  pub resources: Option<Vec<String>>,
+  /// An entry prefixed with `@` (e.g. `@resources.json`) references a resource manifest placing each file on an explicit destination:
+  /// a `.json` object mapping each source to its destination, or a text file with a `<source> -> <destination>` line per file.
+  /// The manifest sources are relative to the manifest, the destinations to the bundle resources directory.
+  pub resources: Option<BundleResources>,
+  /// Glob patterns of the resource files to leave out of the bundle, e.g. `**/*.map`.
+  pub resources_exclude: Option<Vec<String>>,
   pub copyright: Option<String>,
   pub category: Option<String>,
   pub short_description: Option<String>,
   pub long_description: Option<String>,
   #[serde(default)]
   pub deb: DebConfig,
+  /// The Linux bundles settings.
+  #[serde(default)]
+  pub linux: LinuxConfig,
   #[serde(rename = "macOS", default)]
   pub macos: MacConfig,
+  /// The external binaries (sidecars) to bundle next to the app binary.
+  ///
+  /// Each path is resolved with the target triple suffix of the build, and `.exe` on Windows targets,
+  /// e.g. `bin/sidecar` bundles `bin/sidecar-x86_64-unknown-linux-gnu`.
   pub external_bin: Option<Vec<String>>,
+  /// The template of the bundle file names, e.g. `{productName}_{version}_{arch}.{ext}`.
+  ///
+  /// The `{productName}`, `{version}`, `{arch}`, `{target}` (the target triple) and `{ext}` (e.g. `deb` or `app.tar.gz`) tokens
+  /// are replaced with the bundle values, and the characters that aren't allowed on file names are replaced with `_`.
+  /// Directory bundles such as the macOS `.app` keep their name.
+  pub file_name_template: Option<String>,
   #[serde(default)]
   pub windows: WindowsConfig,
+  /// The bundle size in megabytes above which the build prints a warning listing the largest files, defaults to 150.
+  pub size_warn_mb: Option<u64>,
 }
 
 /// A CLI argument definition
@@ -570,6 +828,10 @@ pub struct UpdaterConfig {
   pub endpoints: Option<Vec<String>>,
   /// Optional pubkey.
   pub pubkey: Option<String>,
+  /// The format of the updater archives: `gz` or `zip`.
+  ///
+  /// Defaults to a `.tar.gz` of the app on macOS and Linux and to an uncompressed `.zip` of the installer on Windows. `gz` writes a `.msi.gz` on Windows and `zip` compresses the installer with deflate, it's only supported on Windows since the updater can't restore the executable permissions of the macOS and Linux apps. The archives are signed after being compressed.
+  pub archive_format: Option<String>,
 }
 
 #[skip_serializing_none]
@@ -590,12 +852,146 @@ fn default_dialog() -> Option<bool> {
   Some(true)
 }
 
+/// A shell command, a list of shell commands to run sequentially or the commands to run on each platform.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum HookCommand {
+  /// A list of commands, executed in order.
+  List(Vec<String>),
+  /// A single command.
+  Single(String),
+  /// The commands to run on each platform.
+  Platform(PlatformHookCommand),
+}
+
+impl HookCommand {
+  /// Gets the list of commands to run. A single command is mapped to a one-element list.
+  #[allow(dead_code)]
+  pub fn to_vec(&self) -> Vec<String> {
+    match self {
+      Self::List(commands) => commands.clone(),
+      Self::Single(command) => vec![command.clone()],
+      Self::Platform(platform) => platform
+        .host_command()
+        .map(HookCommand::to_vec)
+        .unwrap_or_default(),
+    }
+  }
+}
+
+/// The commands of a hook keyed by platform.
+#[skip_serializing_none]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PlatformHookCommand {
+  /// The command(s) to run on Windows.
+  pub windows: Option<Box<HookCommand>>,
+  /// The command(s) to run on macOS.
+  pub macos: Option<Box<HookCommand>>,
+  /// The command(s) to run on Linux.
+  pub linux: Option<Box<HookCommand>>,
+  /// The command(s) to run if the current platform has no entry.
+  pub default: Option<Box<HookCommand>>,
+}
+
+impl PlatformHookCommand {
+  /// Gets the command(s) of the host platform, falling back to the `default` entry.
+  #[allow(dead_code)]
+  fn host_command(&self) -> Option<&HookCommand> {
+    let command = if cfg!(target_os = "windows") {
+      &self.windows
+    } else if cfg!(target_os = "macos") {
+      &self.macos
+    } else if cfg!(target_os = "linux") {
+      &self.linux
+    } else {
+      &None
+    };
+    command
+      .as_ref()
+      .or_else(|| self.default.as_ref())
+      .map(AsRef::as_ref)
+  }
+}
+
+/// A list of rustc flags, as a single whitespace separated string or an array.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum RustFlagList {
+  /// A list of flags.
+  List(Vec<String>),
+  /// A whitespace separated string of flags.
+  Single(String),
+}
+
+impl RustFlagList {
+  /// Gets the list of flags, splitting the single string form on whitespace.
+  #[allow(dead_code)]
+  pub fn to_vec(&self) -> Vec<String> {
+    match self {
+      Self::List(flags) => flags.clone(),
+      Self::Single(flags) => flags.split_whitespace().map(ToString::to_string).collect(),
+    }
+  }
+}
+
+/// The rustc flags used to compile the app, for all targets or keyed by target triple.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum RustFlags {
+  /// The flags used for all targets.
+  All(RustFlagList),
+  /// The flags of each target triple. The `default` key is used for the targets without an entry.
+  Target(HashMap<String, RustFlagList>),
+}
+
+impl RustFlags {
+  /// Gets the flags of the given target triple, falling back to the `default` entry.
+  #[allow(dead_code)]
+  pub fn for_target(&self, target: Option<&str>) -> Vec<String> {
+    match self {
+      Self::All(flags) => flags.to_vec(),
+      Self::Target(targets) => target
+        .and_then(|target| targets.get(target))
+        .or_else(|| targets.get("default"))
+        .map(RustFlagList::to_vec)
+        .unwrap_or_default(),
+    }
+  }
+}
+
+/// The app resources, for all targets or keyed by target triple.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum BundleResources {
+  /// The resources bundled for all targets.
+  All(Vec<String>),
+  /// The resources of each target triple, e.g. the native libraries of each architecture. The `default` key is used for the targets without an entry.
+  Target(HashMap<String, Vec<String>>),
+}
+
+impl BundleResources {
+  /// Gets the resources of the given target triple, falling back to the `default` entry.
+  #[allow(dead_code)]
+  pub fn for_target(&self, target: Option<&str>) -> Vec<String> {
+    match self {
+      Self::All(resources) => resources.clone(),
+      Self::Target(targets) => target
+        .and_then(|target| targets.get(target))
+        .or_else(|| targets.get("default"))
+        .cloned()
+        .unwrap_or_default(),
+    }
+  }
+}
+
 /// The Build configuration object.
 #[skip_serializing_none]
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct BuildConfig {
-  /// The binary used to build and run the application.
+  /// The command used to build and run the application, e.g. `cross` or `sccache cargo`.
+  /// Arguments containing whitespace can be wrapped in single or double quotes.
   pub runner: Option<String>,
   /// the app's dev server URL, or the path to the directory containing an index.html file
   #[serde(default = "default_dev_path")]
@@ -605,8 +1001,29 @@ pub struct BuildConfig {
   pub dist_dir: String,
   /// a shell command to run before `tauri dev` kicks in
   pub before_dev_command: Option<String>,
-  /// a shell command to run before `tauri build` kicks in
-  pub before_build_command: Option<String>,
+  /// a shell command (or a list of shell commands, executed sequentially) to run before `tauri build` kicks in, optionally keyed by platform (`windows`, `macos`, `linux` or `default`)
+  pub before_build_command: Option<HookCommand>,
+  /// glob patterns of the files read by the `beforeBuildCommand`, relative to the app directory.
+  ///
+  /// Along with `beforeBuildOutputs`, the command is skipped if every output is newer than every input.
+  pub before_build_inputs: Option<Vec<String>>,
+  /// glob patterns of the files written by the `beforeBuildCommand`, relative to the app directory.
+  ///
+  /// Along with `beforeBuildInputs`, the command is skipped if every output is newer than every input.
+  pub before_build_outputs: Option<Vec<String>>,
+  /// a shell command to run after `tauri build` finishes bundling your app.
+  ///
+  /// It receives the `TAURI_OUT_DIR` (the out directory of each target), `TAURI_PRODUCT_NAME`, `TAURI_TARGET_TRIPLE` (the triple of each target)
+  /// and `TAURI_BUNDLE_PATHS` (the path of each bundle) environment variables, lists being newline separated.
+  pub after_build_command: Option<String>,
+  /// rustc flags (a string, an array or a map of target triple to flags) appended to the `RUSTFLAGS` environment variable of the `tauri build` compilation.
+  ///
+  /// Since they're appended, they take precedence over conflicting flags set on `RUSTFLAGS`.
+  /// Note that cargo ignores the `build.rustflags` of its config files when `RUSTFLAGS` is set.
+  pub rust_flags: Option<RustFlags>,
+  /// Whether `tauri build` strips the symbols of the app binary before bundling, like the `--strip` flag.
+  #[serde(default)]
+  pub strip: bool,
   /// Whether we should inject the Tauri API on `window.__TAURI__` or not.
   #[serde(default)]
   pub with_global_tauri: bool,
@@ -648,6 +1065,11 @@ fn default_build() -> BuildConfig {
     dist_dir: default_dist_dir(),
     before_dev_command: None,
     before_build_command: None,
+    before_build_inputs: None,
+    before_build_outputs: None,
+    after_build_command: None,
+    rust_flags: None,
+    strip: false,
     with_global_tauri: false,
   }
 }
@@ -658,5 +1080,6 @@ fn default_updater() -> UpdaterConfig {
     dialog: Some(true),
     endpoints: None,
     pubkey: None,
+    archive_format: None,
   }
 }
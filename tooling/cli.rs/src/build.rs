@@ -3,29 +3,154 @@
 // SPDX-License-Identifier: MIT
 
 use anyhow::Context;
-use tauri_bundler::bundle::{bundle_project, PackageType, SettingsBuilder};
+use glob::Pattern;
+use serde_json::{json, Value as JsonValue};
+use tauri_bundler::bundle::{
+  bundle_project, Bundle, PackageType, SettingsBuilder, UpdaterArchiveFormat, WixInstallScope,
+};
 
 use crate::helpers::{
   app_paths::{app_dir, tauri_dir},
-  config::get as get_config,
+  config::{
+    get as get_config, reload as reload_config, set_cache_dir as set_config_cache_dir,
+    set_cache_enabled as set_config_cache_enabled, Config, RustFlags, UpdaterConfig, WindowsConfig,
+  },
   execute_with_output,
-  manifest::rewrite_manifest,
-  updater_signature::sign_file_from_env_variables,
+  manifest::{rewrite_manifest, ManifestStatus},
+  progress::Progress,
+  updater_signature::{
+    print_signed_updater_archive, sign_updater_archives, signing_keys_from_env_variables,
+    signing_keys_from_paths, DEFAULT_SIGN_ATTEMPTS,
+  },
   Logger,
 };
 
-use std::{env::set_current_dir, fs::rename, path::PathBuf, process::Command};
+use std::{
+  collections::HashMap,
+  env::{current_dir, set_current_dir, var_os},
+  ffi::OsString,
+  fs::{copy, create_dir_all, read_dir, read_to_string, remove_dir, remove_file, rename},
+  path::{Path, PathBuf},
+  process::Command,
+  time::Instant,
+};
+
+mod bundle_size;
+mod checksums;
+mod cleanup;
+mod error;
+mod explain;
+mod file_name;
+mod fingerprint;
+mod gatekeeper;
+mod icons;
+mod report;
+mod resource_manifest;
+pub mod rust;
+mod sbom;
+mod staleness;
+mod strip;
+mod symbols;
+mod timings;
+mod watch;
+mod xml;
 
-mod rust;
+use bundle_size::{warn_oversized_bundles, DEFAULT_SIZE_WARN_MB};
+use checksums::{write_checksums, ChecksumAlgorithm};
+use cleanup::CleanupGuard;
+pub use error::BuildError;
+use explain::explain_targets;
+use file_name::{rename_bundles, ILLEGAL_CHARACTERS};
+use fingerprint::BundleFingerprint;
+use gatekeeper::assess;
+use icons::validate_icons;
+use report::BuildReport;
+use sbom::write_sbom;
+use staleness::{check_staleness, Staleness};
+use strip::{can_strip, strip_binary};
+use symbols::write_symbols_archive;
+use timings::Timings;
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct Build {
   runner: Option<String>,
   debug: bool,
-  verbose: bool,
-  target: Option<String>,
+  profile: Option<String>,
+  jobs: Option<u32>,
+  bin: Option<String>,
+  verbose: u8,
+  targets: Vec<String>,
   bundles: Option<Vec<String>>,
-  config: Option<String>,
+  config: Vec<String>,
+  no_bundle: bool,
+  json: bool,
+  out_dir: Option<PathBuf>,
+  target_dir: Option<PathBuf>,
+  timings: bool,
+  frozen: bool,
+  offline: bool,
+  locked: bool,
+  features: Vec<String>,
+  no_default_features: bool,
+  all_features: bool,
+  raw_cargo: bool,
+  args: Vec<String>,
+  sign_retries: Option<u32>,
+  universal: bool,
+  dry_run: bool,
+  explain_target: bool,
+  list_bundles: bool,
+  print_config: bool,
+  allow_unsigned_updater: bool,
+  checksums: Option<String>,
+  checksum_files: bool,
+  strict: bool,
+  strip: bool,
+  debug_symbols: bool,
+  include_debug_symbols: bool,
+  gatekeeper: bool,
+  bundle_only: bool,
+  private_keys: Vec<PathBuf>,
+  report: Option<PathBuf>,
+  clean: bool,
+  bundle_identifier_suffix: Option<String>,
+  force: bool,
+  product_name: Option<String>,
+  app_version: Option<String>,
+  allow_debug_updater: bool,
+  quiet: bool,
+  keep_going: bool,
+  sbom: bool,
+  reproducible: bool,
+  watch: bool,
+  no_config_cache: bool,
+  since: Option<String>,
+  /// whether the config must be read again, set by the watch mode when tauri.conf.json changes.
+  reload_config: bool,
+}
+
+/// The artifacts of a build, returned by [`Build::run_with`].
+pub struct BundleResult {
+  /// The bundles of every target, with their paths on the `--out-dir` if it's set.
+  pub bundles: Vec<Bundle>,
+  /// The signatures of the updater archives.
+  pub signatures: Vec<PathBuf>,
+  /// The package types that failed to bundle with `--keep-going`, and their errors.
+  pub failures: Vec<(PackageType, tauri_bundler::Error)>,
+  /// The duration of each build phase.
+  pub timings: Timings,
+}
+
+impl BundleResult {
+  /// The result of the modes that print the config or the targets without building.
+  fn empty(timings: Timings) -> Self {
+    Self {
+      bundles: Vec::new(),
+      signatures: Vec::new(),
+      failures: Vec::new(),
+      timings,
+    }
+  }
 }
 
 impl Build {
@@ -38,8 +163,26 @@ impl Build {
     self
   }
 
-  pub fn verbose(mut self) -> Self {
-    self.verbose = true;
+  pub fn profile(mut self, profile: String) -> Self {
+    self.profile.replace(profile);
+    self
+  }
+
+  /// Sets the number of parallel cargo jobs (`--jobs`), leaving the cargo default if unset.
+  pub fn jobs(mut self, jobs: u32) -> Self {
+    self.jobs.replace(jobs);
+    self
+  }
+
+  /// Builds and bundles only the given cargo binary, as the main binary of the app.
+  pub fn bin(mut self, bin: String) -> Self {
+    self.bin.replace(bin);
+    self
+  }
+
+  /// Sets the verbosity level. Level 1 enables the bundler verbose logging and level 2 also prints the resolved bundler settings.
+  pub fn verbose(mut self, level: u8) -> Self {
+    self.verbose = level;
     self
   }
 
@@ -48,8 +191,8 @@ impl Build {
     self
   }
 
-  pub fn target(mut self, target: String) -> Self {
-    self.target.replace(target);
+  pub fn targets(mut self, targets: Vec<String>) -> Self {
+    self.targets = targets;
     self
   }
 
@@ -58,172 +201,2178 @@ impl Build {
     self
   }
 
-  pub fn config(mut self, config: String) -> Self {
-    self.config.replace(config);
+  pub fn config(mut self, config: Vec<String>) -> Self {
+    self.config = config;
     self
   }
 
-  pub fn run(self) -> crate::Result<()> {
-    let logger = Logger::new("tauri:build");
-    let config = get_config(self.config.as_deref())?;
+  pub fn no_bundle(mut self) -> Self {
+    self.no_bundle = true;
+    self
+  }
 
-    let tauri_path = tauri_dir();
-    set_current_dir(&tauri_path).with_context(|| "failed to change current working directory")?;
+  pub fn json(mut self) -> Self {
+    self.json = true;
+    self
+  }
 
-    let manifest = rewrite_manifest(config.clone())?;
+  pub fn out_dir(mut self, out_dir: String) -> Self {
+    self.out_dir.replace(PathBuf::from(out_dir));
+    self
+  }
 
-    let config_guard = config.lock().unwrap();
-    let config_ = config_guard.as_ref().unwrap();
+  pub fn target_dir(mut self, target_dir: String) -> Self {
+    self.target_dir.replace(PathBuf::from(target_dir));
+    self
+  }
 
-    if let Some(before_build) = &config_.build.before_build_command {
-      if !before_build.is_empty() {
-        logger.log(format!("Running `{}`", before_build));
-        #[cfg(target_os = "windows")]
-        execute_with_output(
-          &mut Command::new("cmd")
-            .arg("/C")
-            .arg(before_build)
-            .current_dir(app_dir()),
-        )
-        .with_context(|| format!("failed to run `{}` with `cmd /C`", before_build))?;
-        #[cfg(not(target_os = "windows"))]
-        execute_with_output(
-          &mut Command::new("sh")
-            .arg("-c")
-            .arg(before_build)
-            .current_dir(app_dir()),
-        )
-        .with_context(|| format!("failed to run `{}` with `sh -c`", before_build))?;
+  pub fn timings(mut self) -> Self {
+    self.timings = true;
+    self
+  }
+
+  pub fn frozen(mut self) -> Self {
+    self.frozen = true;
+    self
+  }
+
+  pub fn offline(mut self) -> Self {
+    self.offline = true;
+    self
+  }
+
+  pub fn locked(mut self) -> Self {
+    self.locked = true;
+    self
+  }
+
+  pub fn features(mut self, features: Vec<String>) -> Self {
+    self.features = features;
+    self
+  }
+
+  pub fn no_default_features(mut self) -> Self {
+    self.no_default_features = true;
+    self
+  }
+
+  pub fn all_features(mut self) -> Self {
+    self.all_features = true;
+    self
+  }
+
+  pub fn raw_cargo(mut self) -> Self {
+    self.raw_cargo = true;
+    self
+  }
+
+  pub fn args(mut self, args: Vec<String>) -> Self {
+    self.args = args;
+    self
+  }
+
+  pub fn sign_retries(mut self, sign_retries: u32) -> Self {
+    self.sign_retries = Some(sign_retries);
+    self
+  }
+
+  pub fn universal(mut self) -> Self {
+    self.universal = true;
+    self
+  }
+
+  pub fn dry_run(mut self) -> Self {
+    self.dry_run = true;
+    self
+  }
+
+  /// Prints how the toolchain of the build targets is resolved instead of building.
+  pub fn explain_target(mut self) -> Self {
+    self.explain_target = true;
+    self
+  }
+
+  /// Prints the bundle formats supported on this platform instead of building.
+  pub fn list_bundles(mut self) -> Self {
+    self.list_bundles = true;
+    self
+  }
+
+  /// Prints the resolved config, with the `--config`, `--product-name` and `--app-version` overrides merged, without building.
+  pub fn print_config(mut self) -> Self {
+    self.print_config = true;
+    self
+  }
+
+  /// The target triples to build, the universal macOS arches with `--universal`. Empty for a host build.
+  fn target_triples(&self) -> Vec<String> {
+    if self.universal {
+      rust::UNIVERSAL_MACOS_ARCHES
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+    } else {
+      self.targets.clone()
+    }
+  }
+
+  pub fn allow_unsigned_updater(mut self) -> Self {
+    self.allow_unsigned_updater = true;
+    self
+  }
+
+  pub fn checksums(mut self, algorithm: String) -> Self {
+    self.checksums.replace(algorithm);
+    self
+  }
+
+  pub fn checksum_files(mut self) -> Self {
+    self.checksum_files = true;
+    self
+  }
+
+  pub fn strict(mut self) -> Self {
+    self.strict = true;
+    self
+  }
+
+  pub fn bundle_only(mut self) -> Self {
+    self.bundle_only = true;
+    self
+  }
+
+  /// Adds a private key file to sign the updater archives with, each key produces its own signature.
+  pub fn private_key(mut self, private_key: String) -> Self {
+    self.private_keys.push(PathBuf::from(private_key));
+    self
+  }
+
+  pub fn report(mut self, report: String) -> Self {
+    self.report.replace(PathBuf::from(report));
+    self
+  }
+
+  pub fn clean(mut self) -> Self {
+    self.clean = true;
+    self
+  }
+
+  pub fn bundle_identifier_suffix(mut self, suffix: String) -> Self {
+    self.bundle_identifier_suffix.replace(suffix);
+    self
+  }
+
+  pub fn force(mut self) -> Self {
+    self.force = true;
+    self
+  }
+
+  pub fn product_name(mut self, product_name: String) -> Self {
+    self.product_name.replace(product_name);
+    self
+  }
+
+  /// Overrides the `package > version` config, e.g. to stamp nightly builds, without changing the config files.
+  pub fn app_version(mut self, app_version: String) -> Self {
+    self.app_version.replace(app_version);
+    self
+  }
+
+  pub fn allow_debug_updater(mut self) -> Self {
+    self.allow_debug_updater = true;
+    self
+  }
+
+  pub fn quiet(mut self) -> Self {
+    self.quiet = true;
+    self
+  }
+
+  pub fn keep_going(mut self) -> Self {
+    self.keep_going = true;
+    self
+  }
+
+  pub fn sbom(mut self) -> Self {
+    self.sbom = true;
+    self
+  }
+
+  /// Strips the symbols of the compiled app binary before bundling.
+  pub fn strip(mut self) -> Self {
+    self.strip = true;
+    self
+  }
+
+  /// Zips the debug symbols of the app binary (the macOS dSYM or the Windows PDB) to `<product>-symbols.zip`.
+  pub fn debug_symbols(mut self) -> Self {
+    self.debug_symbols = true;
+    self
+  }
+
+  /// Ships the debug symbols archive next to each updater archive as `<archive>.symbols.zip`.
+  ///
+  /// The updater archives themselves never include the symbols, so the updates stay small.
+  pub fn include_debug_symbols(mut self) -> Self {
+    self.include_debug_symbols = true;
+    self
+  }
+
+  /// Assesses the macOS `.app` and `.dmg` bundles with Gatekeeper after bundling.
+  pub fn gatekeeper(mut self) -> Self {
+    self.gatekeeper = true;
+    self
+  }
+
+  /// Makes the archive bundles reproducible, using `SOURCE_DATE_EPOCH` or the Unix epoch as their timestamps.
+  pub fn reproducible(mut self) -> Self {
+    self.reproducible = true;
+    self
+  }
+
+  /// Rebuilds the app each time the Rust sources, the config or the distDir change.
+  pub fn watch(mut self) -> Self {
+    self.watch = true;
+    self
+  }
+
+  pub fn no_config_cache(mut self) -> Self {
+    self.no_config_cache = true;
+    self
+  }
+
+  /// Skips the build if the tauri directory and the distDir didn't change since the given git ref.
+  pub fn since(mut self, git_ref: String) -> Self {
+    self.since.replace(git_ref);
+    self
+  }
+
+  pub fn run(mut self) -> crate::Result<()> {
+    // the formats `--bundle all` expands to, it doesn't need a project
+    if self.list_bundles {
+      let width = PackageType::all()
+        .iter()
+        .map(|package_type| package_type.short_name().len())
+        .max()
+        .unwrap_or_default();
+      for package_type in PackageType::all() {
+        println!(
+          "{:width$}  {}",
+          package_type.short_name(),
+          package_type.description(),
+          width = width
+        );
       }
+      return Ok(());
+    }
+    if self.watch {
+      self.watch = false;
+      return watch::run(self);
     }
+    // the config and the targets are printed without building
+    if self.print_config || self.explain_target {
+      self.run_with()?;
+      return Ok(());
+    }
+    let json = self.json;
+    let print_timings = self.timings;
+    let result = self.run_with()?;
 
-    let web_asset_path = PathBuf::from(&config_.build.dist_dir);
-    if !web_asset_path.exists() {
+    let logger = Logger::new("tauri:build");
+    if print_timings {
+      logger.log(format!("Finished in {}", result.timings.summary()));
+    }
+
+    if json {
+      print_json_output(&result.bundles, &result.signatures)?;
+    }
+
+    if !result.failures.is_empty() {
+      let succeeded: Vec<&str> = result
+        .bundles
+        .iter()
+        .map(|bundle| bundle.package_type.short_name())
+        .collect();
+      logger.log(format!(
+        "Bundled: {}",
+        if succeeded.is_empty() {
+          "none".to_string()
+        } else {
+          succeeded.join(", ")
+        }
+      ));
+      for (package_type, error) in &result.failures {
+        logger.error(format!(
+          "Failed to bundle {}: {}",
+          package_type.short_name(),
+          error
+        ));
+      }
+      let failed: Vec<&str> = result
+        .failures
+        .iter()
+        .map(|(package_type, _)| package_type.short_name())
+        .collect();
+      return Err(
+        BuildError::BundlesFailed {
+          package_types: failed.join(", "),
+        }
+        .into(),
+      );
+    }
+
+    Ok(())
+  }
+
+  /// Runs the build, returning its artifacts instead of printing them.
+  ///
+  /// The progress is still logged, but the `--json` output, the `--timings` summary and the `--keep-going` failures
+  /// are left to the caller: the package types that failed to bundle are returned on [`BundleResult::failures`] instead of an error.
+  /// The `--list-bundles` and `--watch` modes only make sense on the command line, so they're ignored.
+  pub fn run_with(self) -> crate::Result<BundleResult> {
+    let mut timings = Timings::new();
+    if self.json {
+      Logger::stderr_only();
+    }
+    if self.debug && self.profile.is_some() {
       return Err(anyhow::anyhow!(
-        "Unable to find your web assets, did you forget to build your web app? Your distDir is set to \"{:?}\".",
-        web_asset_path
+        "`--debug` and `--profile` can't be used together, use `--profile dev` to build with the debug profile"
       ));
     }
+    if self.frozen && self.offline {
+      return Err(anyhow::anyhow!(
+        "`--frozen` already implies `--offline`, use only one of them"
+      ));
+    }
+    rust::validate_runner_args(&self.args)?;
+    // mobile targets would otherwise fail deep in cargo with a linker error
+    if let Some(target) = self
+      .targets
+      .iter()
+      .find(|target| rust::is_unsupported_mobile_target(target))
+    {
+      return Err(BuildError::UnsupportedMobileTarget(target.clone()).into());
+    }
+    // validate the bundle formats before spending time compiling the app
+    let bundle_selection = match &self.bundles {
+      Some(values) => Some(BundleSelection::parse(values)?),
+      None => None,
+    };
+    // the archive timestamps, `SOURCE_DATE_EPOCH` is honored even without `--reproducible`
+    let source_date_epoch = match var_os("SOURCE_DATE_EPOCH") {
+      Some(epoch) => Some(epoch.to_string_lossy().parse::<u64>().with_context(|| {
+        format!(
+          "invalid `SOURCE_DATE_EPOCH` value: {}, expected a Unix timestamp",
+          epoch.to_string_lossy()
+        )
+      })?),
+      None if self.reproducible => Some(0),
+      None => None,
+    };
+    let checksum_algorithm = match &self.checksums {
+      Some(algorithm) => Some(algorithm.parse::<ChecksumAlgorithm>()?),
+      None => None,
+    };
+    let logger = Logger::new("tauri:build");
+    // the product name and version overrides are merged on top of the config so the app and the bundles get them
+    let mut merge_configs = self.config.clone();
+    if let Some(product_name) = &self.product_name {
+      validate_product_name(product_name)?;
+      merge_configs.push(json!({ "package": { "productName": product_name } }).to_string());
+    }
+    if let Some(app_version) = &self.app_version {
+      semver::Version::parse(app_version).with_context(|| {
+        format!(
+          "The app version `{}` isn't a valid semver version, e.g. `1.2.3-nightly.20240101`",
+          app_version
+        )
+      })?;
+      merge_configs.push(json!({ "package": { "version": app_version } }).to_string());
+    }
+    // resolve the output and target directories before changing the current working directory
+    let cwd = current_dir().with_context(|| "failed to read the current working directory")?;
+    let bundle_out_dir = self.out_dir.as_ref().map(|dir| cwd.join(dir));
+    let target_dir = self.target_dir.as_ref().map(|dir| cwd.join(dir));
+
+    set_config_cache_enabled(!self.no_config_cache);
+    // a relative `CARGO_TARGET_DIR` is resolved from the tauri dir, where cargo runs
+    set_config_cache_dir(tauri_dir().join(rust::get_cargo_target_dir(target_dir.as_deref())?));
+    if self.reload_config {
+      reload_config(&merge_configs)?;
+    }
+    let config = get_config(&merge_configs)?;
+
+    if self.print_config {
+      let config_guard = config.lock().unwrap();
+      println!(
+        "{}",
+        serde_json::to_string_pretty(config_guard.as_ref().unwrap())?
+      );
+      return Ok(BundleResult::empty(timings));
+    }
+
+    let private_key_paths: Vec<PathBuf> = self
+      .private_keys
+      .iter()
+      .map(|path| cwd.join(path))
+      .collect();
+    let report_path = self.report.as_ref().map(|path| cwd.join(path));
+
+    let tauri_path = tauri_dir();
+    set_current_dir(&tauri_path).with_context(|| "failed to change current working directory")?;
 
-    let runner_from_config = config_.build.runner.clone();
+    let runner_from_config = config
+      .lock()
+      .unwrap()
+      .as_ref()
+      .unwrap()
+      .build
+      .runner
+      .clone();
     let runner = self
       .runner
+      .clone()
       .or(runner_from_config)
       .unwrap_or_else(|| "cargo".to_string());
 
-    rust::build_project(runner, &self.target, self.debug).with_context(|| "failed to build app")?;
-
-    let app_settings = rust::AppSettings::new(&config_)?;
-
-    let out_dir = app_settings
-      .get_out_dir(self.debug)
-      .with_context(|| "failed to get project out directory")?;
-    if let Some(product_name) = config_.package.product_name.clone() {
-      let bin_name = app_settings.cargo_package_settings().name.clone();
-      #[cfg(windows)]
-      rename(
-        out_dir.join(format!("{}.exe", bin_name)),
-        out_dir.join(format!("{}.exe", product_name)),
-      )?;
-      #[cfg(not(windows))]
-      rename(out_dir.join(bin_name), out_dir.join(product_name))?;
-    }
-
-    if config_.tauri.bundle.active {
-      // move merge modules to the out dir so the bundler can load it
-      #[cfg(windows)]
-      {
-        let (filename, vcruntime_msm) = if cfg!(target_arch = "x86") {
-          let _ = std::fs::remove_file(out_dir.join("Microsoft_VC142_CRT_x64.msm"));
-          (
-            "Microsoft_VC142_CRT_x86.msm",
-            include_bytes!("../MergeModules/Microsoft_VC142_CRT_x86.msm").to_vec(),
-          )
-        } else {
-          let _ = std::fs::remove_file(out_dir.join("Microsoft_VC142_CRT_x86.msm"));
-          (
-            "Microsoft_VC142_CRT_x64.msm",
-            include_bytes!("../MergeModules/Microsoft_VC142_CRT_x64.msm").to_vec(),
-          )
-        };
-        std::fs::write(out_dir.join(filename), vcruntime_msm)?;
+    if self.explain_target {
+      explain_targets(&runner, &self.target_triples(), &tauri_path, &logger);
+      return Ok(BundleResult::empty(timings));
+    }
+
+    // skip the build early if the app sources didn't change, always building if git can't tell
+    if let Some(since) = &self.since {
+      let dist_dir = tauri_path.join(&config.lock().unwrap().as_ref().unwrap().build.dist_dir);
+      match changed_since(since, &[&tauri_path, &dist_dir]) {
+        Some(false) => {
+          logger.log(format!(
+            "No changes since `{}` on {} and {}, skipping the build",
+            since,
+            tauri_path.display(),
+            dist_dir.display()
+          ));
+          return Ok(BundleResult::empty(timings));
+        }
+        Some(true) => {}
+        None => logger.warn(format!(
+          "Failed to list the changes since `{}` with git, building anyway",
+          since
+        )),
       }
-      let mut settings_builder = SettingsBuilder::new()
-        .package_settings(app_settings.get_package_settings())
-        .bundle_settings(app_settings.get_bundle_settings(&config_, &manifest)?)
-        .binaries(app_settings.get_binaries(&config_)?)
-        .project_out_directory(out_dir);
+    }
+
+    // report an invalid or missing runner before rewriting Cargo.toml and running the beforeBuildCommand
+    let (runner_program, _) = rust::parse_runner(&runner)?;
+    if !self.bundle_only && !rust::is_in_path(&runner_program) {
+      return Err(BuildError::RunnerNotFound(runner_program).into());
+    }
 
-      if self.verbose {
-        settings_builder = settings_builder.verbose();
+    let manifest = if self.dry_run {
+      logger.log("Would update the tauri features on Cargo.toml");
+      None
+    } else {
+      let (manifest, status) = rewrite_manifest(config.clone())?;
+      if status == ManifestStatus::Rewritten {
+        logger.log("Updated the tauri features on Cargo.toml");
+        if let (true, Some(diff)) = (self.verbose > 0, &manifest.diff) {
+          logger.log(format!("Cargo.toml changes:\n{}", diff.trim_end()));
+        }
       }
+      Some(manifest)
+    };
 
-      if let Some(names) = self.bundles {
-        let mut types = vec![];
-        for name in names {
-          if name == "none" {
-            break;
-          }
-          match PackageType::from_short_name(&name) {
-            Some(package_type) => {
-              types.push(package_type);
-            }
-            None => {
-              return Err(anyhow::anyhow!(format!(
-                "Unsupported bundle format: {}",
-                name
-              )));
+    let config_guard = config.lock().unwrap();
+    let config_ = config_guard.as_ref().unwrap();
+
+    // `None` builds for the host target
+    let targets: Vec<Option<String>> = if self.universal {
+      if !cfg!(target_os = "macos") {
+        return Err(anyhow::anyhow!("`--universal` is only supported on macOS"));
+      }
+      rust::check_lipo_installed()?;
+      if runner == "cargo" {
+        rust::check_targets_installed(&self.target_triples())?;
+      }
+      vec![Some(rust::UNIVERSAL_MACOS_TARGET.to_string())]
+    } else if self.targets.is_empty() {
+      vec![None]
+    } else {
+      // custom runners such as `cross` provide their own toolchains
+      if runner == "cargo" {
+        rust::check_targets_installed(&self.targets)?;
+        for target in &self.targets {
+          if let Some(message) = rust::check_cross_toolchain(target, &tauri_path) {
+            if self.strict {
+              return Err(anyhow::anyhow!(message));
             }
+            logger.warn(message);
           }
         }
+      }
+      self.targets.iter().cloned().map(Some).collect()
+    };
 
-        settings_builder = settings_builder.package_types(types);
+    // the bundle formats of each target, `None` bundles the formats of the config
+    let target_package_types: Vec<Option<Vec<PackageType>>> = match &bundle_selection {
+      Some(selection) => {
+        let host_triple = rust::host_triple();
+        let target_triples: Vec<Option<&str>> = targets
+          .iter()
+          .map(|target| target.as_deref().or_else(|| host_triple.as_deref()))
+          .collect();
+        selection.validate_targets(&target_triples)?;
+        target_triples
+          .iter()
+          .map(|triple| selection.for_target(*triple))
+          .collect()
       }
+      None => vec![None; targets.len()],
+    };
 
-      // Bundle the project
-      let settings = settings_builder
-        .build()
-        .with_context(|| "failed to build bundler settings")?;
+    // validate the updater config before spending time compiling the app
+    let bundles_updater = !self.no_bundle
+      && config_.tauri.bundle.active
+      && config_.tauri.updater.active
+      && target_package_types.iter().any(|package_types| {
+        package_types
+          .as_ref()
+          .map(|types| types.contains(&PackageType::Updater))
+          .unwrap_or(true)
+      });
+    let mut sign_updater = bundles_updater && config_.tauri.updater.pubkey.is_some();
+    if bundles_updater {
+      validate_updater_archive_format(config_.tauri.updater.archive_format.as_deref())?;
+      if let Err(e) = validate_updater_config(&config_.tauri.updater, &private_key_paths) {
+        if !self.allow_unsigned_updater {
+          return Err(e.context("invalid updater configuration, use `--allow-unsigned-updater` to bundle the updater archives anyway"));
+        }
+        logger.warn(format!("{}, the updater archives won't be signed", e));
+        sign_updater = false;
+      }
+    }
+    if self.include_debug_symbols {
+      if !bundles_updater {
+        logger.warn(
+          "`--include-debug-symbols` only applies to the updater archives, which aren't bundled",
+        );
+      } else if !(self.strip || config_.build.strip) {
+        let message = "`--include-debug-symbols` without `--strip` leaves the symbols on the app binary too, so the updater archives aren't symbol-free";
+        if self.strict {
+          return Err(anyhow::anyhow!(message));
+        }
+        logger.warn(message);
+      }
+    }
 
-      let bundles = bundle_project(settings).with_context(|| "failed to bundle project")?;
+    if self.gatekeeper && !cfg!(target_os = "macos") {
+      logger.warn("`--gatekeeper` is only supported on macOS, the bundles won't be assessed");
+    }
 
-      // If updater is active and pubkey is available
-      if config_.tauri.updater.active && config_.tauri.updater.pubkey.is_some() {
-        // make sure we have our package builts
-        let mut signed_paths = Vec::new();
-        for elem in bundles
-          .iter()
-          .filter(|bundle| bundle.package_type == PackageType::Updater)
-        {
-          // we expect to have only one path in the vec but we iter if we add
-          // another type of updater package who require multiple file signature
-          for path in elem.bundle_paths.iter() {
-            // sign our path from environment variables
-            let (signature_path, _signature) = sign_file_from_env_variables(path)?;
-            signed_paths.append(&mut vec![signature_path]);
+    let debug_build = self.debug || matches!(self.profile.as_deref(), Some("dev") | Some("test"));
+    if debug_build && !self.no_bundle && config_.tauri.bundle.active {
+      if bundles_updater && !self.allow_debug_updater {
+        return Err(anyhow::anyhow!(
+          "Refusing to bundle the updater archives of a debug build, since they would be installed as an update. Use `--allow-debug-updater` to bundle them anyway."
+        ));
+      }
+      if !self.quiet {
+        logger.warn(
+          "Bundling a debug build: the artifacts are unoptimized and include debug information, don't distribute them.",
+        );
+      }
+    }
+
+    let bundle_identifier = match &self.bundle_identifier_suffix {
+      Some(suffix) => Some(suffixed_identifier(
+        config_.tauri.bundle.identifier.as_deref(),
+        suffix,
+      )?),
+      None => None,
+    };
+
+    if cfg!(windows) && !self.no_bundle && config_.tauri.bundle.active {
+      validate_merge_modules(&config_.tauri.bundle.windows.merge_modules)?;
+      validate_wix_install_scope(&config_.tauri.bundle.windows)?;
+      if let Some(wix) = &config_.tauri.bundle.windows.wix {
+        validate_wix_fragments(&wix.fragment_paths)?;
+      }
+    }
+    if let Some(manifest) = &config_.tauri.bundle.windows.manifest {
+      validate_windows_manifest(manifest)?;
+    }
+    validate_dist_dir(&config_.build.dist_dir)?;
+    if cfg!(target_os = "linux") && config_.package.rename_executable {
+      if let Some(product_name) = &config_.package.product_name {
+        if product_name.contains(char::is_whitespace) {
+          logger.warn(format!(
+            "The product name `{}` contains whitespace, so the Linux executable it's renamed to is awkward to launch from a terminal and to reference on the `.desktop` file. Consider naming your cargo binary `{}` and setting `package > renameExecutable` to false, the product name is still used as the app name",
+            product_name,
+            hyphenated_binary_name(product_name)
+          ));
+        }
+      }
+    }
+    validate_info_plist(&config_.tauri.bundle.macos.info_plist)?;
+
+    // the web assets are already embedded on the compiled binary when only bundling
+    let before_build_command = if self.bundle_only {
+      None
+    } else {
+      config_.build.before_build_command.as_ref()
+    };
+    let before_build_command = match (
+      before_build_command,
+      &config_.build.before_build_inputs,
+      &config_.build.before_build_outputs,
+    ) {
+      (Some(before_build), Some(inputs), Some(outputs)) => {
+        match check_staleness(inputs, outputs, app_dir())? {
+          Staleness::UpToDate => {
+            logger.log(
+              "Skipping the beforeBuildCommand, its outputs are newer than its inputs (`build > beforeBuildInputs` and `build > beforeBuildOutputs`)",
+            );
+            None
+          }
+          Staleness::Stale(reason) => {
+            logger.log(format!("Running the beforeBuildCommand since {}", reason));
+            Some(before_build)
           }
         }
-        if !signed_paths.is_empty() {
-          print_signed_updater_archive(&signed_paths)?;
+      }
+      (Some(before_build), None, Some(_)) | (Some(before_build), Some(_), None) => {
+        logger.warn("`build > beforeBuildInputs` and `build > beforeBuildOutputs` must both be set to skip the beforeBuildCommand, running it");
+        Some(before_build)
+      }
+      (before_build, _, _) => before_build,
+    };
+    if let Some(before_build) = before_build_command {
+      let started = Instant::now();
+      let commands = before_build.to_vec();
+      for (index, before_build) in commands.iter().enumerate() {
+        if !before_build.is_empty() {
+          logger.log(format!(
+            "{} `{}` ({}/{})",
+            if self.dry_run { "Would run" } else { "Running" },
+            before_build,
+            index + 1,
+            commands.len()
+          ));
+          if !self.dry_run {
+            execute_shell_command(before_build, Vec::new())?;
+          }
         }
       }
+      timings.record("before-build", started);
     }
 
-    Ok(())
-  }
-}
+    let web_asset_path = PathBuf::from(&config_.build.dist_dir);
+    // on dry run the assets may not exist yet since the beforeBuildCommand doesn't run
+    if !self.dry_run
+      && !self.bundle_only
+      && (!web_asset_path.exists()
+        || !contains_web_assets(&web_asset_path, &web_asset_path, &read_ignore_patterns()?)?)
+    {
+      return Err(BuildError::MissingDistDir(web_asset_path).into());
+    }
 
-fn print_signed_updater_archive(output_paths: &[PathBuf]) -> crate::Result<()> {
-  let pluralised = if output_paths.len() == 1 {
-    "updater archive"
-  } else {
-    "updater archives"
-  };
-  let msg = format!("{} {} at:", output_paths.len(), pluralised);
-  let logger = Logger::new("Signed");
-  logger.log(&msg);
-  for path in output_paths {
-    println!("        {}", path.display());
-  }
-  Ok(())
+    let mut app_settings = rust::AppSettings::new(&config_)?;
+    if let Some(bin) = &self.bin {
+      app_settings.select_binary(bin)?;
+    }
+
+    // removes the temporary merge modules and the partial bundles if the build fails or is interrupted
+    let cleanup_guard = CleanupGuard::new();
+
+    let mut bundles = Vec::new();
+    let mut signed_archives = Vec::new();
+    let mut bundle_failures = Vec::new();
+    let mut out_dirs = Vec::new();
+
+    let dry_run = self.dry_run;
+    let bundle_only = self.bundle_only;
+    let strip = self.strip || config_.build.strip;
+    let debug_symbols = self.debug_symbols;
+    let mut envs = build_constants(
+      bundle_identifier
+        .as_deref()
+        .or_else(|| config_.tauri.bundle.identifier.as_deref()),
+      &app_settings.get_package_settings().version,
+      config_.tauri.updater.active,
+    );
+    // embedded by `tauri_build::build()` on the app build script
+    if let Some(manifest) = &config_.tauri.bundle.windows.manifest {
+      envs.push((
+        "TAURI_WINDOWS_MANIFEST".into(),
+        tauri_path.join(manifest).display().to_string(),
+      ));
+    }
+    let base_build_options = rust::BuildOptions {
+      target: None,
+      target_dir: target_dir.clone(),
+      debug: self.debug,
+      profile: self.profile.clone(),
+      jobs: self.jobs,
+      bin: self.bin.clone(),
+      frozen: self.frozen,
+      offline: self.offline,
+      locked: self.locked,
+      features: self.features.clone(),
+      no_default_features: self.no_default_features,
+      all_features: self.all_features,
+      raw_output: self.raw_cargo,
+      rust_flags: Vec::new(),
+      envs,
+      args: self.args.clone(),
+    };
+    let cargo_target_dir = rust::get_cargo_target_dir(target_dir.as_deref())?;
+    check_dist_dir_location(&web_asset_path, &cargo_target_dir, self.strict, &logger)?;
+
+    if !self.features.is_empty() || self.no_default_features || self.all_features {
+      logger.log(format!(
+        "Cargo features: {}",
+        base_build_options.features_description()
+      ));
+    }
+
+    // compiles the app for the given target, returning the directory of the (renamed) binaries
+    let compile = |target: Option<&str>, timings: &mut Timings| -> crate::Result<PathBuf> {
+      if let Some(target) = target {
+        logger.log(format!("Building for {}", target));
+      }
+      // the per target flags of host builds are resolved with the host triple
+      let rust_flags = match &config_.build.rust_flags {
+        Some(rust_flags @ RustFlags::Target(_)) if target.is_none() => {
+          rust_flags.for_target(rust::host_triple().as_deref())
+        }
+        Some(rust_flags) => rust_flags.for_target(target),
+        None => Vec::new(),
+      };
+      let build_options = rust::BuildOptions {
+        target: target.map(ToString::to_string),
+        rust_flags,
+        ..base_build_options.clone()
+      };
+      let out_dir = app_settings
+        .get_out_dir(target, target_dir.as_deref(), build_options.profile_dir())
+        .with_context(|| "failed to get project out directory")?;
+      if bundle_only {
+        check_compiled_binary(&app_settings, &config_, &out_dir)?;
+      } else if dry_run {
+        logger.log(format!(
+          "Would run {:?}",
+          rust::build_command(&runner, &build_options)?
+        ));
+      } else {
+        let started = Instant::now();
+        // the raw runner output is written to the terminal directly, so the spinner would garble it
+        let progress = if build_options.raw_output {
+          None
+        } else {
+          Some(Progress::start("Compiling the app"))
+        };
+        rust::build_project(runner.clone(), &build_options)
+          .with_context(|| "failed to build app")?;
+        drop(progress);
+        timings.record("compile", started);
+      }
+
+      // the symbols are collected before the binary is renamed and stripped
+      if debug_symbols {
+        let bin_name = app_settings.main_binary_name();
+        let product_name = config_.package.product_name.as_deref().unwrap_or(bin_name);
+        if dry_run {
+          logger.log(format!(
+            "Would write the debug symbols to {}",
+            out_dir
+              .join(format!("{}-symbols.zip", product_name))
+              .display()
+          ));
+        } else {
+          let archive_path = write_symbols_archive(&out_dir, target, bin_name, product_name)
+            .with_context(|| "failed to write the debug symbols archive")?;
+          logger.log(format!(
+            "Wrote the debug symbols to {}",
+            archive_path.display()
+          ));
+        }
+      }
+
+      let product_name = config_.package.product_name.clone();
+      if let (Some(product_name), true) = (product_name, config_.package.rename_executable) {
+        let bin_name = app_settings.main_binary_name().to_string();
+        if dry_run {
+          logger.log(format!(
+            "Would rename the `{}` binary to `{}`",
+            bin_name, product_name
+          ));
+        } else {
+          rename_app(&out_dir, &bin_name, &product_name)?;
+        }
+      }
+
+      if strip {
+        let bin_name = match (
+          &config_.package.product_name,
+          config_.package.rename_executable,
+        ) {
+          (Some(product_name), true) => product_name.clone(),
+          _ => app_settings.main_binary_name().to_string(),
+        };
+        let host_triple = target.and_then(|_| rust::host_triple());
+        if !can_strip(target, host_triple.as_deref()) {
+          logger.log(format!(
+            "Skipping `--strip` for {}, its symbols can't be stripped with the host `strip`",
+            target.unwrap_or("the host target")
+          ));
+        } else if dry_run {
+          logger.log(format!("Would strip the `{}` binary", bin_name));
+        } else {
+          let (original_size, stripped_size) = strip_binary(&out_dir.join(&bin_name))?;
+          let megabytes = |size: u64| size as f64 / (1024.0 * 1024.0);
+          logger.log(format!(
+            "Stripped the `{}` binary from {:.1} MB to {:.1} MB",
+            bin_name,
+            megabytes(original_size),
+            megabytes(stripped_size)
+          ));
+        }
+      }
+      Ok(out_dir)
+    };
+
+    for (target, package_types) in targets.iter().zip(&target_package_types) {
+      let out_dir = if self.universal {
+        let mut arch_out_dirs = Vec::new();
+        for arch in &rust::UNIVERSAL_MACOS_ARCHES {
+          arch_out_dirs.push(compile(Some(*arch), &mut timings)?);
+        }
+        let out_dir = app_settings
+          .get_out_dir(
+            target.as_deref(),
+            target_dir.as_deref(),
+            base_build_options.profile_dir(),
+          )
+          .with_context(|| "failed to get project out directory")?;
+        if self.dry_run {
+          logger.log(format!(
+            "Would create the universal binaries at {}",
+            out_dir.display()
+          ));
+        } else {
+          create_dir_all(&out_dir)
+            .with_context(|| format!("failed to create directory {}", out_dir.display()))?;
+          for binary in app_settings.get_binaries(&config_)? {
+            let inputs: Vec<PathBuf> = arch_out_dirs
+              .iter()
+              .map(|dir| dir.join(binary.name()))
+              .collect();
+            rust::lipo(&inputs, &out_dir.join(binary.name()))?;
+          }
+          logger.log(format!(
+            "Created the universal binaries at {}",
+            out_dir.display()
+          ));
+        }
+        out_dir
+      } else {
+        compile(target.as_deref(), &mut timings)?
+      };
+
+      if self.sbom {
+        if self.dry_run {
+          logger.log(format!("Would write the SBOM to {}", out_dir.display()));
+        } else {
+          let mut cargo_args = Vec::new();
+          if self.frozen {
+            cargo_args.push("--frozen");
+          }
+          if self.offline {
+            cargo_args.push("--offline");
+          }
+          if self.locked {
+            cargo_args.push("--locked");
+          }
+          let package_settings = app_settings.get_package_settings();
+          // the universal binaries include the dependencies of every arch
+          let sbom_target = if self.universal {
+            None
+          } else {
+            target.as_deref()
+          };
+          let sbom_path = write_sbom(
+            &package_settings.product_name,
+            &package_settings.version,
+            sbom_target,
+            &cargo_args,
+            &out_dir,
+          )?;
+          logger.log(format!("Wrote the SBOM to {}", sbom_path.display()));
+        }
+      }
+
+      let mut target_bundles = Vec::new();
+      let mut target_signed_archives = Vec::new();
+
+      if self.no_bundle {
+        if let Some(main_binary) = app_settings
+          .get_binaries(&config_)?
+          .iter()
+          .find(|bin| bin.main())
+        {
+          logger.log(format!(
+            "Skipping bundling, your app binary is located at {}",
+            out_dir.join(main_binary.name()).display()
+          ));
+        }
+      } else if config_.tauri.bundle.active && self.dry_run {
+        let types = match package_types.clone() {
+          Some(types) => without_inactive_updater(types, config_.tauri.updater.active)?,
+          None => {
+            let mut types = PackageType::platform_defaults()?;
+            if config_.tauri.updater.active {
+              types.push(PackageType::Updater);
+            }
+            types
+          }
+        };
+        let names: Vec<&str> = types.iter().map(PackageType::short_name).collect();
+        if self.clean {
+          logger.log(format!(
+            "Would remove the previous bundles at {}",
+            out_dir.join("bundle").display()
+          ));
+        }
+        logger.log(format!(
+          "Would bundle the app at {} as {}",
+          out_dir.display(),
+          names.join(", ")
+        ));
+      } else if config_.tauri.bundle.active {
+        let manifest = manifest
+          .as_ref()
+          .expect("Cargo.toml is only left untouched on dry run");
+        // move merge modules to the out dir so the bundler can load it
+        #[cfg(windows)]
+        {
+          if config_.tauri.bundle.windows.include_vc_runtime {
+            let (filename, vcruntime_msm) = if cfg!(target_arch = "x86") {
+              let _ = std::fs::remove_file(out_dir.join("Microsoft_VC142_CRT_x64.msm"));
+              (
+                "Microsoft_VC142_CRT_x86.msm",
+                include_bytes!("../MergeModules/Microsoft_VC142_CRT_x86.msm").to_vec(),
+              )
+            } else {
+              let _ = std::fs::remove_file(out_dir.join("Microsoft_VC142_CRT_x86.msm"));
+              (
+                "Microsoft_VC142_CRT_x64.msm",
+                include_bytes!("../MergeModules/Microsoft_VC142_CRT_x64.msm").to_vec(),
+              )
+            };
+            std::fs::write(out_dir.join(filename), vcruntime_msm)?;
+            cleanup_guard.track_file(out_dir.join(filename));
+          } else {
+            // the bundler references every merge module on the out dir, so remove stale copies
+            let _ = std::fs::remove_file(out_dir.join("Microsoft_VC142_CRT_x86.msm"));
+            let _ = std::fs::remove_file(out_dir.join("Microsoft_VC142_CRT_x64.msm"));
+          }
+          for merge_module in &config_.tauri.bundle.windows.merge_modules {
+            cleanup_guard.track_file(copy_to_dir(merge_module, &out_dir)?);
+          }
+        }
+        let package_settings = app_settings.get_package_settings();
+        let mut bundle_settings =
+          app_settings.get_bundle_settings(&config_, manifest, target.as_deref())?;
+        if let Some(identifier) = &bundle_identifier {
+          bundle_settings.identifier.replace(identifier.clone());
+        }
+        let binaries = app_settings.get_binaries(&config_)?;
+        let fingerprint_inputs = format!(
+          "{:?}\n{}\n{:?}\n{:?}\n{:?}",
+          package_settings,
+          serde_json::to_value(&config_.tauri)?,
+          bundle_settings.identifier,
+          package_types,
+          source_date_epoch
+        );
+        if self.verbose > 1 {
+          logger.log(format!("Package settings: {:#?}", package_settings));
+          logger.log(format!("Bundle settings: {:#?}", bundle_settings));
+          logger.log(format!("Binaries: {:#?}", binaries));
+          logger.log(format!("Project out directory: {}", out_dir.display()));
+        }
+        let mut settings_builder = SettingsBuilder::new()
+          .package_settings(package_settings)
+          .bundle_settings(bundle_settings)
+          .binaries(binaries)
+          .project_out_directory(&out_dir);
+
+        if self.verbose > 0 {
+          settings_builder = settings_builder.verbose();
+        }
+
+        if self.keep_going {
+          settings_builder = settings_builder.keep_going();
+        }
+
+        if let Some(source_date_epoch) = source_date_epoch {
+          settings_builder = settings_builder.source_date_epoch(source_date_epoch);
+        }
+
+        // the external binaries are resolved with the target triple suffix
+        if let Some(target) = target {
+          settings_builder = settings_builder.target(target.clone());
+        }
+
+        if let Some(types) = package_types.clone() {
+          settings_builder = settings_builder.package_types(without_inactive_updater(
+            types,
+            config_.tauri.updater.active,
+          )?);
+        }
+
+        // Bundle the project
+        let started = Instant::now();
+        let settings = settings_builder
+          .build()
+          .with_context(|| "failed to build bundler settings")?;
+        // the icons are validated for the formats the bundler builds, an invalid selection is reported by the bundler
+        if let Ok(package_types) = settings.package_types() {
+          let problems = validate_icons(
+            config_.tauri.bundle.icon.as_deref().unwrap_or_default(),
+            &package_types,
+          );
+          if !problems.is_empty() {
+            let message = format!("Invalid bundle icons:\n  {}", problems.join("\n  "));
+            if self.strict {
+              return Err(anyhow::anyhow!(message));
+            }
+            logger.warn(message);
+          }
+        }
+        let excluded_resources = settings.excluded_resources_count();
+        if excluded_resources > 0 {
+          logger.log(format!(
+            "Excluded {} resource file(s) matching `tauri > bundle > resourcesExclude`",
+            excluded_resources
+          ));
+        }
+
+        // skip the bundler if the binary and the bundle settings didn't change since the last run
+        let fingerprint = BundleFingerprint::new(&settings, &fingerprint_inputs)?;
+        let cached_bundles = if self.force || self.clean {
+          None
+        } else {
+          fingerprint.cached_bundles(&out_dir)
+        };
+        if let Some(cached_bundles) = cached_bundles {
+          logger.log(
+            "The app and its bundle settings didn't change, reusing the previous bundles (use `--force` to bundle again)",
+          );
+          for path in cached_bundles
+            .iter()
+            .flat_map(|bundle| &bundle.bundle_paths)
+          {
+            logger.log(format!("        {}", path.display()));
+          }
+          target_bundles = cached_bundles;
+        } else {
+          let bundle_dir = out_dir.join("bundle");
+          if self.clean {
+            clean_bundle_dir(&bundle_dir, &logger)?;
+          }
+          cleanup_guard.track_dir(bundle_dir.clone());
+          let progress = Progress::start("Bundling the app");
+          // the settings are kept to describe the oversized bundles
+          let result = bundle_project(settings.clone());
+          drop(progress);
+          cleanup_guard.untrack_dir(&bundle_dir);
+          let bundle_target = target
+            .clone()
+            .or_else(rust::host_triple)
+            .unwrap_or_default();
+          let rename = |bundles: &mut [Bundle]| match &config_.tauri.bundle.file_name_template {
+            Some(template) => rename_bundles(bundles, template, &settings, &bundle_target),
+            None => Ok(()),
+          };
+          match result {
+            Ok(result) => {
+              target_bundles = result;
+              rename(&mut target_bundles)?;
+              fingerprint.save(&out_dir, &target_bundles)?;
+            }
+            // with `--keep-going` the successful bundles are kept and the failures reported at the end
+            Err(tauri_bundler::Error::PartialBundle { bundles, failures }) => {
+              target_bundles = bundles;
+              rename(&mut target_bundles)?;
+              bundle_failures.extend(failures);
+            }
+            Err(e) => return Err(BuildError::BundleFailed(e).into()),
+          }
+          warn_oversized_bundles(
+            &target_bundles,
+            &settings,
+            config_
+              .tauri
+              .bundle
+              .size_warn_mb
+              .unwrap_or(DEFAULT_SIZE_WARN_MB),
+            &logger,
+          );
+        }
+        timings.record("bundle", started);
+
+        if self.gatekeeper && cfg!(target_os = "macos") {
+          let started = Instant::now();
+          let macos_bundle_paths = target_bundles
+            .iter()
+            .filter(|bundle| {
+              matches!(
+                bundle.package_type,
+                PackageType::MacOsBundle | PackageType::Dmg
+              )
+            })
+            .flat_map(|bundle| &bundle.bundle_paths);
+          for path in macos_bundle_paths {
+            let assessment = assess(path)?;
+            if assessment.accepted {
+              logger.log(format!(
+                "Gatekeeper accepted {}: {}",
+                path.display(),
+                assessment.details
+              ));
+            } else {
+              let message = format!(
+                "Gatekeeper rejected {}: {}",
+                path.display(),
+                assessment.details
+              );
+              if self.strict {
+                return Err(anyhow::anyhow!(message));
+              }
+              logger.warn(message);
+            }
+          }
+          timings.record("gatekeeper", started);
+        }
+
+        // If updater is active and pubkey is available
+        if sign_updater {
+          // make sure we have our package builts
+          // we expect to have only one path per updater bundle but we iter if we add
+          // another type of updater package who require multiple file signature
+          let updater_paths = target_bundles
+            .iter()
+            .filter(|bundle| bundle.package_type == PackageType::Updater)
+            .flat_map(|bundle| bundle.bundle_paths.clone())
+            .collect();
+          let started = Instant::now();
+          let progress = Progress::start("Signing the updater archives");
+          let keys = if private_key_paths.is_empty() {
+            signing_keys_from_env_variables().map_err(BuildError::SigningFailed)?
+          } else {
+            signing_keys_from_paths(&private_key_paths)
+          };
+          target_signed_archives = sign_updater_archives(
+            updater_paths,
+            keys,
+            self.sign_retries.unwrap_or(DEFAULT_SIGN_ATTEMPTS),
+          )
+          .map_err(BuildError::SigningFailed)?;
+          drop(progress);
+          timings.record("sign", started);
+        }
+
+        // the symbols are shipped next to the updater archives, after signing so they don't get a signature
+        if self.include_debug_symbols {
+          let product_name = config_
+            .package
+            .product_name
+            .as_deref()
+            .unwrap_or_else(|| app_settings.main_binary_name());
+          let symbols_archive = out_dir.join(format!("{}-symbols.zip", product_name));
+          for bundle in target_bundles
+            .iter_mut()
+            .filter(|bundle| bundle.package_type == PackageType::Updater)
+          {
+            let mut symbols_paths = Vec::new();
+            for path in &bundle.bundle_paths {
+              let symbols_path = PathBuf::from(format!("{}.symbols.zip", path.display()));
+              std::fs::copy(&symbols_archive, &symbols_path).with_context(|| {
+                format!(
+                  "failed to copy the debug symbols {} to {}",
+                  symbols_archive.display(),
+                  symbols_path.display()
+                )
+              })?;
+              symbols_paths.push(symbols_path);
+            }
+            bundle.bundle_paths.extend(symbols_paths);
+          }
+        }
+      }
+
+      // the bundles are left on the project out directory unless `--out-dir` is set
+      let mut artifacts_dir = out_dir.clone();
+      if let Some(bundle_out_dir) = &bundle_out_dir {
+        // each target gets its own subdirectory so the artifacts don't overwrite each other
+        let bundle_out_dir = match target {
+          Some(target) if targets.len() > 1 => bundle_out_dir.join(target),
+          _ => bundle_out_dir.clone(),
+        };
+        if self.dry_run {
+          logger.log(format!(
+            "Would copy the bundles to {}",
+            bundle_out_dir.display()
+          ));
+        } else {
+          create_dir_all(&bundle_out_dir).with_context(|| {
+            format!(
+              "failed to create output directory {}",
+              bundle_out_dir.display()
+            )
+          })?;
+          for path in target_bundles
+            .iter_mut()
+            .flat_map(|bundle| bundle.bundle_paths.iter_mut())
+            .chain(
+              target_signed_archives
+                .iter_mut()
+                .flat_map(|signed_archive| signed_archive.signatures.iter_mut()),
+            )
+          {
+            *path = copy_to_dir(path, &bundle_out_dir)?;
+          }
+          // the signed archives were copied with the bundles
+          for signed_archive in &mut target_signed_archives {
+            signed_archive.archive = bundle_out_dir.join(
+              signed_archive
+                .archive
+                .file_name()
+                .expect("failed to get bundle file name"),
+            );
+          }
+          logger.log(format!(
+            "Copied the bundles to {}",
+            bundle_out_dir.display()
+          ));
+        }
+        artifacts_dir = bundle_out_dir;
+      }
+
+      if let Some(algorithm) = checksum_algorithm {
+        // app bundles such as the macOS `.app` are directories and can't be hashed
+        let bundle_files: Vec<PathBuf> = target_bundles
+          .iter()
+          .flat_map(|bundle| bundle.bundle_paths.iter())
+          .filter(|path| path.is_file())
+          .cloned()
+          .collect();
+        if self.dry_run {
+          logger.log(format!(
+            "Would write the {} checksums of the bundles to {}",
+            algorithm,
+            artifacts_dir.display()
+          ));
+        } else if !bundle_files.is_empty() {
+          let started = Instant::now();
+          let checksums_path = write_checksums(
+            algorithm,
+            &bundle_files,
+            &artifacts_dir,
+            self.checksum_files,
+          )?;
+          timings.record("checksums", started);
+          logger.log(format!(
+            "Wrote the {} checksums of {} bundles to {}",
+            algorithm,
+            bundle_files.len(),
+            checksums_path.display()
+          ));
+        }
+      }
+
+      bundles.append(&mut target_bundles);
+      signed_archives.append(&mut target_signed_archives);
+      out_dirs.push(out_dir);
+    }
+
+    if !signed_archives.is_empty() {
+      print_signed_updater_archive(&signed_archives)?;
+    }
+    let signed_paths: Vec<PathBuf> = signed_archives
+      .iter()
+      .flat_map(|signed_archive| signed_archive.signatures.iter().cloned())
+      .collect();
+
+    if let Some(after_build) = &config_.build.after_build_command {
+      if !after_build.is_empty() {
+        if self.dry_run {
+          logger.log(format!("Would run `{}`", after_build));
+        } else {
+          logger.log(format!("Running `{}`", after_build));
+          let started = Instant::now();
+          let host_triple = rust::host_triple().unwrap_or_default();
+          let target_triples: Vec<&str> = targets
+            .iter()
+            .map(|target| target.as_deref().unwrap_or(&host_triple))
+            .collect();
+          execute_shell_command(
+            after_build,
+            vec![
+              ("TAURI_OUT_DIR", join_lines(&out_dirs)),
+              (
+                "TAURI_PRODUCT_NAME",
+                app_settings.get_package_settings().product_name.into(),
+              ),
+              ("TAURI_TARGET_TRIPLE", target_triples.join("\n").into()),
+              (
+                "TAURI_BUNDLE_PATHS",
+                join_lines(bundles.iter().flat_map(|bundle| &bundle.bundle_paths)),
+              ),
+            ],
+          )?;
+          timings.record("after-build", started);
+        }
+      }
+    }
+
+    if let Some(report_path) = &report_path {
+      if self.dry_run {
+        logger.log(format!(
+          "Would write the build report to {}",
+          report_path.display()
+        ));
+      } else {
+        BuildReport {
+          config: config_,
+          runner: &runner,
+          targets: &targets,
+          bundles: &bundles,
+          signed_paths: &signed_paths,
+        }
+        .write(report_path)?;
+        logger.log(format!(
+          "Wrote the build report to {}",
+          report_path.display()
+        ));
+      }
+    }
+
+    // a build with failed package types removes its temporary files, like any failed build
+    if bundle_failures.is_empty() {
+      cleanup_guard.disarm();
+    }
+
+    Ok(BundleResult {
+      bundles,
+      signatures: signed_paths,
+      failures: bundle_failures,
+      timings,
+    })
+  }
+}
+
+/// Checks that the updater config and the private key files or the signing environment variables can produce signed updater archives.
+fn validate_updater_config(
+  updater: &UpdaterConfig,
+  private_key_paths: &[PathBuf],
+) -> crate::Result<()> {
+  if updater
+    .endpoints
+    .as_ref()
+    .map(|endpoints| endpoints.is_empty())
+    .unwrap_or(true)
+  {
+    return Err(anyhow::anyhow!(
+      "The updater is active but no `tauri > updater > endpoints` is set on tauri.conf.json"
+    ));
+  }
+  if updater.pubkey.is_none() {
+    return Err(anyhow::anyhow!(
+      "The updater is active but no `tauri > updater > pubkey` is set on tauri.conf.json. Generate a key pair with `tauri sign -g` and set its public key"
+    ));
+  }
+  for private_key_path in private_key_paths {
+    if !private_key_path.is_file() {
+      return Err(anyhow::anyhow!(
+        "The private key file {} doesn't exist",
+        private_key_path.display()
+      ));
+    }
+  }
+  if private_key_paths.is_empty() && var_os("TAURI_PRIVATE_KEY").is_none() {
+    return Err(anyhow::anyhow!(
+      "A public key has been found, but no private key. Make sure to set `TAURI_PRIVATE_KEY` environment variable"
+    ));
+  }
+  Ok(())
+}
+
+/// Warns if the distDir is inside the cargo target directory, which cargo may clean or rebuild. Errors instead if `strict` is set.
+///
+/// The paths are canonicalized to handle symlinks and relative paths, so the check is skipped if any of them doesn't exist yet.
+fn check_dist_dir_location(
+  dist_dir: &Path,
+  cargo_target_dir: &Path,
+  strict: bool,
+  logger: &Logger<'_>,
+) -> crate::Result<()> {
+  if let (Ok(dist_dir), Ok(cargo_target_dir)) =
+    (dist_dir.canonicalize(), cargo_target_dir.canonicalize())
+  {
+    if dist_dir.starts_with(&cargo_target_dir) {
+      let message = format!(
+        "Your distDir {} is inside the cargo target directory {}, which cargo may clean or rebuild. Move your web assets to another directory.",
+        dist_dir.display(),
+        cargo_target_dir.display()
+      );
+      if strict {
+        return Err(anyhow::anyhow!(message));
+      }
+      logger.warn(message);
+    }
+  }
+  Ok(())
+}
+
+/// Checks that the `--product-name` value can be used as a file name, since the binary is renamed to it.
+fn validate_product_name(product_name: &str) -> crate::Result<()> {
+  if product_name.trim().is_empty()
+    || product_name == "."
+    || product_name == ".."
+    || product_name.ends_with('.')
+    || product_name
+      .chars()
+      .any(|c| c.is_control() || ILLEGAL_CHARACTERS.contains(&c))
+  {
+    return Err(anyhow::anyhow!(
+      "The product name `{}` is invalid, it can't be empty, end with a dot or contain any of the {} characters",
+      product_name,
+      ILLEGAL_CHARACTERS.iter().collect::<String>()
+    ));
+  }
+  Ok(())
+}
+
+/// Gets the build-time constants of the app, set as environment variables on the cargo invocation.
+///
+/// The app reads them at compile time with `env!("TAURI_BUILD_VERSION")`,
+/// or `option_env!` so it still compiles with a plain `cargo build`:
+///
+/// - `TAURI_BUILD_IDENTIFIER`: the bundle identifier, including the `--bundle-identifier-suffix` (unset if there's no identifier).
+/// - `TAURI_BUILD_VERSION`: the app version.
+/// - `TAURI_BUILD_UPDATER_ACTIVE`: `true` if the updater is active, `false` otherwise.
+///
+/// rustc tracks the variables read with `env!`, so cargo recompiles the app when a value changes.
+fn build_constants(
+  identifier: Option<&str>,
+  version: &str,
+  updater_active: bool,
+) -> Vec<(String, String)> {
+  let mut envs = vec![
+    ("TAURI_BUILD_VERSION".into(), version.into()),
+    (
+      "TAURI_BUILD_UPDATER_ACTIVE".into(),
+      updater_active.to_string(),
+    ),
+  ];
+  if let Some(identifier) = identifier {
+    envs.push(("TAURI_BUILD_IDENTIFIER".into(), identifier.into()));
+  }
+  envs
+}
+
+/// Appends the `--bundle-identifier-suffix` value to the bundle identifier, checking that the result is a valid reverse-DNS identifier.
+fn suffixed_identifier(identifier: Option<&str>, suffix: &str) -> crate::Result<String> {
+  let identifier = identifier.ok_or_else(|| {
+    anyhow::anyhow!("`--bundle-identifier-suffix` requires `tauri.bundle.identifier` to be set")
+  })?;
+  let identifier = format!("{}.{}", identifier, suffix.trim_start_matches('.'));
+  let segments: Vec<&str> = identifier.split('.').collect();
+  let is_valid = segments.len() > 1
+    && segments.iter().all(|segment| {
+      !segment.is_empty()
+        && !segment.starts_with('-')
+        && segment
+          .chars()
+          .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+  if is_valid {
+    Ok(identifier)
+  } else {
+    Err(anyhow::anyhow!(
+      "The bundle identifier `{}` is invalid, it must be a reverse-DNS string (e.g. `com.example.app.beta`) of alphanumeric characters and hyphens.",
+      identifier
+    ))
+  }
+}
+
+/// Checks that the main binary was already compiled to `out_dir`, with either its cargo name or the product name.
+fn check_compiled_binary(
+  app_settings: &rust::AppSettings,
+  config: &Config,
+  out_dir: &Path,
+) -> crate::Result<()> {
+  let exe_name = |name: &str| {
+    if cfg!(windows) {
+      format!("{}.exe", name)
+    } else {
+      name.to_string()
+    }
+  };
+  let mut names = vec![exe_name(app_settings.main_binary_name())];
+  if let Some(main_binary) = app_settings
+    .get_binaries(config)?
+    .iter()
+    .find(|bin| bin.main())
+  {
+    names.push(exe_name(main_binary.name()));
+  }
+  if names.iter().any(|name| out_dir.join(name).exists()) {
+    Ok(())
+  } else {
+    Err(anyhow::anyhow!(
+      "No compiled binary found at {}. `--bundle-only` requires the app to be compiled with the same target and profile first, e.g. with `tauri build --no-bundle`.",
+      out_dir.display()
+    ))
+  }
+}
+
+/// Checks that each configured merge module exists and is a `.msm` file.
+fn validate_merge_modules(merge_modules: &[PathBuf]) -> crate::Result<()> {
+  for merge_module in merge_modules {
+    if merge_module.extension().and_then(|ext| ext.to_str()) != Some("msm") {
+      return Err(anyhow::anyhow!(
+        "The merge module {} on `tauri > bundle > windows > mergeModules` isn't a `.msm` file",
+        merge_module.display()
+      ));
+    }
+    if !merge_module.is_file() {
+      return Err(anyhow::anyhow!(
+        "The merge module {} on `tauri > bundle > windows > mergeModules` doesn't exist",
+        merge_module.display()
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Checks that each configured WiX fragment exists and is a `.wxs` file.
+fn validate_wix_fragments(fragment_paths: &[PathBuf]) -> crate::Result<()> {
+  for fragment_path in fragment_paths {
+    if fragment_path.extension().and_then(|ext| ext.to_str()) != Some("wxs") {
+      return Err(anyhow::anyhow!(
+        "The WiX fragment {} on `tauri > bundle > windows > wix > fragmentPaths` isn't a `.wxs` file",
+        fragment_path.display()
+      ));
+    }
+    if !fragment_path.is_file() {
+      return Err(anyhow::anyhow!(
+        "The WiX fragment {} on `tauri > bundle > windows > wix > fragmentPaths` doesn't exist",
+        fragment_path.display()
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Checks the `tauri > bundle > windows > wix > installScope` value, and that a per-user installer doesn't include the Visual C++ runtime.
+fn validate_wix_install_scope(windows: &WindowsConfig) -> crate::Result<()> {
+  let install_scope = match windows
+    .wix
+    .as_ref()
+    .and_then(|wix| wix.install_scope.as_deref())
+  {
+    Some(install_scope) => install_scope,
+    None => return Ok(()),
+  };
+  match WixInstallScope::from_name(install_scope) {
+    None => Err(anyhow::anyhow!(
+      "The install scope `{}` on `tauri > bundle > windows > wix > installScope` isn't supported, use `perMachine` or `perUser`",
+      install_scope
+    )),
+    // the runtime merge module installs to the system directory, which requires elevation
+    Some(WixInstallScope::PerUser) if windows.include_vc_runtime => Err(anyhow::anyhow!(
+      "The Visual C++ runtime is installed per machine, so it can't be included on a `perUser` installer. Set `tauri > bundle > windows > includeVcRuntime` to false and link the CRT statically or install the redistributable separately"
+    )),
+    Some(_) => Ok(()),
+  }
+}
+
+/// Checks that the updater can extract the archives of the `tauri > updater > archiveFormat` on the host platform.
+fn validate_updater_archive_format(archive_format: Option<&str>) -> crate::Result<()> {
+  if let Some(archive_format) = archive_format {
+    match UpdaterArchiveFormat::from_short_name(archive_format) {
+      None => {
+        return Err(anyhow::anyhow!(
+          "The updater archive format `{}` on `tauri > updater > archiveFormat` isn't supported, the updater extracts `gz` and `zip` archives",
+          archive_format
+        ))
+      }
+      Some(UpdaterArchiveFormat::Zip) if !cfg!(windows) => {
+        return Err(anyhow::anyhow!(
+          "The `zip` updater archive format on `tauri > updater > archiveFormat` is only supported on Windows since the updater can't restore the executable permissions of the macOS and Linux apps, use `gz`"
+        ))
+      }
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+/// Derives a binary name from a product name with whitespace, e.g. `my-app` for `My App`.
+fn hyphenated_binary_name(product_name: &str) -> String {
+  product_name
+    .split_whitespace()
+    .map(str::to_lowercase)
+    .collect::<Vec<String>>()
+    .join("-")
+}
+
+/// Checks that the distDir is set to a local path, a common mistake is setting it to the dev server URL.
+fn validate_dist_dir(dist_dir: &str) -> crate::Result<()> {
+  let dist_dir = dist_dir.trim();
+  if dist_dir.is_empty() {
+    return Err(BuildError::EmptyDistDir.into());
+  }
+  if dist_dir.contains("://") {
+    return Err(BuildError::DistDirIsUrl(dist_dir.to_string()).into());
+  }
+  Ok(())
+}
+
+/// Checks that the `tauri > bundle > windows > manifest` file exists and is well-formed XML.
+fn validate_windows_manifest(manifest: &Path) -> crate::Result<()> {
+  let content = read_to_string(manifest).with_context(|| {
+    format!(
+      "failed to read the Windows manifest {} on `tauri > bundle > windows > manifest`",
+      manifest.display()
+    )
+  })?;
+  xml::check_well_formed(&content).map_err(|e| {
+    anyhow::anyhow!(
+      "The Windows manifest {} on `tauri > bundle > windows > manifest` isn't well-formed XML: {}",
+      manifest.display(),
+      e
+    )
+  })
+}
+
+/// Checks that the `tauri > bundle > macOS > infoPlist` values can be written to the plist, i.e. they don't contain `null`.
+fn validate_info_plist(info_plist: &HashMap<String, JsonValue>) -> crate::Result<()> {
+  fn find_null(path: String, value: &JsonValue) -> Option<String> {
+    match value {
+      JsonValue::Null => Some(path),
+      JsonValue::Array(values) => values
+        .iter()
+        .enumerate()
+        .find_map(|(index, value)| find_null(format!("{}[{}]", path, index), value)),
+      JsonValue::Object(entries) => entries
+        .iter()
+        .find_map(|(key, value)| find_null(format!("{}.{}", path, key), value)),
+      _ => None,
+    }
+  }
+
+  let mut keys: Vec<&String> = info_plist.keys().collect();
+  keys.sort();
+  for key in keys {
+    if key.is_empty() {
+      return Err(anyhow::anyhow!(
+        "`tauri > bundle > macOS > infoPlist` has an empty key"
+      ));
+    }
+    if let Some(path) = find_null(key.clone(), &info_plist[key]) {
+      return Err(anyhow::anyhow!(
+        "The `tauri > bundle > macOS > infoPlist` value {} is null, which plists don't support",
+        path
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Reads the glob patterns of the `.tauriignore` file on the project root, ignoring empty lines and `#` comments.
+fn read_ignore_patterns() -> crate::Result<Vec<Pattern>> {
+  let ignore_file_path = app_dir().join(".tauriignore");
+  if !ignore_file_path.exists() {
+    return Ok(Vec::new());
+  }
+  let ignore_file = read_to_string(&ignore_file_path)
+    .with_context(|| format!("failed to read {}", ignore_file_path.display()))?;
+  ignore_file
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(|line| {
+      Pattern::new(line.trim_end_matches('/'))
+        .with_context(|| format!("invalid pattern `{}` on .tauriignore", line))
+    })
+    .collect()
+}
+
+/// Checks if the directory contains at least one file that isn't matched by the ignore patterns.
+///
+/// The patterns are matched against the entry file name and its path relative to `root`.
+fn contains_web_assets(
+  dir: &Path,
+  root: &Path,
+  ignore_patterns: &[Pattern],
+) -> crate::Result<bool> {
+  for entry in read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+    let path = entry?.path();
+    let relative_path = path.strip_prefix(root).unwrap_or(&path);
+    let file_name = path
+      .file_name()
+      .map(|name| name.to_string_lossy().to_string())
+      .unwrap_or_default();
+    if ignore_patterns
+      .iter()
+      .any(|pattern| pattern.matches_path(relative_path) || pattern.matches(&file_name))
+    {
+      continue;
+    }
+    if !path.is_dir() || contains_web_assets(&path, root, ignore_patterns)? {
+      return Ok(true);
+    }
+  }
+  Ok(false)
+}
+
+/// Renames the compiled binary from its cargo name to the product name.
+///
+/// If the binary can't be found but a binary with the product name exists, it is considered already renamed.
+fn rename_app(out_dir: &Path, bin_name: &str, product_name: &str) -> crate::Result<()> {
+  let (bin_file_name, product_file_name) = if cfg!(windows) {
+    (format!("{}.exe", bin_name), format!("{}.exe", product_name))
+  } else {
+    (bin_name.to_string(), product_name.to_string())
+  };
+  let bin_path = out_dir.join(&bin_file_name);
+  let product_path = out_dir.join(&product_file_name);
+
+  if !bin_path.exists() && product_path.exists() {
+    return Ok(());
+  }
+
+  rename(&bin_path, &product_path).with_context(|| {
+    format!(
+      "failed to rename `{}` to `{}`, expected the compiled binary at {}. Make sure it matches the `[[bin]]` name on your Cargo.toml",
+      bin_file_name,
+      product_file_name,
+      bin_path.display()
+    )
+  })
+}
+
+/// Joins the given paths with newlines, the format of the path lists passed to the afterBuildCommand.
+fn join_lines<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) -> OsString {
+  let mut joined = OsString::new();
+  for (index, path) in paths.into_iter().enumerate() {
+    if index > 0 {
+      joined.push("\n");
+    }
+    joined.push(path);
+  }
+  joined
+}
+
+/// Runs a shell command on the app directory with `cmd /C` on Windows and `sh -c` on other platforms.
+fn execute_shell_command(command: &str, envs: Vec<(&str, OsString)>) -> crate::Result<()> {
+  #[cfg(target_os = "windows")]
+  let (shell, flag) = ("cmd", "/C");
+  #[cfg(not(target_os = "windows"))]
+  let (shell, flag) = ("sh", "-c");
+  execute_with_output(
+    Command::new(shell)
+      .arg(flag)
+      .arg(command)
+      .envs(envs)
+      .current_dir(app_dir()),
+  )
+  .with_context(|| format!("failed to run `{}` with `{} {}`", command, shell, flag))
+}
+
+/// Maps the bundle short names to package types, stopping at the `none` sentinel.
+///
+/// The `all` value maps to every package type supported by the host platform.
+/// A name prefixed with `!` (e.g. `!rpm`) excludes that format from the expanded list.
+fn parse_package_types(names: &[String]) -> crate::Result<Vec<PackageType>> {
+  let mut types = vec![];
+  let mut excluded = vec![];
+  for name in names {
+    if name == "none" {
+      break;
+    }
+    if let Some(name) = name.strip_prefix('!') {
+      excluded.push(parse_package_type(name.trim())?);
+      continue;
+    }
+    if name == "all" {
+      for package_type in PackageType::all() {
+        if !types.contains(package_type) {
+          types.push(*package_type);
+        }
+      }
+      continue;
+    }
+    let package_type = parse_package_type(name)?;
+    if !types.contains(&package_type) {
+      types.push(package_type);
+    }
+  }
+  types.retain(|package_type| !excluded.contains(package_type));
+  Ok(types)
+}
+
+/// Maps a bundle short name to its package type.
+fn parse_package_type(name: &str) -> crate::Result<PackageType> {
+  PackageType::from_short_name(name).ok_or_else(|| {
+    let valid_names: Vec<&str> = PackageType::all()
+      .iter()
+      .map(|package_type| package_type.short_name())
+      .collect();
+    BuildError::UnsupportedBundle {
+      name: name.to_string(),
+      valid_formats: valid_names.join(", "),
+    }
+    .into()
+  })
+}
+
+/// The bundle formats requested with `--bundle`.
+///
+/// Each value is a comma separated list of formats, optionally scoped to a target with `<target>:<format>`,
+/// where the target is a triple or an architecture prefix such as `x86_64`.
+/// The formats without a target are bundled for every target.
+struct BundleSelection {
+  /// the formats without a target.
+  common: Option<Vec<PackageType>>,
+  /// the formats scoped to a target, keyed by the target.
+  targeted: HashMap<String, Vec<PackageType>>,
+  /// the scoped format names, to report the ones matching no target.
+  targeted_names: HashMap<String, String>,
+}
+
+impl BundleSelection {
+  fn parse(values: &[String]) -> crate::Result<Self> {
+    let mut common = Vec::new();
+    let mut targeted: HashMap<String, Vec<String>> = HashMap::new();
+    for entry in values
+      .iter()
+      .flat_map(|value| value.split(','))
+      .map(str::trim)
+      .filter(|entry| !entry.is_empty())
+    {
+      match entry.split_once(':') {
+        Some((target, name)) => targeted
+          .entry(target.to_string())
+          .or_default()
+          .push(name.to_string()),
+        None => common.push(entry.to_string()),
+      }
+    }
+
+    let targeted_names = targeted
+      .iter()
+      .map(|(target, names)| (target.clone(), names.join(", ")))
+      .collect();
+    let mut targeted_types = HashMap::new();
+    for (target, names) in targeted {
+      targeted_types.insert(target, parse_package_types(&names)?);
+    }
+    Ok(Self {
+      common: if common.is_empty() {
+        None
+      } else {
+        Some(parse_package_types(&common)?)
+      },
+      targeted: targeted_types,
+      targeted_names,
+    })
+  }
+
+  /// Ensures every scoped format matches at least one of the build targets.
+  fn validate_targets(&self, target_triples: &[Option<&str>]) -> crate::Result<()> {
+    for (target, name) in &self.targeted_names {
+      let matched = target_triples
+        .iter()
+        .flatten()
+        .any(|triple| target_matches(target, triple));
+      if !matched {
+        let build_targets: Vec<&str> = target_triples
+          .iter()
+          .map(|triple| triple.unwrap_or("host"))
+          .collect();
+        return Err(
+          BuildError::UnmatchedBundleTarget {
+            name: name.clone(),
+            target: target.clone(),
+            build_targets: build_targets.join(", "),
+          }
+          .into(),
+        );
+      }
+    }
+    Ok(())
+  }
+
+  /// The formats to bundle for the given target triple, `None` if no value applies to it.
+  fn for_target(&self, target_triple: Option<&str>) -> Option<Vec<PackageType>> {
+    let mut applies = self.common.is_some();
+    let mut types = self.common.clone().unwrap_or_default();
+    if let Some(triple) = target_triple {
+      for (target, target_types) in &self.targeted {
+        if target_matches(target, triple) {
+          applies = true;
+          for package_type in target_types {
+            if !types.contains(package_type) {
+              types.push(*package_type);
+            }
+          }
+        }
+      }
+    }
+    if applies {
+      Some(types)
+    } else {
+      None
+    }
+  }
+}
+
+/// Whether a `--bundle` target scope matches a target triple, either exactly or as its architecture prefix.
+fn target_matches(scope: &str, triple: &str) -> bool {
+  triple == scope || (triple.starts_with(scope) && triple[scope.len()..].starts_with('-'))
+}
+
+/// Whether any file under `paths` changed since the `git_ref` commit, including the uncommitted and the untracked files.
+///
+/// Returns `None` if git isn't available or can't diff the paths, e.g. outside of a repository or with an unknown ref.
+fn changed_since(git_ref: &str, paths: &[&Path]) -> Option<bool> {
+  let git = |args: &[&str]| {
+    Command::new("git")
+      .args(args)
+      .arg("--")
+      .args(paths)
+      .output()
+      .ok()
+      .filter(|output| output.status.success())
+      .map(|output| !output.stdout.iter().all(u8::is_ascii_whitespace))
+  };
+  let changed = git(&["diff", "--name-only", git_ref])?;
+  let untracked = git(&["ls-files", "--others", "--exclude-standard"])?;
+  Some(changed || untracked)
+}
+
+/// Removes the bundles of previous builds from `bundle_dir`, logging each removed file.
+///
+/// Symlinks are removed without following them, so nothing outside of `bundle_dir` is deleted.
+fn clean_bundle_dir(bundle_dir: &Path, logger: &Logger<'_>) -> crate::Result<()> {
+  fn remove_files(dir: &Path, logger: &Logger<'_>) -> std::io::Result<()> {
+    for entry in read_dir(dir)? {
+      let path = entry?.path();
+      if path.symlink_metadata()?.is_dir() {
+        remove_files(&path, logger)?;
+        remove_dir(&path)?;
+      } else {
+        remove_file(&path)?;
+        logger.log(format!("Removed {}", path.display()));
+      }
+    }
+    Ok(())
+  }
+
+  if bundle_dir.is_dir() {
+    remove_files(bundle_dir, logger)
+      .with_context(|| format!("failed to clean {}", bundle_dir.display()))?;
+  }
+  Ok(())
+}
+
+/// Copies a bundle artifact (a file or a directory) into the given directory, returning its new path.
+fn copy_to_dir(path: &Path, dir: &Path) -> crate::Result<PathBuf> {
+  let dest = dir.join(path.file_name().expect("failed to get bundle file name"));
+  copy_recursively(path, &dest)
+    .with_context(|| format!("failed to copy {} to {}", path.display(), dest.display()))?;
+  Ok(dest)
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> std::io::Result<()> {
+  if from.is_dir() {
+    create_dir_all(to)?;
+    for entry in read_dir(from)? {
+      let entry = entry?;
+      copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+    }
+  } else {
+    copy(from, to)?;
+  }
+  Ok(())
+}
+
+/// Prints the produced bundles and updater signatures as a JSON object to stdout.
+fn print_json_output(bundles: &[Bundle], signed_paths: &[PathBuf]) -> crate::Result<()> {
+  let bundles: Vec<JsonValue> = bundles
+    .iter()
+    .map(|bundle| {
+      json!({
+        "packageType": bundle.package_type.short_name(),
+        "bundlePaths": bundle.bundle_paths,
+      })
+    })
+    .collect();
+  let output = json!({
+    "bundles": bundles,
+    "signatures": signed_paths,
+  });
+  println!("{}", serde_json::to_string_pretty(&output)?);
+  Ok(())
+}
+
+/// Drops the updater bundle when the updater isn't active, failing when it's the only requested bundle.
+fn without_inactive_updater(
+  mut types: Vec<PackageType>,
+  updater_active: bool,
+) -> crate::Result<Vec<PackageType>> {
+  if !updater_active && types.contains(&PackageType::Updater) {
+    types.retain(|package_type| *package_type != PackageType::Updater);
+    if types.is_empty() {
+      return Err(anyhow::anyhow!(
+        "the updater bundle requires the updater to be active, set `tauri > updater > active` to `true`"
+      ));
+    }
+  }
+  Ok(types)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    parse_package_types, rust, suffixed_identifier, without_inactive_updater, BundleSelection,
+    PackageType,
+  };
+
+  fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+  }
+
+  #[test]
+  fn package_types() {
+    let all_but_updater: Vec<PackageType> = PackageType::all()
+      .iter()
+      .copied()
+      .filter(|package_type| *package_type != PackageType::Updater)
+      .collect();
+    let cases: Vec<(&[&str], Option<Vec<PackageType>>)> = vec![
+      (
+        &["deb", "updater"],
+        Some(vec![PackageType::Deb, PackageType::Updater]),
+      ),
+      (&["deb", "deb"], Some(vec![PackageType::Deb])),
+      (&["all", "!updater"], Some(all_but_updater)),
+      (&["!deb", "deb"], Some(vec![])),
+      (&["none", "deb"], Some(vec![])),
+      (&["unknown"], None),
+      (&["!unknown"], None),
+    ];
+    for (names, expected) in cases {
+      assert_eq!(
+        parse_package_types(&strings(names)).ok(),
+        expected,
+        "{:?}",
+        names
+      );
+    }
+  }
+
+  #[test]
+  fn bundle_selection() {
+    let x86_64 = Some("x86_64-unknown-linux-gnu");
+    let aarch64 = Some("aarch64-unknown-linux-gnu");
+    let cases: Vec<(&[&str], Option<&str>, Option<Vec<PackageType>>)> = vec![
+      (&["deb,aarch64:rpm"], x86_64, Some(vec![PackageType::Deb])),
+      (
+        &["deb", "aarch64:rpm"],
+        aarch64,
+        Some(vec![PackageType::Deb, PackageType::Rpm]),
+      ),
+      (&["aarch64-unknown-linux-gnu:deb"], x86_64, None),
+      (&["aarch64-unknown-linux-gnu:deb"], None, None),
+      // an architecture prefix only matches whole triple components
+      (&["x86:deb"], x86_64, None),
+      (&["x86_64:all,x86_64:!updater"], aarch64, None),
+    ];
+    for (values, target, expected) in cases {
+      let selection = BundleSelection::parse(&strings(values)).expect("invalid selection");
+      assert_eq!(selection.for_target(target), expected, "{:?}", values);
+    }
+
+    let selection = BundleSelection::parse(&strings(&["aarch64:deb"])).expect("invalid selection");
+    assert!(selection.validate_targets(&[aarch64]).is_ok());
+    assert!(selection.validate_targets(&[x86_64, None]).is_err());
+  }
+
+  #[test]
+  fn runner() {
+    let cases: Vec<(&str, Option<(&str, &[&str])>)> = vec![
+      ("cargo", Some(("cargo", &["build"]))),
+      ("sccache cargo", Some(("sccache", &["cargo", "build"]))),
+      ("cargo-zigbuild build", Some(("cargo-zigbuild", &["build"]))),
+      (
+        r#""C:\Program Files\cargo.exe" +nightly"#,
+        Some((r"C:\Program Files\cargo.exe", &["+nightly", "build"])),
+      ),
+      ("'my runner' 'build'", Some(("my runner", &["build"]))),
+      ("cargo \"build", None),
+      ("  ", None),
+    ];
+    for (runner, expected) in cases {
+      let expected = expected.map(|(program, args)| (program.to_string(), strings(args)));
+      assert_eq!(rust::runner_build_args(runner).ok(), expected, "{}", runner);
+    }
+  }
+
+  #[test]
+  fn suffixed_identifiers() {
+    let cases = vec![
+      (
+        Some("com.example.app"),
+        "beta",
+        Some("com.example.app.beta"),
+      ),
+      (
+        Some("com.example.app"),
+        ".beta-1",
+        Some("com.example.app.beta-1"),
+      ),
+      (Some("com.example.app"), "beta_1", None),
+      (Some("com.example.app"), "-beta", None),
+      (Some("com.example.app"), "beta.", None),
+      (None, "beta", None),
+    ];
+    for (identifier, suffix, expected) in cases {
+      assert_eq!(
+        suffixed_identifier(identifier, suffix).ok().as_deref(),
+        expected,
+        "{:?} {}",
+        identifier,
+        suffix
+      );
+    }
+  }
+
+  #[test]
+  fn inactive_updater() {
+    let types = vec![PackageType::Deb, PackageType::Updater];
+    assert_eq!(
+      without_inactive_updater(types.clone(), true).unwrap(),
+      types
+    );
+    assert_eq!(
+      without_inactive_updater(types, false).unwrap(),
+      vec![PackageType::Deb]
+    );
+    assert!(without_inactive_updater(vec![PackageType::Updater], false).is_err());
+  }
 }
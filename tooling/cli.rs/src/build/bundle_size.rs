@@ -0,0 +1,104 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::helpers::Logger;
+use tauri_bundler::bundle::{Bundle, Settings};
+
+use std::{
+  fs::read_dir,
+  path::{Path, PathBuf},
+};
+
+/// The bundle size above which a warning is printed, unless `tauri > bundle > sizeWarnMb` is set.
+pub const DEFAULT_SIZE_WARN_MB: u64 = 150;
+/// The number of largest files listed on the warning.
+const LARGEST_FILES_COUNT: usize = 5;
+
+/// Warns about the bundles larger than `threshold_mb`, listing their largest files.
+///
+/// The files of directory bundles such as the macOS `.app` are listed directly,
+/// otherwise the largest bundler inputs (binaries, sidecars and resources) are listed.
+pub fn warn_oversized_bundles(
+  bundles: &[Bundle],
+  settings: &Settings,
+  threshold_mb: u64,
+  logger: &Logger<'_>,
+) {
+  let threshold = threshold_mb * 1024 * 1024;
+  for path in bundles.iter().flat_map(|bundle| bundle.bundle_paths.iter()) {
+    let mut files = Vec::new();
+    if path.is_dir() {
+      collect_files(path, &mut files);
+    } else if let Ok(metadata) = path.metadata() {
+      files.push((path.clone(), metadata.len()));
+    }
+    let size: u64 = files.iter().map(|(_, size)| size).sum();
+    if size <= threshold {
+      continue;
+    }
+
+    let mut contributors = if path.is_dir() {
+      files
+    } else {
+      bundle_inputs(settings)
+    };
+    contributors.sort_by(|(_, a), (_, b)| b.cmp(a));
+    let largest: Vec<String> = contributors
+      .iter()
+      .take(LARGEST_FILES_COUNT)
+      .map(|(file, size)| {
+        let file = file.strip_prefix(path).unwrap_or(file);
+        format!("  {} ({})", file.display(), format_size(*size))
+      })
+      .collect();
+    logger.warn(format!(
+      "{} is {}, larger than the {} MB warning threshold (`tauri > bundle > sizeWarnMb`). The largest files are:\n{}",
+      path.display(),
+      format_size(size),
+      threshold_mb,
+      largest.join("\n")
+    ));
+  }
+}
+
+/// Gets the binaries, external binaries and resources of the bundler settings with their sizes.
+fn bundle_inputs(settings: &Settings) -> Vec<(PathBuf, u64)> {
+  let binaries = settings
+    .binaries()
+    .iter()
+    .map(|binary| settings.binary_path(binary));
+  let other_inputs = settings.external_binaries().filter_map(Result::ok).chain(
+    settings
+      .resources()
+      .filter_map(Result::ok)
+      .map(|(src, _)| src),
+  );
+  binaries
+    .chain(other_inputs)
+    .filter_map(|path| {
+      let size = path.metadata().ok()?.len();
+      Some((path, size))
+    })
+    .collect()
+}
+
+/// Collects the files of `dir` with their sizes, recursively.
+fn collect_files(dir: &Path, files: &mut Vec<(PathBuf, u64)>) {
+  let entries = match read_dir(dir) {
+    Ok(entries) => entries,
+    Err(_) => return,
+  };
+  for entry in entries.filter_map(Result::ok) {
+    let path = entry.path();
+    match entry.metadata() {
+      Ok(metadata) if metadata.is_dir() => collect_files(&path, files),
+      Ok(metadata) => files.push((path, metadata.len())),
+      Err(_) => {}
+    }
+  }
+}
+
+fn format_size(size: u64) -> String {
+  format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
+}
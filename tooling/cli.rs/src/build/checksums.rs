@@ -0,0 +1,101 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+use sha2::{Digest, Sha256, Sha512};
+
+use std::{
+  fmt,
+  fs::{write, File},
+  io::copy,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+/// The name of the file listing the checksums of all bundles.
+const CHECKSUMS_FILE_NAME: &str = "checksums.txt";
+
+/// The supported checksum algorithms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+  Sha256,
+  Sha512,
+}
+
+impl FromStr for ChecksumAlgorithm {
+  type Err = anyhow::Error;
+
+  fn from_str(name: &str) -> crate::Result<Self> {
+    match name {
+      "sha256" => Ok(Self::Sha256),
+      "sha512" => Ok(Self::Sha512),
+      _ => Err(anyhow::anyhow!(
+        "Unsupported checksum algorithm: {}. The valid algorithms are: sha256, sha512",
+        name
+      )),
+    }
+  }
+}
+
+impl fmt::Display for ChecksumAlgorithm {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Sha256 => write!(f, "sha256"),
+      Self::Sha512 => write!(f, "sha512"),
+    }
+  }
+}
+
+impl ChecksumAlgorithm {
+  /// Hashes the file at the given path, returning the lowercase hex digest.
+  fn hash_file(self, path: &Path) -> crate::Result<String> {
+    let mut file =
+      File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let digest = match self {
+      Self::Sha256 => {
+        let mut hasher = Sha256::new();
+        copy(&mut file, &mut hasher)?;
+        hasher.finalize().to_vec()
+      }
+      Self::Sha512 => {
+        let mut hasher = Sha512::new();
+        copy(&mut file, &mut hasher)?;
+        hasher.finalize().to_vec()
+      }
+    };
+    Ok(hex::encode(digest))
+  }
+}
+
+/// Writes the checksums of the given files to a `checksums.txt` file on `dir`, returning its path.
+///
+/// The file uses the `sha256sum` format, with paths relative to `dir`, so it can be verified with `sha256sum -c checksums.txt`.
+/// If `per_file` is set, each file also gets a `.sha256` (or `.sha512`) file next to it.
+pub fn write_checksums(
+  algorithm: ChecksumAlgorithm,
+  paths: &[PathBuf],
+  dir: &Path,
+  per_file: bool,
+) -> crate::Result<PathBuf> {
+  let mut checksums = String::new();
+  for path in paths {
+    let hash = algorithm
+      .hash_file(path)
+      .with_context(|| format!("failed to hash {}", path.display()))?;
+    let relative_path = path.strip_prefix(dir).unwrap_or(path);
+    checksums.push_str(&format!("{}  {}\n", hash, relative_path.display()));
+
+    if per_file {
+      let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+      let checksum_path = PathBuf::from(format!("{}.{}", path.display(), algorithm));
+      write(&checksum_path, format!("{}  {}\n", hash, file_name))
+        .with_context(|| format!("failed to write {}", checksum_path.display()))?;
+    }
+  }
+
+  let checksums_path = dir.join(CHECKSUMS_FILE_NAME);
+  write(&checksums_path, checksums)
+    .with_context(|| format!("failed to write {}", checksums_path.display()))?;
+  Ok(checksums_path)
+}
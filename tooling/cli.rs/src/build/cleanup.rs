@@ -0,0 +1,163 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::helpers::Logger;
+
+use once_cell::sync::Lazy;
+
+use std::{
+  fs::{read_dir, remove_file},
+  path::{Path, PathBuf},
+  process::exit,
+  sync::{Arc, Mutex, Once},
+  time::SystemTime,
+};
+
+/// The state of the guard of the running build, cleaned up by the Ctrl-C handler.
+static ACTIVE_STATE: Lazy<Mutex<Option<Arc<Mutex<CleanupState>>>>> = Lazy::new(Default::default);
+/// The Ctrl-C handler can only be set once per process, but the watch mode runs several builds.
+static SET_HANDLER: Once = Once::new();
+
+#[derive(Default)]
+struct CleanupState {
+  /// the temporary files written by the CLI.
+  files: Vec<PathBuf>,
+  /// the directories where the files modified after the given instant are partial artifacts.
+  dirs: Vec<(PathBuf, SystemTime)>,
+}
+
+/// Removes the temporary files and the partially written bundles when the build fails or is interrupted with Ctrl-C.
+///
+/// The cleanup runs when the guard is dropped, unless [`CleanupGuard::disarm`] is called after a successful build.
+/// It is best-effort: failures to remove a file are only logged.
+pub struct CleanupGuard {
+  state: Arc<Mutex<CleanupState>>,
+  armed: bool,
+}
+
+impl CleanupGuard {
+  /// Creates the guard and installs the Ctrl-C handler running the cleanup.
+  pub fn new() -> Self {
+    let state = Arc::new(Mutex::new(CleanupState::default()));
+    ACTIVE_STATE.lock().unwrap().replace(state.clone());
+    SET_HANDLER.call_once(|| {
+      if let Err(e) = ctrlc::set_handler(|| {
+        // between the builds of the watch mode there's nothing to clean up
+        let active_state = ACTIVE_STATE.lock().ok().and_then(|mut state| state.take());
+        if let Some(state) = active_state {
+          cleanup(&state);
+          Logger::new("tauri:build").error("Build interrupted");
+        }
+        exit(130);
+      }) {
+        Logger::new("tauri:build").warn(format!("failed to set the Ctrl-C handler: {}", e));
+      }
+    });
+    Self { state, armed: true }
+  }
+
+  /// Tracks a temporary file written by the CLI, removed on cleanup.
+  #[cfg_attr(not(windows), allow(dead_code))]
+  pub fn track_file(&self, path: PathBuf) {
+    self.state.lock().unwrap().files.push(path);
+  }
+
+  /// Tracks a directory where every file modified from now on is removed on cleanup.
+  pub fn track_dir(&self, dir: PathBuf) {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .dirs
+      .push((dir, SystemTime::now()));
+  }
+
+  /// Stops tracking a directory, e.g. once its artifacts are complete.
+  pub fn untrack_dir(&self, dir: &Path) {
+    self
+      .state
+      .lock()
+      .unwrap()
+      .dirs
+      .retain(|(tracked_dir, _)| tracked_dir != dir);
+  }
+
+  /// Keeps the tracked files, the build succeeded.
+  pub fn disarm(mut self) {
+    self.armed = false;
+  }
+}
+
+impl Drop for CleanupGuard {
+  fn drop(&mut self) {
+    if let Ok(mut active_state) = ACTIVE_STATE.lock() {
+      if active_state
+        .as_ref()
+        .map(|state| Arc::ptr_eq(state, &self.state))
+        .unwrap_or(false)
+      {
+        active_state.take();
+      }
+    }
+    if self.armed {
+      cleanup(&self.state);
+    }
+  }
+}
+
+fn cleanup(state: &Mutex<CleanupState>) {
+  let logger = Logger::new("tauri:build");
+  let (files, dirs) = match state.lock() {
+    Ok(mut state) => (
+      std::mem::take(&mut state.files),
+      std::mem::take(&mut state.dirs),
+    ),
+    Err(_) => return,
+  };
+  let mut removed = Vec::new();
+  for file in files {
+    remove_path(&file, &logger, &mut removed);
+  }
+  for (dir, since) in dirs {
+    remove_modified_files(&dir, since, &logger, &mut removed);
+  }
+  for path in removed {
+    logger.log(format!("Removed {}", path.display()));
+  }
+}
+
+fn remove_path(path: &Path, logger: &Logger<'_>, removed: &mut Vec<PathBuf>) {
+  if path.exists() {
+    match remove_file(path) {
+      Ok(()) => removed.push(path.to_path_buf()),
+      Err(e) => logger.warn(format!("failed to remove {}: {}", path.display(), e)),
+    }
+  }
+}
+
+/// Removes the files of `dir` modified on or after `since`, recursively.
+fn remove_modified_files(
+  dir: &Path,
+  since: SystemTime,
+  logger: &Logger<'_>,
+  removed: &mut Vec<PathBuf>,
+) {
+  let entries = match read_dir(dir) {
+    Ok(entries) => entries,
+    Err(_) => return,
+  };
+  for entry in entries.filter_map(Result::ok) {
+    let path = entry.path();
+    if path.is_dir() {
+      remove_modified_files(&path, since, logger, removed);
+    } else if entry
+      .metadata()
+      .and_then(|metadata| metadata.modified())
+      .map(|modified| modified >= since)
+      .unwrap_or(false)
+    {
+      remove_path(&path, logger, removed);
+    }
+  }
+}
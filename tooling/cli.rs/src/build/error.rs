@@ -0,0 +1,92 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::{path::PathBuf, process::ExitStatus};
+
+/// The failures of [`super::Build::run`] that callers may want to handle.
+///
+/// They are returned wrapped in an [`anyhow::Error`], use `error.downcast_ref::<BuildError>()` to match on them.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum BuildError {
+  /// The distDir doesn't exist or doesn't contain any web asset.
+  #[error("Unable to find your web assets, did you forget to build your web app? Your distDir is set to \"{0:?}\".")]
+  MissingDistDir(PathBuf),
+  /// The distDir is a URL, e.g. the dev server URL, instead of the path to the prebuilt web assets.
+  #[error("Your distDir is set to the URL \"{0}\", but `tauri build` embeds the prebuilt web assets: set `build > distDir` to the directory your web app builds to (e.g. \"../dist\") and keep the dev server URL on `build > devPath`.")]
+  DistDirIsUrl(String),
+  /// The distDir is empty.
+  #[error("Your distDir is empty: set `build > distDir` to the directory your web app builds to (e.g. \"../dist\"), it must contain your index.html file.")]
+  EmptyDistDir,
+  /// The runner program isn't installed, e.g. `cargo` or the first token of a custom runner command.
+  #[error("runner `{0}` not found; is it installed and on the PATH?")]
+  RunnerNotFound(String),
+  /// The runner exited with a non successful status.
+  #[error("Result of `{runner} build` operation was unsuccessful: {status}")]
+  CargoFailed {
+    /// the runner binary, e.g. `cargo`.
+    runner: String,
+    /// the exit status of the runner.
+    status: ExitStatus,
+  },
+  /// Cargo.lock is out of date and `--locked` prevents cargo from updating it.
+  #[error("Cargo.lock needs to be updated but `--locked` was passed. Run `cargo update` and commit Cargo.lock, or remove `--locked`.")]
+  LockfileOutdated,
+  /// The bundler failed to bundle the app.
+  #[error("failed to bundle project")]
+  BundleFailed(#[source] tauri_bundler::Error),
+  /// Some package types failed to bundle with `--keep-going`, the other bundles were produced.
+  #[error("failed to bundle {package_types}")]
+  BundlesFailed {
+    /// the comma separated list of the package types that failed.
+    package_types: String,
+  },
+  /// The updater archives couldn't be signed.
+  #[error("failed to sign the updater archives")]
+  SigningFailed(#[source] anyhow::Error),
+  /// A `--bundle` value isn't a known bundle format.
+  #[error("Unsupported bundle format: {name}. The valid formats are: {valid_formats}")]
+  UnsupportedBundle {
+    /// the requested bundle format.
+    name: String,
+    /// the comma separated list of the valid formats.
+    valid_formats: String,
+  },
+  /// A `--bundle <target>:<format>` value is scoped to a target that isn't being built.
+  #[error("The bundle format {name} is scoped to {target}, which doesn't match any of the build targets: {build_targets}")]
+  UnmatchedBundleTarget {
+    /// the requested bundle format.
+    name: String,
+    /// the target the format is scoped to.
+    target: String,
+    /// the comma separated list of the build targets.
+    build_targets: String,
+  },
+  /// A `--target` value is an Android or iOS target.
+  #[error("The `{0}` target is a mobile target, and mobile targets are not supported by `tauri build` yet. Follow https://github.com/tauri-apps/tauri for the mobile support status.")]
+  UnsupportedMobileTarget(String),
+}
+
+impl BuildError {
+  /// The process exit code of `tauri build` when it fails with this error, so wrapping scripts can react to each failure class:
+  ///
+  /// - `2`: the web assets are missing (the distDir doesn't exist, is empty or is a URL).
+  /// - `3`: the app failed to compile (the runner is missing, exited with an error or the lockfile is outdated).
+  /// - `4`: the bundler failed, including the package types that failed with `--keep-going`.
+  /// - `5`: the updater archives couldn't be signed.
+  ///
+  /// The invalid `--bundle` and `--target` values and the unexpected errors exit with `1`.
+  /// Note that clap also exits with `2` when the command line arguments can't be parsed.
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      Self::MissingDistDir(_) | Self::DistDirIsUrl(_) | Self::EmptyDistDir => 2,
+      Self::RunnerNotFound(_) | Self::CargoFailed { .. } | Self::LockfileOutdated => 3,
+      Self::BundleFailed(_) | Self::BundlesFailed { .. } => 4,
+      Self::SigningFailed(_) => 5,
+      Self::UnsupportedBundle { .. }
+      | Self::UnmatchedBundleTarget { .. }
+      | Self::UnsupportedMobileTarget(_) => 1,
+    }
+  }
+}
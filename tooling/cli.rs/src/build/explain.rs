@@ -0,0 +1,115 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use super::rust;
+use crate::helpers::Logger;
+
+use std::{env::var, path::Path, process::Command};
+
+/// Prints how the toolchain of each build target is resolved, without compiling anything.
+///
+/// For each target this shows the active toolchain, whether the target's standard library is installed,
+/// the sysroot, and which linker cargo will use and where it's configured.
+pub fn explain_targets(runner: &str, targets: &[String], project_dir: &Path, logger: &Logger<'_>) {
+  let host = rust::host_triple();
+  let toolchain = command_output("rustup", &["show", "active-toolchain"]);
+  let rustc_version = command_output("rustc", &["--version"]);
+  let sysroot = command_output("rustc", &["--print", "sysroot"]);
+  let installed_targets = rust::installed_targets();
+
+  if runner != "cargo" {
+    logger.warn(format!(
+      "The `{}` runner may provide its own toolchains, the local toolchain below might not be used.",
+      runner
+    ));
+  }
+
+  let host_target = host.clone().into_iter().collect::<Vec<String>>();
+  let targets = if targets.is_empty() {
+    &host_target
+  } else {
+    targets
+  };
+  for target in targets {
+    let mut lines = Vec::new();
+    let is_host = host.as_deref() == Some(target.as_str());
+    lines.push(format!(
+      "Target {}{}",
+      target,
+      if is_host { " (host)" } else { "" }
+    ));
+
+    lines.push(format!(
+      "  toolchain: {} ({})",
+      toolchain.as_deref().unwrap_or("unknown, rustup not found"),
+      rustc_version.as_deref().unwrap_or("rustc not found")
+    ));
+
+    let installed = match &installed_targets {
+      Some(installed) if installed.contains(target) => "yes".to_string(),
+      Some(_) => format!("no, run `rustup target add {}` to install it", target),
+      None => "unknown, rustup not found".into(),
+    };
+    lines.push(format!("  installed: {}", installed));
+
+    match &sysroot {
+      Some(sysroot) => {
+        let std_dir = Path::new(sysroot).join("lib/rustlib").join(target);
+        lines.push(format!("  sysroot: {}", sysroot));
+        lines.push(format!(
+          "  standard library: {}{}",
+          std_dir.display(),
+          if std_dir.exists() { "" } else { " (missing)" }
+        ));
+      }
+      None => lines.push("  sysroot: unknown, rustc not found".into()),
+    }
+
+    let linker_var = format!(
+      "CARGO_TARGET_{}_LINKER",
+      target.to_uppercase().replace('-', "_")
+    );
+    let linker = if let Ok(linker) = var(&linker_var) {
+      Some((linker, format!("the {} environment variable", linker_var)))
+    } else if let Some((linker, config_path)) = rust::cargo_config_linker(project_dir, target) {
+      Some((linker, config_path.display().to_string()))
+    } else if is_host {
+      None
+    } else {
+      rust::cross_linker(target).map(|(linker, package)| {
+        (
+          linker.to_string(),
+          format!(
+            "expected for cross compiling, provided by `{}` on Debian/Ubuntu",
+            package
+          ),
+        )
+      })
+    };
+    match linker {
+      Some((linker, source)) => lines.push(format!(
+        "  linker: {} (from {}){}",
+        linker,
+        source,
+        if rust::is_in_path(&linker) {
+          ""
+        } else {
+          ", not found"
+        }
+      )),
+      None => lines.push("  linker: the rustc default for the target".into()),
+    }
+
+    logger.log(lines.join("\n"));
+  }
+}
+
+/// Runs the program and returns its trimmed stdout, or `None` if it isn't available or fails.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+  let output = Command::new(program).args(args).output().ok()?;
+  if !output.status.success() {
+    return None;
+  }
+  Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,141 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+use tauri_bundler::bundle::{Bundle, Settings};
+
+use std::{
+  collections::HashMap,
+  fs::rename,
+  path::{Path, PathBuf},
+};
+
+/// The characters that aren't allowed on file names on every platform.
+pub const ILLEGAL_CHARACTERS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// The compression extensions of the archive bundles, such as `.tar.gz` or the Windows updater `.msi.zip`.
+const COMPRESSION_EXTENSIONS: &[&str] = &["gz", "xz", "zst", "bz2", "zip"];
+
+/// Renames the file bundles with the `tauri > bundle > fileNameTemplate` template, updating their paths.
+///
+/// The bundles are renamed in place, and two bundles that would get the same name are rejected before renaming any of them.
+pub fn rename_bundles(
+  bundles: &mut [Bundle],
+  template: &str,
+  settings: &Settings,
+  target: &str,
+) -> crate::Result<()> {
+  let mut renames: Vec<(PathBuf, PathBuf)> = Vec::new();
+  let mut sources: HashMap<PathBuf, &Path> = HashMap::new();
+  for path in bundles.iter().flat_map(|bundle| &bundle.bundle_paths) {
+    if path.is_dir() {
+      continue;
+    }
+    let file_name = path
+      .file_name()
+      .map(|file_name| file_name.to_string_lossy().into_owned())
+      .unwrap_or_default();
+    let name = render(
+      template,
+      settings.product_name(),
+      settings.version_string(),
+      settings.binary_arch(),
+      target,
+      &extension(&file_name),
+    );
+    if name.is_empty() || name == "." || name == ".." {
+      return Err(anyhow::anyhow!(
+        "The `tauri > bundle > fileNameTemplate` template `{}` renders an empty file name for {}",
+        template,
+        path.display()
+      ));
+    }
+    let renamed = path.with_file_name(&name);
+    if let Some(source) = sources.insert(renamed.clone(), path) {
+      return Err(anyhow::anyhow!(
+        "The `tauri > bundle > fileNameTemplate` template `{}` names both {} and {} as `{}`, add a distinguishing token such as `{{ext}}` or `{{arch}}`",
+        template,
+        source.display(),
+        path.display(),
+        name
+      ));
+    }
+    renames.push((path.clone(), renamed));
+  }
+
+  for (path, renamed) in &renames {
+    if path != renamed {
+      rename(path, renamed).with_context(|| {
+        format!(
+          "failed to rename {} to {}",
+          path.display(),
+          renamed.display()
+        )
+      })?;
+    }
+  }
+  for path in bundles
+    .iter_mut()
+    .flat_map(|bundle| bundle.bundle_paths.iter_mut())
+  {
+    if let Some((_, renamed)) = renames.iter().find(|(source, _)| source == path) {
+      *path = renamed.clone();
+    }
+  }
+  Ok(())
+}
+
+/// Replaces the template tokens, sanitizing the values and the rendered name.
+fn render(
+  template: &str,
+  product_name: &str,
+  version: &str,
+  arch: &str,
+  target: &str,
+  ext: &str,
+) -> String {
+  let name = template
+    .replace("{productName}", product_name)
+    .replace("{version}", version)
+    .replace("{arch}", arch)
+    .replace("{target}", target)
+    .replace("{ext}", ext);
+  name
+    .trim()
+    .trim_end_matches('.')
+    .chars()
+    .map(|c| {
+      if c.is_control() || ILLEGAL_CHARACTERS.contains(&c) {
+        '_'
+      } else {
+        c
+      }
+    })
+    .collect()
+}
+
+/// The extension of a bundle file name, including the inner extension of the compressed archives,
+/// e.g. `deb`, `tar.gz` or `AppImage.tar.gz`.
+fn extension(file_name: &str) -> String {
+  let mut parts: Vec<&str> = file_name.rsplit('.').collect();
+  // the file name without a dot has no extension
+  if parts.len() < 2 {
+    return String::new();
+  }
+  parts.pop();
+  let mut extension = vec![parts[0]];
+  if COMPRESSION_EXTENSIONS.contains(&parts[0]) {
+    for part in parts.iter().skip(1).take(2) {
+      let is_word = !part.is_empty() && part.chars().all(|c| c.is_ascii_alphabetic());
+      if !is_word {
+        break;
+      }
+      extension.insert(0, part);
+      if *part != "tar" {
+        break;
+      }
+    }
+  }
+  extension.join(".")
+}
@@ -0,0 +1,159 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tauri_bundler::bundle::{Bundle, PackageType, Settings};
+
+use std::{
+  fs::{read_dir, read_to_string, write, File},
+  io::copy,
+  path::{Path, PathBuf},
+  time::UNIX_EPOCH,
+};
+
+/// The name of the file on the out dir storing the fingerprint of the last bundling.
+const FINGERPRINT_FILE_NAME: &str = ".tauri-bundle-fingerprint.json";
+
+/// The hash of the bundler inputs: the binaries, the bundle settings, the resources modification times
+/// and the contents of the files the settings reference, such as the icons and the WiX template.
+pub struct BundleFingerprint {
+  hash: String,
+}
+
+impl BundleFingerprint {
+  /// Computes the fingerprint of the given bundler settings.
+  ///
+  /// `inputs` describes the resolved bundle configuration, since the bundler settings aren't serializable.
+  pub fn new(settings: &Settings, inputs: &str) -> crate::Result<Self> {
+    let mut hasher = Sha256::new();
+    hasher.update(inputs.as_bytes());
+    for binary in settings.binaries() {
+      let path = settings.binary_path(binary);
+      let mut file =
+        File::open(&path).with_context(|| format!("failed to open {}", path.display()))?;
+      copy(&mut file, &mut hasher)?;
+    }
+    for path in settings.external_binaries().chain(
+      settings
+        .resources()
+        .map(|resource| resource.map(|(src, _)| src)),
+    ) {
+      let path = path?;
+      let modified = path
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_nanos())
+        .unwrap_or_default();
+      hasher.update(format!("{}:{}\n", path.display(), modified).as_bytes());
+    }
+    for path in referenced_paths(settings)? {
+      hash_path(&mut hasher, &path)?;
+    }
+    Ok(Self {
+      hash: hex::encode(hasher.finalize()),
+    })
+  }
+
+  /// Gets the bundles of the previous run if it had the same fingerprint and all of its artifacts still exist.
+  pub fn cached_bundles(&self, out_dir: &Path) -> Option<Vec<Bundle>> {
+    let cache = read_to_string(out_dir.join(FINGERPRINT_FILE_NAME)).ok()?;
+    let cache: serde_json::Value = serde_json::from_str(&cache).ok()?;
+    if cache["fingerprint"].as_str()? != self.hash {
+      return None;
+    }
+    let mut bundles = Vec::new();
+    for bundle in cache["bundles"].as_array()? {
+      let package_type = PackageType::from_short_name(bundle["packageType"].as_str()?)?;
+      let bundle_paths = bundle["bundlePaths"]
+        .as_array()?
+        .iter()
+        .map(|path| path.as_str().map(PathBuf::from))
+        .collect::<Option<Vec<PathBuf>>>()?;
+      if !bundle_paths.iter().all(|path| path.exists()) {
+        return None;
+      }
+      bundles.push(Bundle {
+        package_type,
+        bundle_paths,
+      });
+    }
+    Some(bundles)
+  }
+
+  /// Stores the fingerprint and the produced bundles on the out dir for the next run.
+  pub fn save(&self, out_dir: &Path, bundles: &[Bundle]) -> crate::Result<()> {
+    let bundles: Vec<serde_json::Value> = bundles
+      .iter()
+      .map(|bundle| {
+        json!({
+          "packageType": bundle.package_type.short_name(),
+          "bundlePaths": bundle.bundle_paths,
+        })
+      })
+      .collect();
+    let path = out_dir.join(FINGERPRINT_FILE_NAME);
+    write(
+      &path,
+      serde_json::to_string_pretty(&json!({
+        "fingerprint": self.hash,
+        "bundles": bundles,
+      }))?,
+    )
+    .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(())
+  }
+}
+
+/// The files and directories the bundle settings reference by path, so `inputs` only has their paths.
+fn referenced_paths(settings: &Settings) -> crate::Result<Vec<PathBuf>> {
+  let mut paths = Vec::new();
+  for icon in settings.icon_files() {
+    paths.push(icon?);
+  }
+  let macos = settings.macos();
+  paths.extend(
+    macos
+      .license
+      .iter()
+      .chain(&macos.entitlements)
+      // the system frameworks are referenced by name, they don't exist and are skipped
+      .chain(macos.frameworks.iter().flatten())
+      .map(PathBuf::from),
+  );
+  let windows = settings.windows();
+  paths.push(windows.icon_path.clone());
+  if let Some(wix) = &windows.wix {
+    paths.extend(wix.template.clone());
+    paths.extend(wix.fragment_paths.iter().cloned());
+  }
+  paths.extend(settings.deb().files.values().cloned());
+  // the debian files are a map, sort the paths so the hash doesn't depend on its order
+  paths.sort();
+  paths.dedup();
+  Ok(paths)
+}
+
+/// Hashes the path and the contents of a file, or of each file of a directory. Missing paths are skipped.
+fn hash_path(hasher: &mut Sha256, path: &Path) -> crate::Result<()> {
+  if path.is_dir() {
+    let mut entries = read_dir(path)
+      .with_context(|| format!("failed to read {}", path.display()))?
+      .map(|entry| entry.map(|entry| entry.path()))
+      .collect::<std::io::Result<Vec<PathBuf>>>()?;
+    entries.sort();
+    for entry in entries {
+      hash_path(hasher, &entry)?;
+    }
+  } else if path.is_file() {
+    hasher.update(format!("{}\n", path.display()).as_bytes());
+    let mut file =
+      File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    copy(&mut file, hasher)?;
+  }
+  Ok(())
+}
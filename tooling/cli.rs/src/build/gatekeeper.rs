@@ -0,0 +1,51 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+
+use std::{path::Path, process::Command};
+
+/// The result of a Gatekeeper assessment.
+pub struct Assessment {
+  /// Whether Gatekeeper would let the user open the bundle.
+  pub accepted: bool,
+  /// The `spctl` output, e.g. the signature source or the rejection reason.
+  pub details: String,
+}
+
+/// Runs the Gatekeeper assessment of a macOS `.app` or `.dmg` bundle with `spctl`.
+///
+/// Unlike `codesign --verify`, it checks the whole chain the user's Mac checks on the first launch:
+/// the Developer ID signature and the notarization ticket.
+/// The `.app` is assessed for execution and the `.dmg` for opening by its primary signature.
+pub fn assess(path: &Path) -> crate::Result<Assessment> {
+  let mut command = Command::new("spctl");
+  command.arg("--assess").arg("--verbose");
+  if path
+    .extension()
+    .map_or(false, |extension| extension == "dmg")
+  {
+    command
+      .args(&["--type", "open"])
+      .args(&["--context", "context:primary-signature"]);
+  } else {
+    command.args(&["--type", "execute"]);
+  }
+  let output = command
+    .arg(path)
+    .output()
+    .with_context(|| "failed to run `spctl`")?;
+
+  // spctl prints the assessment on stderr, e.g. `<path>: accepted\nsource=Notarized Developer ID`
+  let prefix = format!("{}: ", path.display());
+  let details = String::from_utf8_lossy(&output.stderr)
+    .lines()
+    .map(|line| line.strip_prefix(prefix.as_str()).unwrap_or(line))
+    .collect::<Vec<_>>()
+    .join(", ");
+  Ok(Assessment {
+    accepted: output.status.success(),
+    details,
+  })
+}
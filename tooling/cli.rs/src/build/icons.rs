@@ -0,0 +1,139 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use tauri_bundler::bundle::PackageType;
+
+use std::{
+  fs::File,
+  io::Read,
+  path::{Path, PathBuf},
+};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const ICO_SIGNATURE: &[u8] = &[0, 0, 1, 0];
+const ICNS_SIGNATURE: &[u8] = b"icns";
+/// The smallest icon size of the macOS `.icns` files.
+const MIN_ICNS_SIZE: u32 = 16;
+
+/// The image formats of the bundle icons, detected from the file contents.
+enum IconFormat {
+  Png { width: u32, height: u32 },
+  Ico,
+  Icns,
+}
+
+/// Checks the bundle icons before bundling, so the problems are reported together instead of failing deep in the bundler.
+///
+/// Every icon must exist and be a valid PNG, ICO or ICNS image, then the icons must cover the formats required by the
+/// given package types: an `.ico` for the Windows installer, an `.icns` or square PNGs for the macOS app
+/// and PNGs for the Linux packages. Returns the list of problems, empty if the icons are valid.
+pub fn validate_icons(icons: &[String], package_types: &[PackageType]) -> Vec<String> {
+  let mut problems = Vec::new();
+  let mut formats = Vec::new();
+  for pattern in icons {
+    let paths: Vec<PathBuf> = match glob::glob(pattern) {
+      Ok(paths) => paths.filter_map(Result::ok).collect(),
+      Err(e) => {
+        problems.push(format!("the icon pattern {} is invalid: {}", pattern, e));
+        continue;
+      }
+    };
+    if paths.is_empty() {
+      problems.push(format!("the icon {} doesn't exist", pattern));
+    }
+    for path in paths {
+      match read_format(&path) {
+        Ok(Some(format)) => formats.push((path, format)),
+        Ok(None) => problems.push(format!(
+          "the icon {} isn't a valid PNG, ICO or ICNS image",
+          path.display()
+        )),
+        Err(e) => problems.push(format!("failed to read the icon {}: {}", path.display(), e)),
+      }
+    }
+  }
+
+  let pngs: Vec<(&PathBuf, u32, u32)> = formats
+    .iter()
+    .filter_map(|(path, format)| match format {
+      IconFormat::Png { width, height } => Some((path, *width, *height)),
+      _ => None,
+    })
+    .collect();
+  let has_ico = formats
+    .iter()
+    .any(|(_, format)| matches!(format, IconFormat::Ico));
+  let has_icns = formats
+    .iter()
+    .any(|(_, format)| matches!(format, IconFormat::Icns));
+
+  let bundles_windows =
+    package_types.contains(&PackageType::WindowsMsi) || package_types.contains(&PackageType::Nsis);
+  if bundles_windows && !has_ico {
+    problems.push("the Windows installers require an `.ico` icon".into());
+  }
+  let bundles_macos =
+    package_types.contains(&PackageType::MacOsBundle) || package_types.contains(&PackageType::Dmg);
+  if bundles_macos && !has_icns {
+    // the `.icns` file is generated from the PNG icons
+    if pngs.is_empty() {
+      problems.push("the macOS app requires an `.icns` icon or PNG icons to generate it".into());
+    }
+    for (path, width, height) in &pngs {
+      if width != height {
+        problems.push(format!(
+          "the icon {} is {}x{}, the macOS icons must be square",
+          path.display(),
+          width,
+          height
+        ));
+      } else if *width < MIN_ICNS_SIZE {
+        problems.push(format!(
+          "the icon {} is {}x{}, the macOS icons must be at least {}x{}",
+          path.display(),
+          width,
+          height,
+          MIN_ICNS_SIZE,
+          MIN_ICNS_SIZE
+        ));
+      }
+    }
+  }
+  let bundles_linux = package_types.contains(&PackageType::Deb)
+    || package_types.contains(&PackageType::AppImage)
+    || package_types.contains(&PackageType::Flatpak)
+    || package_types.contains(&PackageType::Snap);
+  if bundles_linux && pngs.is_empty() {
+    problems.push("the Linux packages require PNG icons".into());
+  }
+
+  problems
+}
+
+/// Reads the image format from the file signature, and the dimensions of PNG images from their IHDR chunk.
+fn read_format(path: &Path) -> std::io::Result<Option<IconFormat>> {
+  let mut header = [0; 24];
+  let mut file = File::open(path)?;
+  let mut read = 0;
+  while read < header.len() {
+    match file.read(&mut header[read..])? {
+      0 => break,
+      n => read += n,
+    }
+  }
+  let header = &header[..read];
+
+  let format = if header.starts_with(PNG_SIGNATURE) && header.len() == 24 {
+    let width = u32::from_be_bytes([header[16], header[17], header[18], header[19]]);
+    let height = u32::from_be_bytes([header[20], header[21], header[22], header[23]]);
+    Some(IconFormat::Png { width, height })
+  } else if header.starts_with(ICO_SIGNATURE) {
+    Some(IconFormat::Ico)
+  } else if header.starts_with(ICNS_SIGNATURE) {
+    Some(IconFormat::Icns)
+  } else {
+    None
+  };
+  Ok(format)
+}
@@ -0,0 +1,101 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
+use tauri_bundler::bundle::Bundle;
+
+use crate::helpers::config::Config;
+
+use std::{
+  fs::{read_dir, write},
+  path::{Path, PathBuf},
+  process::Command,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The information of a build run written to the `--report` file.
+pub struct BuildReport<'a> {
+  /// the resolved config.
+  pub config: &'a Config,
+  /// the runner binary, e.g. `cargo`.
+  pub runner: &'a str,
+  /// the target triples built for, `None` being the host target.
+  pub targets: &'a [Option<String>],
+  /// the produced bundles.
+  pub bundles: &'a [Bundle],
+  /// the updater signatures.
+  pub signed_paths: &'a [PathBuf],
+}
+
+impl BuildReport<'_> {
+  /// Serializes the report as a JSON object to the given path.
+  pub fn write(&self, path: &Path) -> crate::Result<()> {
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .expect("system clock is incorrect")
+      .as_secs();
+    let config_hash = hex::encode(Sha256::digest(
+      serde_json::to_string(self.config)?.as_bytes(),
+    ));
+    let bundles: Vec<JsonValue> = self
+      .bundles
+      .iter()
+      .map(|bundle| {
+        let paths: Vec<JsonValue> = bundle
+          .bundle_paths
+          .iter()
+          .map(|path| json!({ "path": path, "size": path_size(path) }))
+          .collect();
+        json!({
+          "packageType": bundle.package_type.short_name(),
+          "bundlePaths": paths,
+        })
+      })
+      .collect();
+
+    let report = json!({
+      "timestamp": timestamp,
+      "configHash": config_hash,
+      "targets": self.targets,
+      "cargoVersion": runner_version(self.runner),
+      "bundles": bundles,
+      "updaterSigned": !self.signed_paths.is_empty(),
+      "signatures": self.signed_paths,
+    });
+    write(path, serde_json::to_string_pretty(&report)?)
+      .with_context(|| format!("failed to write the build report to {}", path.display()))?;
+    Ok(())
+  }
+}
+
+/// Gets the version of the runner by invoking it with `--version`.
+fn runner_version(runner: &str) -> Option<String> {
+  let output = Command::new(runner).arg("--version").output().ok()?;
+  if output.status.success() {
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+  } else {
+    None
+  }
+}
+
+/// Gets the size in bytes of the given file, or the sum of its files if it's a directory (e.g. a macOS `.app`).
+fn path_size(path: &Path) -> u64 {
+  if path.is_dir() {
+    read_dir(path)
+      .map(|entries| {
+        entries
+          .filter_map(Result::ok)
+          .map(|entry| path_size(&entry.path()))
+          .sum()
+      })
+      .unwrap_or_default()
+  } else {
+    path
+      .metadata()
+      .map(|metadata| metadata.len())
+      .unwrap_or_default()
+  }
+}
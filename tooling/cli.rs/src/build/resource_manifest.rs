@@ -0,0 +1,118 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  fs::read_to_string,
+  path::{Component, Path, PathBuf},
+};
+
+/// The prefix of the `tauri > bundle > resources` entries referencing a resource manifest, e.g. `@resources.json`.
+pub const MANIFEST_PREFIX: char = '@';
+
+/// Reads the `(source, destination)` resource mappings of the given resource manifests.
+///
+/// A `.json` manifest is an object mapping each source to its destination, e.g. `{ "assets/app.db": "data/app.db" }`.
+/// The other manifests have a `<source> -> <destination>` mapping per line, where the empty lines and the `#` comments are skipped.
+/// The sources are relative to the manifest directory and the destinations to the bundle resources directory.
+///
+/// Each source must be an existing file, and two mappings can't have the same destination.
+pub fn read_resource_manifests(manifests: &[&str]) -> crate::Result<Vec<(PathBuf, PathBuf)>> {
+  let mut mappings = Vec::new();
+  let mut destinations: HashMap<PathBuf, PathBuf> = HashMap::new();
+  for manifest in manifests {
+    let manifest = Path::new(manifest);
+    let contents = read_to_string(manifest).with_context(|| {
+      format!(
+        "failed to read the resource manifest {}",
+        manifest.display()
+      )
+    })?;
+    let is_json = manifest.extension().map_or(false, |ext| ext == "json");
+    let entries: Vec<(String, String)> = if is_json {
+      serde_json::from_str::<BTreeMap<String, String>>(&contents)
+        .with_context(|| {
+          format!(
+            "failed to parse the resource manifest {}, expected an object mapping each source to its destination",
+            manifest.display()
+          )
+        })?
+        .into_iter()
+        .collect()
+    } else {
+      parse_lines(&contents).with_context(|| {
+        format!(
+          "failed to parse the resource manifest {}",
+          manifest.display()
+        )
+      })?
+    };
+
+    let base_dir = manifest.parent().unwrap_or_else(|| Path::new(""));
+    for (source, destination) in entries {
+      let source = base_dir.join(source);
+      if !source.is_file() {
+        return Err(anyhow::anyhow!(
+          "The resource {} of the resource manifest {} doesn't exist or isn't a file",
+          source.display(),
+          manifest.display()
+        ));
+      }
+      let destination = PathBuf::from(destination);
+      if !is_relative_destination(&destination) {
+        return Err(anyhow::anyhow!(
+          "The destination `{}` of the resource manifest {} must be a relative file path without `..` components",
+          destination.display(),
+          manifest.display()
+        ));
+      }
+      // `./data/app.db` and `data/app.db` are the same destination
+      let normalized: PathBuf = destination.components().collect();
+      if let Some(previous) = destinations.insert(normalized, source.clone()) {
+        return Err(anyhow::anyhow!(
+          "Both {} and {} are mapped to the `{}` resource destination",
+          previous.display(),
+          source.display(),
+          destination.display()
+        ));
+      }
+      mappings.push((source, destination));
+    }
+  }
+  Ok(mappings)
+}
+
+/// Parses the `<source> -> <destination>` lines of a text resource manifest.
+fn parse_lines(contents: &str) -> crate::Result<Vec<(String, String)>> {
+  let mut entries = Vec::new();
+  for (index, line) in contents.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    match line.split_once("->") {
+      Some((source, destination)) if !source.trim().is_empty() => {
+        entries.push((source.trim().to_string(), destination.trim().to_string()));
+      }
+      _ => {
+        return Err(anyhow::anyhow!(
+          "invalid mapping `{}` on line {}, expected `<source> -> <destination>`",
+          line,
+          index + 1
+        ))
+      }
+    }
+  }
+  Ok(entries)
+}
+
+/// Whether the destination stays inside the bundle resources directory and names a file.
+fn is_relative_destination(destination: &Path) -> bool {
+  destination.file_name().is_some()
+    && destination
+      .components()
+      .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
@@ -3,20 +3,34 @@
 // SPDX-License-Identifier: MIT
 
 use std::{
+  collections::HashMap,
+  env::{split_paths, var, var_os},
   fs::File,
-  io::Read,
+  io::{BufRead, BufReader, Read},
   path::{Path, PathBuf},
-  process::Command,
+  process::{Command, Stdio},
   str::FromStr,
+  thread::spawn,
 };
 
 use anyhow::Context;
 use serde::Deserialize;
 
-use crate::helpers::{app_paths::tauri_dir, config::Config, manifest::Manifest};
+use super::{
+  resource_manifest::{read_resource_manifests, MANIFEST_PREFIX},
+  BuildError,
+};
+use crate::helpers::{
+  app_paths::tauri_dir,
+  colors_enabled,
+  config::{BundleResources, Config},
+  manifest::Manifest,
+  ColorChoice, Logger,
+};
 use tauri_bundler::{
-  AppCategory, BundleBinary, BundleSettings, DebianSettings, MacOsSettings, PackageSettings,
-  UpdaterSettings, WindowsSettings,
+  AppCategory, BundleBinary, BundleSettings, DebianSettings, FlatpakSettings, MacOsSettings,
+  NsisSettings, PackageSettings, RpmSettings, SnapSettings, UpdaterArchiveFormat, UpdaterSettings,
+  WindowsSettings,
 };
 
 /// The `workspace` section of the app configuration (read from Cargo.toml).
@@ -85,41 +99,497 @@ struct CargoBuildConfig {
   target_dir: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct CargoTargetConfig {
+  linker: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct CargoConfig {
   build: Option<CargoBuildConfig>,
+  target: Option<HashMap<String, CargoTargetConfig>>,
+}
+
+/// The target name used for the output directory of the macOS universal binaries.
+pub const UNIVERSAL_MACOS_TARGET: &str = "universal-apple-darwin";
+/// The macOS targets combined into the universal binaries.
+pub const UNIVERSAL_MACOS_ARCHES: [&str; 2] = ["x86_64-apple-darwin", "aarch64-apple-darwin"];
+
+/// The target triple components of the platforms `tauri build` can't build for yet, remove them as their support lands.
+const UNSUPPORTED_TARGET_COMPONENTS: &[&str] = &["android", "androideabi", "ios"];
+
+/// Whether the target triple is a mobile target, e.g. `aarch64-linux-android` or `aarch64-apple-ios`.
+pub fn is_unsupported_mobile_target(target: &str) -> bool {
+  UNSUPPORTED_TARGET_COMPONENTS
+    .iter()
+    .any(|component| target.split('-').any(|part| part == *component))
+}
+
+/// Options for the `build` invocation of the runner.
+#[derive(Clone, Default)]
+pub struct BuildOptions {
+  /// the target triple to build against.
+  pub target: Option<String>,
+  /// the directory for all generated artifacts.
+  pub target_dir: Option<PathBuf>,
+  /// whether to build with the debug profile or not.
+  pub debug: bool,
+  /// the cargo profile to build with (`--profile`), replacing the `debug` flag.
+  pub profile: Option<String>,
+  /// the number of parallel cargo jobs (`--jobs`).
+  pub jobs: Option<u32>,
+  /// the only binary to build (`--bin`).
+  pub bin: Option<String>,
+  /// whether to require the lockfile and cache to be up to date (`--frozen`).
+  pub frozen: bool,
+  /// whether to run without accessing the network (`--offline`).
+  pub offline: bool,
+  /// whether to require Cargo.lock to be up to date (`--locked`).
+  pub locked: bool,
+  /// the cargo features to enable in addition to `custom-protocol`.
+  pub features: Vec<String>,
+  /// whether to disable the default features (`--no-default-features`).
+  pub no_default_features: bool,
+  /// whether to enable all features (`--all-features`).
+  pub all_features: bool,
+  /// the rustc flags appended to the `RUSTFLAGS` environment variable.
+  pub rust_flags: Vec<String>,
+  /// the environment variables set on the runner invocation, e.g. the build constants of the app.
+  pub envs: Vec<(String, String)>,
+  /// whether the runner output should be inherited instead of logged with the `tauri:build:cargo` prefix.
+  pub raw_output: bool,
+  /// the arguments passed after `--`, appended as-is to the runner invocation.
+  pub args: Vec<String>,
 }
 
-pub fn build_project(runner: String, target: &Option<String>, debug: bool) -> crate::Result<()> {
-  let mut args = vec!["build", "--features=custom-protocol"];
+/// The runner flags managed by the CLI, which can't be passed after `--`.
+const MANAGED_RUNNER_FLAGS: &[&str] = &["--target", "--release", "-r", "--profile", "--target-dir"];
+
+/// Checks that the arguments passed after `--` don't collide with the runner flags set by the CLI.
+pub fn validate_runner_args(args: &[String]) -> crate::Result<()> {
+  for arg in args {
+    let flag = arg.split('=').next().unwrap_or_default();
+    if MANAGED_RUNNER_FLAGS.contains(&flag) {
+      return Err(anyhow::anyhow!(
+        "`{}` can't be passed to the runner directly, use the `tauri build` option instead (e.g. `--target`, `--debug` or `--profile`)",
+        flag
+      ));
+    }
+  }
+  Ok(())
+}
 
-  if let Some(target) = target {
+/// Splits the runner command into the program and its arguments, e.g. `sccache cargo` into `sccache` and `["cargo"]`.
+///
+/// The tokens are separated by whitespace. Quotes group a token containing whitespace: single quotes keep their content as-is,
+/// double quotes too except for the `\"` and `\\` escapes. Backslashes outside of quotes are literal,
+/// so Windows paths such as `C:\tools\cargo.exe` don't need quoting.
+pub fn parse_runner(runner: &str) -> crate::Result<(String, Vec<String>)> {
+  let mut tokens = Vec::new();
+  let mut token: Option<String> = None;
+  let mut chars = runner.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      c if c.is_whitespace() => {
+        if let Some(token) = token.take() {
+          tokens.push(token);
+        }
+      }
+      '\'' | '"' => {
+        let quote = c;
+        let token = token.get_or_insert_with(String::new);
+        loop {
+          match chars.next() {
+            Some(c) if c == quote => break,
+            Some('\\') if quote == '"' => match chars.next() {
+              Some(c) if c == '"' || c == '\\' => token.push(c),
+              Some(c) => {
+                token.push('\\');
+                token.push(c);
+              }
+              None => token.push('\\'),
+            },
+            Some(c) => token.push(c),
+            None => {
+              return Err(anyhow::anyhow!(
+                "invalid runner `{}`: unterminated {} quote",
+                runner,
+                quote
+              ))
+            }
+          }
+        }
+      }
+      c => token.get_or_insert_with(String::new).push(c),
+    }
+  }
+  tokens.extend(token);
+  if tokens.is_empty() {
+    return Err(anyhow::anyhow!("the runner command is empty"));
+  }
+  let program = tokens.remove(0);
+  Ok((program, tokens))
+}
+
+/// Splits the runner command with [`parse_runner`], appending the `build` subcommand
+/// unless the runner already ends with it, e.g. `cargo-zigbuild build`.
+pub fn runner_build_args(runner: &str) -> crate::Result<(String, Vec<String>)> {
+  let (program, mut args) = parse_runner(runner)?;
+  if args.last().map(String::as_str) != Some("build") {
+    args.push("build".into());
+  }
+  Ok((program, args))
+}
+
+/// Creates the `build` invocation of the runner with the given options.
+///
+/// The runner command is split with [`runner_build_args`].
+pub fn build_command(runner: &str, options: &BuildOptions) -> crate::Result<Command> {
+  let (program, runner_args) = runner_build_args(runner)?;
+  let features = std::iter::once("custom-protocol")
+    .chain(options.features.iter().map(String::as_str))
+    .collect::<Vec<&str>>()
+    .join(",");
+  let features = format!("--features={}", features);
+  let mut args = vec![&*features];
+
+  if options.no_default_features {
+    args.push("--no-default-features");
+  }
+
+  if options.all_features {
+    args.push("--all-features");
+  }
+
+  if let Some(target) = &options.target {
     args.push("--target");
     args.push(target);
   }
 
-  if !debug {
+  if let Some(profile) = &options.profile {
+    args.push("--profile");
+    args.push(profile);
+  } else if !options.debug {
     args.push("--release");
   }
 
-  let status = Command::new(&runner)
-    .args(args)
-    .status()
-    .with_context(|| format!("failed to run {}", runner))?;
+  if options.frozen {
+    args.push("--frozen");
+  }
+
+  if options.offline {
+    args.push("--offline");
+  }
+
+  if options.locked {
+    args.push("--locked");
+  }
+
+  let mut command = Command::new(program);
+  command.args(&runner_args);
+  command.args(args);
+
+  if let Some(target_dir) = &options.target_dir {
+    command.arg("--target-dir").arg(target_dir);
+  }
+
+  if let Some(jobs) = options.jobs {
+    command.arg("--jobs").arg(jobs.to_string());
+  }
+
+  if let Some(bin) = &options.bin {
+    command.arg("--bin").arg(bin);
+  }
+
+  command.args(&options.args);
+
+  if !options.rust_flags.is_empty() {
+    let mut rust_flags = var("RUSTFLAGS").unwrap_or_default();
+    for flag in &options.rust_flags {
+      if !rust_flags.is_empty() {
+        rust_flags.push(' ');
+      }
+      rust_flags.push_str(flag);
+    }
+    command.env("RUSTFLAGS", rust_flags);
+  }
+
+  command.envs(options.envs.iter().map(|(k, v)| (k, v)));
+
+  Ok(command)
+}
+
+/// Gets the host target triple from `rustc -vV`.
+pub fn host_triple() -> Option<String> {
+  let output = Command::new("rustc").arg("-vV").output().ok()?;
+  String::from_utf8_lossy(&output.stdout)
+    .lines()
+    .find_map(|line| line.strip_prefix("host: "))
+    .map(|host| host.trim().to_string())
+}
+
+impl BuildOptions {
+  /// Describes the enabled cargo features, e.g. `custom-protocol, menu (without the default features)`.
+  pub fn features_description(&self) -> String {
+    if self.all_features {
+      return "all".into();
+    }
+    let mut description = std::iter::once("custom-protocol")
+      .chain(self.features.iter().map(String::as_str))
+      .collect::<Vec<&str>>()
+      .join(", ");
+    if self.no_default_features {
+      description.push_str(" (without the default features)");
+    }
+    description
+  }
+
+  /// The name of the directory where cargo outputs the artifacts of the selected profile.
+  pub fn profile_dir(&self) -> &str {
+    match self.profile.as_deref() {
+      Some("dev") | Some("test") => "debug",
+      Some("release") | Some("bench") => "release",
+      Some(profile) => profile,
+      None if self.debug => "debug",
+      None => "release",
+    }
+  }
+}
+
+pub fn build_project(runner: String, options: &BuildOptions) -> crate::Result<()> {
+  let mut command = build_command(&runner, options)?;
+
+  let status = if options.raw_output {
+    command
+      .args(&["--color", ColorChoice::get().cargo_color()])
+      .status()
+      .with_context(|| format!("failed to run {}", runner))?
+  } else {
+    // the cargo output isn't a terminal anymore, so it follows the colors of the log lines
+    command.args(&["--color", if colors_enabled() { "always" } else { "never" }]);
+    let mut child = command
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .with_context(|| format!("failed to run {}", runner))?;
+    let stdout = child.stdout.take().expect("failed to get stdout handle");
+    let stderr = child.stderr.take().expect("failed to get stderr handle");
+    let stdout_thread = spawn(move || log_output(stdout));
+    let stderr_thread = spawn(move || log_output(stderr));
+    let status = child
+      .wait()
+      .with_context(|| format!("failed to wait on {}", runner))?;
+    let _ = stdout_thread.join();
+    let lockfile_outdated = stderr_thread.join().unwrap_or_default();
+    if !status.success() && options.locked && lockfile_outdated {
+      return Err(BuildError::LockfileOutdated.into());
+    }
+    status
+  };
   if !status.success() {
-    return Err(anyhow::anyhow!(format!(
-      "Result of `{} build` operation was unsuccessful: {}",
-      runner, status
-    )));
+    return Err(BuildError::CargoFailed { runner, status }.into());
+  }
+
+  Ok(())
+}
+
+/// Checks that the given target triples are installed, so the build fails before compiling anything.
+///
+/// The check is skipped if rustup isn't available.
+pub fn check_targets_installed(targets: &[String]) -> crate::Result<()> {
+  let installed = match installed_targets() {
+    Some(installed) => installed,
+    None => return Ok(()),
+  };
+  for target in targets {
+    if !installed.contains(target) {
+      return Err(anyhow::anyhow!(
+        "The `{}` target is not installed, run `rustup target add {}` to install it.",
+        target,
+        target
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Gets the targets installed with `rustup target list --installed`, or `None` if rustup isn't available.
+pub fn installed_targets() -> Option<Vec<String>> {
+  let output = Command::new("rustup")
+    .args(&["target", "list", "--installed"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())?;
+  Some(
+    String::from_utf8_lossy(&output.stdout)
+      .lines()
+      .map(|line| line.trim().to_string())
+      .collect(),
+  )
+}
+
+/// The linkers required by the known cross targets, with the Debian/Ubuntu package providing them.
+const CROSS_LINKERS: &[(&str, &str, &str)] = &[
+  (
+    "aarch64-unknown-linux-gnu",
+    "aarch64-linux-gnu-gcc",
+    "gcc-aarch64-linux-gnu",
+  ),
+  (
+    "armv7-unknown-linux-gnueabihf",
+    "arm-linux-gnueabihf-gcc",
+    "gcc-arm-linux-gnueabihf",
+  ),
+  (
+    "x86_64-unknown-linux-gnu",
+    "x86_64-linux-gnu-gcc",
+    "gcc-x86-64-linux-gnu",
+  ),
+  (
+    "x86_64-pc-windows-gnu",
+    "x86_64-w64-mingw32-gcc",
+    "mingw-w64",
+  ),
+  ("i686-pc-windows-gnu", "i686-w64-mingw32-gcc", "mingw-w64"),
+];
+
+/// Checks that the linker of the given cross target is available, returning an actionable message if it isn't.
+///
+/// The linker set with the `CARGO_TARGET_<TRIPLE>_LINKER` environment variable takes precedence over the
+/// `target.<triple>.linker` of the cargo configs of `project_dir`, and the expected gcc prefix is only checked when neither is set.
+/// Targets matching the host and unknown targets without a configured linker are not checked.
+pub fn check_cross_toolchain(target: &str, project_dir: &Path) -> Option<String> {
+  if host_triple().as_deref() == Some(target) {
+    return None;
   }
+  let linker_var = format!(
+    "CARGO_TARGET_{}_LINKER",
+    target.to_uppercase().replace('-', "_")
+  );
+  let configured_linker = if let Ok(linker) = var(&linker_var) {
+    Some((linker, format!("the `{}` environment variable", linker_var)))
+  } else {
+    cargo_config_linker(project_dir, target)
+      .map(|(linker, config_path)| (linker, config_path.display().to_string()))
+  };
+  match configured_linker {
+    Some((linker, source)) if !is_in_path(&linker) => Some(format!(
+      "The `{}` linker required to cross compile to `{}` (set by {}) was not found.",
+      linker, target, source
+    )),
+    Some(_) => None,
+    None => {
+      let (expected_linker, package) = cross_linker(target)?;
+      if is_in_path(expected_linker) {
+        None
+      } else {
+        Some(format!(
+          "The `{}` linker required to cross compile to `{}` was not found. Install the cross toolchain (e.g. `{}` on Debian/Ubuntu) and set the `{}` environment variable to `{}`.",
+          expected_linker, target, package, linker_var, expected_linker
+        ))
+      }
+    }
+  }
+}
 
+/// Gets the gcc linker expected for the given cross target and the Debian/Ubuntu package providing it.
+pub fn cross_linker(target: &str) -> Option<(&'static str, &'static str)> {
+  CROSS_LINKERS
+    .iter()
+    .find(|(t, _, _)| *t == target)
+    .map(|(_, linker, package)| (*linker, *package))
+}
+
+/// Gets the `target.<triple>.linker` of the cargo configs of `dir` and its ancestors, with the config file setting it.
+///
+/// The configs that can't be read or parsed are skipped, cargo reports them when building.
+pub fn cargo_config_linker(dir: &Path, target: &str) -> Option<(String, PathBuf)> {
+  let mut dir = dir.to_path_buf();
+  loop {
+    for config_path in &[dir.join(".cargo/config"), dir.join(".cargo/config.toml")] {
+      let linker = std::fs::read_to_string(config_path)
+        .ok()
+        .and_then(|config| toml::from_str::<CargoConfig>(&config).ok())
+        .and_then(|config| config.target)
+        .and_then(|mut targets| targets.remove(target))
+        .and_then(|target| target.linker);
+      if let Some(linker) = linker {
+        return Some((linker, config_path.clone()));
+      }
+    }
+    if !dir.pop() {
+      return None;
+    }
+  }
+}
+
+/// Checks if the given program exists, either as a path or on one of the `PATH` directories.
+pub fn is_in_path(program: &str) -> bool {
+  let program = Path::new(program);
+  if program.components().count() > 1 {
+    return program.exists();
+  }
+  var_os("PATH")
+    .map(|paths| {
+      split_paths(&paths).any(|dir| {
+        let path = dir.join(program);
+        path.exists() || (cfg!(windows) && path.with_extension("exe").exists())
+      })
+    })
+    .unwrap_or(false)
+}
+
+/// Checks that the `lipo` tool is available to create the universal binaries.
+pub fn check_lipo_installed() -> crate::Result<()> {
+  match Command::new("lipo").arg("-info").output() {
+    Ok(_) => Ok(()),
+    Err(_) => Err(anyhow::anyhow!(
+      "`lipo` not found, make sure the Xcode command line tools are installed (`xcode-select --install`)."
+    )),
+  }
+}
+
+/// Combines the given binaries into an universal binary at `output` with `lipo -create`.
+pub fn lipo(inputs: &[PathBuf], output: &Path) -> crate::Result<()> {
+  let status = Command::new("lipo")
+    .arg("-create")
+    .args(inputs)
+    .arg("-output")
+    .arg(output)
+    .status()
+    .with_context(|| "failed to run lipo")?;
+  if !status.success() {
+    return Err(anyhow::anyhow!(
+      "Failed to create the universal binary {}: {}",
+      output.display(),
+      status
+    ));
+  }
   Ok(())
 }
 
+/// Logs the runner output line by line, returning whether cargo refused to update Cargo.lock because of `--locked`.
+fn log_output(output: impl Read) -> bool {
+  let logger = Logger::new("tauri:build:cargo");
+  let mut lockfile_outdated = false;
+  for line in BufReader::new(output).lines() {
+    match line {
+      Ok(line) => {
+        lockfile_outdated |= line.contains("needs to be updated but --locked was passed");
+        logger.log(line);
+      }
+      Err(_) => break,
+    }
+  }
+  lockfile_outdated
+}
+
 pub struct AppSettings {
   cargo_settings: CargoSettings,
   cargo_package_settings: CargoPackageSettings,
   package_settings: PackageSettings,
+  /// the binary selected with `--bin`, the only one built and bundled.
+  selected_binary: Option<String>,
 }
 
 impl AppSettings {
@@ -156,30 +626,90 @@ impl AppSettings {
       cargo_settings,
       cargo_package_settings,
       package_settings,
+      selected_binary: None,
     })
   }
 
-  pub fn cargo_package_settings(&self) -> &CargoPackageSettings {
-    &self.cargo_package_settings
+  /// Selects the only binary to bundle, it becomes the main binary.
+  ///
+  /// Fails if the crate has no binary with the given name.
+  pub fn select_binary(&mut self, name: &str) -> crate::Result<()> {
+    let names = self.binary_names();
+    if !names.iter().any(|bin| bin == name) {
+      return Err(anyhow::anyhow!(
+        "`--bin {}` doesn't match any binary of the crate, the available binaries are: {}",
+        name,
+        names.join(", ")
+      ));
+    }
+    self.selected_binary.replace(name.to_string());
+    Ok(())
+  }
+
+  /// The cargo name of the main binary: the binary selected with `--bin` or the package name.
+  pub fn main_binary_name(&self) -> &str {
+    self
+      .selected_binary
+      .as_deref()
+      .unwrap_or(&self.cargo_package_settings.name)
+  }
+
+  /// The cargo names of the binaries of the crate: the `[[bin]]` targets, the `src/bin` files and the `src/main.rs` binary.
+  fn binary_names(&self) -> Vec<String> {
+    let mut names: Vec<String> = self
+      .cargo_settings
+      .bin
+      .iter()
+      .flatten()
+      .map(|bin| bin.name.clone())
+      .collect();
+    if let Ok(fs_bins) = std::fs::read_dir(tauri_dir().join("src/bin")) {
+      for entry in fs_bins.filter_map(Result::ok) {
+        if let Some(name) = entry.path().file_stem() {
+          let name = name.to_string_lossy().to_string();
+          if !names.contains(&name) {
+            names.push(name);
+          }
+        }
+      }
+    }
+    if tauri_dir().join("src/main.rs").exists()
+      && !names.contains(&self.cargo_package_settings.name)
+    {
+      names.push(self.cargo_package_settings.name.clone());
+    }
+    names
   }
 
+  /// Gets the bundle settings of the given target triple, `None` being the host target.
   pub fn get_bundle_settings(
     &self,
     config: &Config,
     manifest: &Manifest,
+    target: Option<&str>,
   ) -> crate::Result<BundleSettings> {
     tauri_config_to_bundle_settings(
       manifest,
+      target,
       config.tauri.bundle.clone(),
       config.tauri.system_tray.clone(),
       config.tauri.updater.clone(),
     )
   }
 
-  pub fn get_out_dir(&self, debug: bool) -> crate::Result<PathBuf> {
-    let tauri_dir = tauri_dir();
-    let workspace_dir = get_workspace_dir(&tauri_dir);
-    get_target_dir(&workspace_dir, None, !debug)
+  pub fn get_out_dir(
+    &self,
+    target: Option<&str>,
+    target_dir: Option<&Path>,
+    profile_dir: &str,
+  ) -> crate::Result<PathBuf> {
+    // the binary is located at `<target-dir>/[<triple>/]<profile>`
+    let mut path = get_cargo_target_dir(target_dir)?;
+    if let Some(triple) = target {
+      path.push(triple);
+    }
+    path.push(profile_dir);
+    Ok(path)
   }
 
   pub fn get_package_settings(&self) -> PackageSettings {
@@ -187,6 +717,27 @@ impl AppSettings {
   }
 
   pub fn get_binaries(&self, config: &Config) -> crate::Result<Vec<BundleBinary>> {
+    // the main binary keeps its cargo name if the executable isn't renamed to the product name
+    let product_name = if config.package.rename_executable {
+      config.package.product_name.clone()
+    } else {
+      None
+    };
+    if let Some(selected_binary) = &self.selected_binary {
+      let src_path = self
+        .cargo_settings
+        .bin
+        .iter()
+        .flatten()
+        .find(|bin| &bin.name == selected_binary)
+        .and_then(|bin| bin.path.clone());
+      return Ok(vec![BundleBinary::new(
+        product_name.unwrap_or_else(|| selected_binary.clone()),
+        true,
+      )
+      .set_src_path(src_path)]);
+    }
+
     let mut binaries: Vec<BundleBinary> = vec![];
     if let Some(bin) = &self.cargo_settings.bin {
       let default_run = self
@@ -200,11 +751,7 @@ impl AppSettings {
             || binary.name.as_str() == default_run
           {
             BundleBinary::new(
-              config
-                .package
-                .product_name
-                .clone()
-                .unwrap_or_else(|| binary.name.clone()),
+              product_name.clone().unwrap_or_else(|| binary.name.clone()),
               true,
             )
           } else {
@@ -234,17 +781,13 @@ impl AppSettings {
     if let Some(default_run) = self.package_settings.default_run.as_ref() {
       match binaries.iter_mut().find(|bin| bin.name() == default_run) {
         Some(bin) => {
-          if let Some(product_name) = config.package.product_name.clone() {
+          if let Some(product_name) = product_name {
             bin.set_name(product_name);
           }
         }
         None => {
           binaries.push(BundleBinary::new(
-            config
-              .package
-              .product_name
-              .clone()
-              .unwrap_or_else(|| default_run.to_string()),
+            product_name.unwrap_or_else(|| default_run.to_string()),
             true,
           ));
         }
@@ -253,7 +796,11 @@ impl AppSettings {
 
     match binaries.len() {
       0 => binaries.push(BundleBinary::new(
-        self.package_settings.product_name.clone(),
+        if config.package.rename_executable {
+          self.package_settings.product_name.clone()
+        } else {
+          self.cargo_package_settings.name.clone()
+        },
         true,
       )),
       1 => binaries.get_mut(0).unwrap().set_main(true),
@@ -264,15 +811,21 @@ impl AppSettings {
   }
 }
 
-/// This function determines where 'target' dir is and suffixes it with 'release' or 'debug'
-/// to determine where the compiled binary will be located.
-fn get_target_dir(
-  project_root_dir: &Path,
-  target: Option<String>,
-  is_release: bool,
-) -> crate::Result<PathBuf> {
-  let mut path: PathBuf = match std::env::var_os("CARGO_TARGET_DIR") {
-    Some(target_dir) => target_dir.into(),
+/// Gets the cargo 'target' dir of the app, where the 'release', 'debug' or custom profile directories are located.
+///
+/// The `target_dir` argument takes precedence over the `CARGO_TARGET_DIR` environment variable and the cargo config files.
+pub fn get_cargo_target_dir(target_dir: Option<&Path>) -> crate::Result<PathBuf> {
+  let workspace_dir = get_workspace_dir(&tauri_dir());
+  get_target_dir(&workspace_dir, target_dir)
+}
+
+/// This function determines where 'target' dir is, defaulting to the `target` folder of the project root.
+fn get_target_dir(project_root_dir: &Path, target_dir: Option<&Path>) -> crate::Result<PathBuf> {
+  let target_dir = target_dir
+    .map(Path::to_path_buf)
+    .or_else(|| std::env::var_os("CARGO_TARGET_DIR").map(Into::into));
+  let path: PathBuf = match target_dir {
+    Some(target_dir) => target_dir,
     None => {
       let mut root_dir = project_root_dir.to_path_buf();
       let target_path: Option<PathBuf> = loop {
@@ -304,11 +857,6 @@ fn get_target_dir(
       target_path.unwrap_or_else(|| project_root_dir.join("target"))
     }
   };
-
-  if let Some(ref triple) = target {
-    path.push(triple);
-  }
-  path.push(if is_release { "release" } else { "debug" });
   Ok(path)
 }
 
@@ -342,6 +890,7 @@ pub fn get_workspace_dir(current_dir: &Path) -> PathBuf {
 
 fn tauri_config_to_bundle_settings(
   manifest: &Manifest,
+  target: Option<&str>,
   config: crate::helpers::config::BundleConfig,
   system_tray_config: Option<crate::helpers::config::SystemTrayConfig>,
   updater_config: crate::helpers::config::UpdaterConfig,
@@ -357,8 +906,23 @@ fn tauri_config_to_bundle_settings(
   #[cfg(not(windows))]
   let windows_icon_path = PathBuf::from("");
 
-  #[allow(unused_mut)]
-  let mut resources = config.resources.unwrap_or_default();
+  let resources: Vec<String> = match config.resources {
+    // the per target resources of host builds are resolved with the host triple
+    Some(resources @ BundleResources::Target(_)) if target.is_none() => {
+      resources.for_target(host_triple().as_deref())
+    }
+    Some(resources) => resources.for_target(target),
+    None => Vec::new(),
+  };
+  // the `@<path>` entries reference resource manifests, bundled in addition to the globs
+  let (manifests, resources): (Vec<String>, Vec<String>) = resources
+    .into_iter()
+    .partition(|resource| resource.starts_with(MANIFEST_PREFIX));
+  let manifests: Vec<&str> = manifests
+    .iter()
+    .map(|manifest| &manifest[MANIFEST_PREFIX.len_utf8()..])
+    .collect();
+  let resource_mappings = read_resource_manifests(&manifests)?;
   #[allow(unused_mut)]
   let mut depends = config.deb.depends.unwrap_or_default();
 
@@ -385,6 +949,12 @@ fn tauri_config_to_bundle_settings(
     } else {
       Some(resources)
     },
+    resources_exclude: config.resources_exclude,
+    resource_mappings: if resource_mappings.is_empty() {
+      None
+    } else {
+      Some(resource_mappings)
+    },
     copyright: config.copyright,
     category: match config.category {
       Some(category) => Some(AppCategory::from_str(&category).map_err(|e| match e {
@@ -404,6 +974,14 @@ fn tauri_config_to_bundle_settings(
       },
       use_bootstrapper: Some(config.deb.use_bootstrapper),
       files: config.deb.files,
+      signing_key: config.deb.signing_key,
+      repository_metadata: config.deb.repository_metadata,
+    },
+    flatpak: FlatpakSettings {
+      runtime: config.linux.flatpak.runtime,
+      runtime_version: config.linux.flatpak.runtime_version,
+      sdk: config.linux.flatpak.sdk,
+      finish_args: config.linux.flatpak.finish_args,
     },
     macos: MacOsSettings {
       frameworks: config.macos.frameworks,
@@ -413,12 +991,28 @@ fn tauri_config_to_bundle_settings(
       exception_domain: config.macos.exception_domain,
       signing_identity: config.macos.signing_identity,
       entitlements: config.macos.entitlements,
+      notarize: config.macos.notarize,
+      info_plist: config.macos.info_plist,
+    },
+    rpm: RpmSettings {
+      gpg_key: config.linux.rpm.gpg_key,
+    },
+    snap: SnapSettings {
+      confinement: config.linux.snap.confinement,
+      grade: config.linux.snap.grade,
+      plugs: config.linux.snap.plugs,
     },
     windows: WindowsSettings {
       timestamp_url: config.windows.timestamp_url,
       digest_algorithm: config.windows.digest_algorithm,
       certificate_thumbprint: config.windows.certificate_thumbprint,
       wix: config.windows.wix.map(|w| w.into()),
+      nsis: NsisSettings {
+        install_dir: config.windows.nsis.install_dir,
+        desktop_shortcut: config.windows.nsis.desktop_shortcut,
+        start_menu_shortcut: config.windows.nsis.start_menu_shortcut,
+        delete_app_data_on_uninstall: config.windows.nsis.delete_app_data_on_uninstall,
+      },
       icon_path: windows_icon_path,
     },
     updater: Some(UpdaterSettings {
@@ -428,6 +1022,10 @@ fn tauri_config_to_bundle_settings(
       dialog: updater_config.dialog.unwrap_or(true),
       pubkey: updater_config.pubkey,
       endpoints: updater_config.endpoints,
+      archive_format: updater_config
+        .archive_format
+        .as_deref()
+        .and_then(UpdaterArchiveFormat::from_short_name),
     }),
     ..Default::default()
   })
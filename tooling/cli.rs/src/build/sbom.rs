@@ -0,0 +1,134 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::json;
+
+use std::{
+  fs::write,
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+/// The name of the CycloneDX document written to the out dir.
+const SBOM_FILE_NAME: &str = "sbom.cdx.json";
+
+#[derive(Deserialize)]
+struct CargoMetadataPackage {
+  id: String,
+  name: String,
+  version: String,
+  license: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CargoMetadataNode {
+  id: String,
+}
+
+#[derive(Deserialize)]
+struct CargoMetadataResolve {
+  nodes: Vec<CargoMetadataNode>,
+  root: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CargoMetadata {
+  packages: Vec<CargoMetadataPackage>,
+  resolve: Option<CargoMetadataResolve>,
+}
+
+/// Writes the CycloneDX software bill of materials of the app to `out_dir`, returning its path.
+///
+/// The dependencies are read from `cargo metadata`, filtered by `target` if set.
+/// `cargo_args` are the lockfile and network flags of the build, e.g. `--locked` or `--offline`.
+pub fn write_sbom(
+  app_name: &str,
+  app_version: &str,
+  target: Option<&str>,
+  cargo_args: &[&str],
+  out_dir: &Path,
+) -> crate::Result<PathBuf> {
+  let mut command = Command::new("cargo");
+  command.args(&["metadata", "--format-version", "1"]);
+  command.args(cargo_args);
+  if let Some(target) = target {
+    command.args(&["--filter-platform", target]);
+  }
+  let output = command
+    .output()
+    .with_context(|| "failed to run `cargo metadata`")?;
+  if !output.status.success() {
+    return Err(anyhow::anyhow!(
+      "`cargo metadata` failed: {}",
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+  let metadata: CargoMetadata = serde_json::from_slice(&output.stdout)
+    .with_context(|| "failed to parse the `cargo metadata` output")?;
+
+  // only the packages on the dependency graph of the app, without the app itself
+  let (resolved_ids, root_id) = match &metadata.resolve {
+    Some(resolve) => (
+      Some(
+        resolve
+          .nodes
+          .iter()
+          .map(|node| node.id.as_str())
+          .collect::<Vec<&str>>(),
+      ),
+      resolve.root.as_deref(),
+    ),
+    None => (None, None),
+  };
+  let mut components: Vec<serde_json::Value> = metadata
+    .packages
+    .iter()
+    .filter(|package| Some(package.id.as_str()) != root_id)
+    .filter(|package| {
+      resolved_ids
+        .as_ref()
+        .map(|ids| ids.contains(&package.id.as_str()))
+        .unwrap_or(true)
+    })
+    .map(|package| {
+      let mut component = json!({
+        "type": "library",
+        "name": package.name,
+        "version": package.version,
+        "purl": format!("pkg:cargo/{}@{}", package.name, package.version),
+      });
+      if let Some(license) = &package.license {
+        component["licenses"] = json!([{ "expression": license }]);
+      }
+      component
+    })
+    .collect();
+  components.sort_by(|a, b| a["purl"].as_str().cmp(&b["purl"].as_str()));
+
+  let sbom = json!({
+    "bomFormat": "CycloneDX",
+    "specVersion": "1.3",
+    "version": 1,
+    "metadata": {
+      "tools": [{
+        "vendor": "Tauri",
+        "name": "tauri-cli",
+        "version": env!("CARGO_PKG_VERSION"),
+      }],
+      "component": {
+        "type": "application",
+        "name": app_name,
+        "version": app_version,
+      },
+    },
+    "components": components,
+  });
+
+  let path = out_dir.join(SBOM_FILE_NAME);
+  write(&path, serde_json::to_string_pretty(&sbom)?)
+    .with_context(|| format!("failed to write the SBOM to {}", path.display()))?;
+  Ok(path)
+}
@@ -0,0 +1,99 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+
+use std::{
+  fs::read_dir,
+  path::{Path, PathBuf},
+  time::SystemTime,
+};
+
+/// Whether a command must run, make-style: its outputs are stale if an input is newer than one of them.
+pub enum Staleness {
+  /// Every output is newer than every input, so the command can be skipped.
+  UpToDate,
+  /// The command must run, for the given reason.
+  Stale(String),
+}
+
+/// Compares the modification times of the files matching the `inputs` and `outputs` glob patterns, relative to `base_dir`.
+///
+/// Directories are expanded to their files. The outputs are stale if no output exists yet,
+/// if no input matches (since a wrong pattern would skip the command forever) or if an input is newer than an output.
+pub fn check_staleness(
+  inputs: &[String],
+  outputs: &[String],
+  base_dir: &Path,
+) -> crate::Result<Staleness> {
+  let output_files = match_files(outputs, base_dir)?;
+  let outputs = modification_times(&output_files)?;
+  let oldest_output = match outputs.iter().min_by_key(|(_, modified)| *modified) {
+    Some(oldest_output) => oldest_output,
+    None => return Ok(Staleness::Stale("no output exists yet".into())),
+  };
+  let input_files = match_files(inputs, base_dir)?;
+  let inputs = modification_times(&input_files)?;
+  let newest_input = match inputs.iter().max_by_key(|(_, modified)| *modified) {
+    Some(newest_input) => newest_input,
+    None => return Ok(Staleness::Stale("no input file matches".into())),
+  };
+  if newest_input.1 >= oldest_output.1 {
+    return Ok(Staleness::Stale(format!(
+      "{} is newer than {}",
+      display(&newest_input.0, base_dir),
+      display(&oldest_output.0, base_dir)
+    )));
+  }
+  Ok(Staleness::UpToDate)
+}
+
+/// Gets the files matching the glob patterns, relative to `base_dir`, expanding the directories.
+fn match_files(patterns: &[String], base_dir: &Path) -> crate::Result<Vec<PathBuf>> {
+  let mut files = Vec::new();
+  for pattern in patterns {
+    let pattern_path = base_dir.join(pattern);
+    let pattern_str = pattern_path.to_string_lossy();
+    let paths =
+      glob::glob(&pattern_str).with_context(|| format!("invalid glob pattern `{}`", pattern))?;
+    for path in paths.filter_map(Result::ok) {
+      collect_files(path, &mut files);
+    }
+  }
+  Ok(files)
+}
+
+fn collect_files(path: PathBuf, files: &mut Vec<PathBuf>) {
+  if path.is_dir() {
+    if let Ok(entries) = read_dir(&path) {
+      for entry in entries.filter_map(Result::ok) {
+        collect_files(entry.path(), files);
+      }
+    }
+  } else if !files.contains(&path) {
+    files.push(path);
+  }
+}
+
+/// Gets the modification time of each file.
+fn modification_times(files: &[PathBuf]) -> crate::Result<Vec<(&PathBuf, SystemTime)>> {
+  files
+    .iter()
+    .map(|file| {
+      let modified = file
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .with_context(|| format!("failed to read the modification time of {}", file.display()))?;
+      Ok((file, modified))
+    })
+    .collect()
+}
+
+fn display(path: &Path, base_dir: &Path) -> String {
+  path
+    .strip_prefix(base_dir)
+    .unwrap_or(path)
+    .display()
+    .to_string()
+}
@@ -0,0 +1,58 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+
+use std::{path::Path, process::Command};
+
+/// Whether the binaries of the target can be stripped with the host `strip`.
+///
+/// The Windows debug symbols are already separated on PDB files. The macOS `strip` handles every Apple architecture,
+/// but the Linux one only handles the host architecture, so the cross compiled Linux binaries are skipped.
+pub fn can_strip(target: Option<&str>, host_triple: Option<&str>) -> bool {
+  let target = match target {
+    Some(target) => target,
+    None => return !cfg!(windows),
+  };
+  if target.contains("windows") {
+    return false;
+  }
+  if cfg!(target_os = "macos") {
+    target.contains("apple")
+  } else if cfg!(target_os = "linux") {
+    Some(target) == host_triple
+  } else {
+    false
+  }
+}
+
+/// Strips the symbols of the binary in place, with `strip -x` on macOS (keeping the global symbols the dynamic linker needs)
+/// and `strip` on Linux. Returns the size of the binary before and after stripping.
+pub fn strip_binary(path: &Path) -> crate::Result<(u64, u64)> {
+  let size = |path: &Path| {
+    path
+      .metadata()
+      .map(|metadata| metadata.len())
+      .with_context(|| format!("failed to read the size of {}", path.display()))
+  };
+  let original_size = size(path)?;
+
+  let mut command = Command::new("strip");
+  if cfg!(target_os = "macos") {
+    command.arg("-x");
+  }
+  let output = command
+    .arg(path)
+    .output()
+    .with_context(|| "failed to run `strip`")?;
+  if !output.status.success() {
+    return Err(anyhow::anyhow!(
+      "failed to strip {}: {}",
+      path.display(),
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+
+  Ok((original_size, size(path)?))
+}
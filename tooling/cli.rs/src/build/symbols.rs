@@ -0,0 +1,121 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use anyhow::Context;
+use zip::{write::FileOptions, ZipWriter};
+
+use std::{
+  fs::{read_dir, File},
+  io::copy,
+  path::{Path, PathBuf},
+  process::Command,
+};
+
+/// Collects the debug symbols of the app binary and zips them to `<product>-symbols.zip` on `out_dir`.
+///
+/// The symbols are looked up next to the binary, on the cargo out directory:
+/// - macOS: the `<binary>.dSYM` bundle. If cargo didn't write it (it depends on `split-debuginfo`),
+///   it's generated with `dsymutil`, which must run before the binary is stripped.
+/// - Windows (MSVC): the `<binary>.pdb` written by the linker, where the hyphens of the binary name are underscores.
+///
+/// The other targets don't have separate symbol files, so an error is returned.
+/// `bin_name` is the name of the binary compiled by cargo, before it's renamed to the product name.
+pub fn write_symbols_archive(
+  out_dir: &Path,
+  target: Option<&str>,
+  bin_name: &str,
+  product_name: &str,
+) -> crate::Result<PathBuf> {
+  // `None` is the host target
+  let is_apple = target.map_or(cfg!(target_os = "macos"), |target| target.contains("apple"));
+  let is_msvc = target.map_or(cfg!(all(windows, target_env = "msvc")), |target| {
+    target.contains("windows-msvc")
+  });
+  let symbols = if is_apple {
+    dsym_bundle(out_dir, bin_name)?
+  } else if is_msvc {
+    pdb_file(out_dir, bin_name)?
+  } else {
+    return Err(anyhow::anyhow!(
+      "the debug symbols archive isn't supported on {}, only the macOS dSYM and Windows PDB files are collected",
+      target.unwrap_or("this platform")
+    ));
+  };
+
+  let archive_path = out_dir.join(format!("{}-symbols.zip", product_name));
+  let file = File::create(&archive_path)
+    .with_context(|| format!("failed to create {}", archive_path.display()))?;
+  let mut zip = ZipWriter::new(file);
+  let base_dir = symbols.parent().unwrap_or(out_dir);
+  let mut files = Vec::new();
+  collect_files(&symbols, &mut files);
+  for file in files {
+    let name = file.strip_prefix(base_dir).unwrap_or(&file);
+    // zip entries always use forward slashes
+    let name = name
+      .components()
+      .map(|component| component.as_os_str().to_string_lossy())
+      .collect::<Vec<_>>()
+      .join("/");
+    zip.start_file(name, FileOptions::default())?;
+    let mut reader =
+      File::open(&file).with_context(|| format!("failed to read {}", file.display()))?;
+    copy(&mut reader, &mut zip)?;
+  }
+  zip.finish()?;
+
+  Ok(archive_path)
+}
+
+/// Gets the `.dSYM` bundle of the binary, generating it with `dsymutil` if cargo didn't.
+fn dsym_bundle(out_dir: &Path, bin_name: &str) -> crate::Result<PathBuf> {
+  let dsym = out_dir.join(format!("{}.dSYM", bin_name));
+  if !dsym.exists() {
+    let output = Command::new("dsymutil")
+      .arg(out_dir.join(bin_name))
+      .arg("-o")
+      .arg(&dsym)
+      .output()
+      .with_context(|| "failed to run `dsymutil`")?;
+    if !output.status.success() {
+      return Err(anyhow::anyhow!(
+        "failed to generate {}: {}",
+        dsym.display(),
+        String::from_utf8_lossy(&output.stderr).trim()
+      ));
+    }
+  }
+  Ok(dsym)
+}
+
+/// Gets the `.pdb` file of the binary.
+fn pdb_file(out_dir: &Path, bin_name: &str) -> crate::Result<PathBuf> {
+  let candidates = [
+    out_dir.join(format!("{}.pdb", bin_name.replace('-', "_"))),
+    out_dir.join(format!("{}.pdb", bin_name)),
+  ];
+  candidates
+    .iter()
+    .find(|path| path.exists())
+    .cloned()
+    .ok_or_else(|| {
+      anyhow::anyhow!(
+        "No PDB file found at {}, make sure the profile keeps the debug info",
+        candidates[0].display()
+      )
+    })
+}
+
+/// Collects the files of the path, recursively if it's a directory.
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+  if path.is_dir() {
+    if let Ok(entries) = read_dir(path) {
+      for entry in entries.filter_map(Result::ok) {
+        collect_files(&entry.path(), files);
+      }
+    }
+  } else {
+    files.push(path.to_path_buf());
+  }
+}
@@ -0,0 +1,42 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use std::time::{Duration, Instant};
+
+/// Accumulates the duration of each build phase.
+pub struct Timings {
+  /// the instant the build started.
+  start: Instant,
+  /// the phases and their accumulated duration, in the order they first ran.
+  phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+  pub fn new() -> Self {
+    Self {
+      start: Instant::now(),
+      phases: Vec::new(),
+    }
+  }
+
+  /// Adds the time elapsed since `started` to the given phase.
+  pub fn record(&mut self, phase: &'static str, started: Instant) {
+    let elapsed = started.elapsed();
+    match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+      Some((_, duration)) => *duration += elapsed,
+      None => self.phases.push((phase, elapsed)),
+    }
+  }
+
+  /// Formats the phase durations and the total build duration, e.g. `compile 42.00s, bundle 11.00s, total 53.00s`.
+  pub fn summary(&self) -> String {
+    let mut entries: Vec<String> = self
+      .phases
+      .iter()
+      .map(|(name, duration)| format!("{} {:.2}s", name, duration.as_secs_f64()))
+      .collect();
+    entries.push(format!("total {:.2}s", self.start.elapsed().as_secs_f64()));
+    entries.join(", ")
+  }
+}
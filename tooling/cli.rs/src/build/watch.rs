@@ -0,0 +1,161 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use super::Build;
+use crate::helpers::{
+  app_paths::{app_dir, tauri_dir},
+  config::get as get_config,
+  Logger,
+};
+
+use anyhow::Context;
+use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
+
+use std::{
+  env::{current_dir, set_current_dir},
+  mem::take,
+  path::{Path, PathBuf},
+  sync::mpsc::{channel, Receiver},
+  time::{Duration, Instant},
+};
+
+/// The time the watcher waits for the file changes to settle before rebuilding.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Runs the build, then runs it again each time the Rust sources, the config or the distDir change.
+///
+/// Cargo compiles incrementally and the bundler is skipped when its inputs didn't change,
+/// so each cycle only rebuilds what the changes affect. A failed cycle is reported and the watcher keeps running,
+/// Ctrl-C removes the partial artifacts of the running cycle and exits.
+pub fn run(build: Build) -> crate::Result<()> {
+  let logger = Logger::new("tauri:build");
+  // `Build::run` changes the current working directory, each cycle starts from the original one
+  let cwd = current_dir().with_context(|| "failed to read the current working directory")?;
+
+  run_cycle(&build, &cwd, &[], &logger)?;
+
+  // the config is loaded by the first cycle, with the `--config`, `--product-name` and `--app-version` overrides
+  let tauri_path = tauri_dir();
+  let dist_dir = {
+    let config = get_config(&build.config)?;
+    let config_guard = config.lock().unwrap();
+    let config_ = config_guard.as_ref().unwrap();
+    tauri_path.join(&config_.build.dist_dir)
+  };
+
+  let (tx, rx) = channel();
+  let mut watcher = watcher(tx, DEBOUNCE).unwrap();
+  watcher.watch(tauri_path.join("src"), RecursiveMode::Recursive)?;
+  watcher.watch(tauri_path.join("Cargo.toml"), RecursiveMode::NonRecursive)?;
+  watcher.watch(
+    tauri_path.join("tauri.conf.json"),
+    RecursiveMode::NonRecursive,
+  )?;
+  // the distDir is watched by its canonical path, so the event paths can be matched with it
+  let dist_dir = dist_dir.canonicalize().unwrap_or(dist_dir);
+  if dist_dir.exists() {
+    watcher.watch(&dist_dir, RecursiveMode::Recursive)?;
+  }
+
+  let mut pending_paths = Vec::new();
+  loop {
+    let changed_paths = if pending_paths.is_empty() {
+      wait_for_changes(&rx)
+    } else {
+      take(&mut pending_paths)
+    };
+    if changed_paths.is_empty() {
+      // the watcher was dropped
+      return Ok(());
+    }
+    let mut cycle_build = build.clone();
+    cycle_build.reload_config = changed_paths
+      .iter()
+      .any(|path| path.ends_with("tauri.conf.json"));
+    run_cycle(&cycle_build, &cwd, &changed_paths, &logger)?;
+    pending_paths = drain_cycle_changes(&rx, &dist_dir);
+  }
+}
+
+/// Runs one build, printing a summary of the changes that triggered it and its outcome.
+fn run_cycle(
+  build: &Build,
+  cwd: &Path,
+  changed_paths: &[PathBuf],
+  logger: &Logger<'_>,
+) -> crate::Result<()> {
+  if !changed_paths.is_empty() {
+    let app_path = app_dir();
+    let names: Vec<String> = changed_paths
+      .iter()
+      .map(|path| {
+        path
+          .strip_prefix(app_path)
+          .unwrap_or(path)
+          .display()
+          .to_string()
+      })
+      .collect();
+    logger.log(format!("Rebuilding after changes to {}", names.join(", ")));
+  }
+
+  let started = Instant::now();
+  let result = build.clone().run();
+  set_current_dir(cwd).with_context(|| "failed to change current working directory")?;
+  match result {
+    Ok(()) => logger.log(format!(
+      "Build finished in {:.1}s",
+      started.elapsed().as_secs_f32()
+    )),
+    Err(e) => logger.error(format!(
+      "Build failed in {:.1}s: {:#}",
+      started.elapsed().as_secs_f32(),
+      e
+    )),
+  }
+  logger.log("Watching for changes... (press Ctrl-C to exit)");
+  Ok(())
+}
+
+/// Blocks until a file changes, returning every path changed within the debounce window.
+///
+/// Returns an empty list if the watcher is disconnected.
+fn wait_for_changes(rx: &Receiver<DebouncedEvent>) -> Vec<PathBuf> {
+  let mut changed_paths = Vec::new();
+  while let Ok(event) = rx.recv() {
+    push_event_path(event, &mut changed_paths);
+    while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+      push_event_path(event, &mut changed_paths);
+    }
+    if !changed_paths.is_empty() {
+      break;
+    }
+  }
+  changed_paths
+}
+
+/// Collects the changes received while a cycle ran, leaving out the distDir ones:
+/// the cycle's beforeBuildCommand rewrites the distDir, which would trigger another cycle right away.
+fn drain_cycle_changes(rx: &Receiver<DebouncedEvent>, dist_dir: &Path) -> Vec<PathBuf> {
+  let mut changed_paths = Vec::new();
+  // the events of the last writes are only sent once the debounce window elapsed
+  while let Ok(event) = rx.recv_timeout(DEBOUNCE * 2) {
+    push_event_path(event, &mut changed_paths);
+  }
+  changed_paths.retain(|path| !path.starts_with(dist_dir));
+  changed_paths
+}
+
+fn push_event_path(event: DebouncedEvent, changed_paths: &mut Vec<PathBuf>) {
+  let path = match event {
+    DebouncedEvent::Create(path) => path,
+    DebouncedEvent::Remove(path) => path,
+    DebouncedEvent::Rename(_, dest) => dest,
+    DebouncedEvent::Write(path) => path,
+    _ => return,
+  };
+  if !changed_paths.contains(&path) {
+    changed_paths.push(path);
+  }
+}
@@ -0,0 +1,110 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+/// Checks that `content` is a well-formed XML document: a single root element, balanced tags and quoted attribute values.
+///
+/// The document isn't validated against a schema and the entities aren't resolved.
+pub fn check_well_formed(content: &str) -> Result<(), String> {
+  let mut open_elements: Vec<&str> = Vec::new();
+  let mut has_root = false;
+  let mut rest = content;
+  loop {
+    let start = rest.find('<').unwrap_or(rest.len());
+    let text = rest[..start].trim();
+    if !text.is_empty() && open_elements.is_empty() {
+      return Err(format!(
+        "unexpected text outside of the root element: {}",
+        text
+      ));
+    }
+    rest = &rest[start..];
+    if rest.is_empty() {
+      break;
+    }
+
+    if rest.starts_with("<?") {
+      rest = skip_past(rest, "?>")?;
+    } else if rest.starts_with("<!--") {
+      rest = skip_past(rest, "-->")?;
+    } else if rest.starts_with("<![CDATA[") {
+      if open_elements.is_empty() {
+        return Err("unexpected CDATA section outside of the root element".into());
+      }
+      rest = skip_past(rest, "]]>")?;
+    } else if rest.starts_with("<!") {
+      rest = skip_past(rest, ">")?;
+    } else {
+      let end = tag_end(rest)?;
+      let tag = &rest[1..end];
+      rest = &rest[end + 1..];
+      if let Some(name) = tag.strip_prefix('/') {
+        let name = name.trim();
+        match open_elements.pop() {
+          Some(open) if open == name => {}
+          Some(open) => {
+            return Err(format!(
+              "the closing tag </{}> doesn't match the open element <{}>",
+              name, open
+            ))
+          }
+          None => return Err(format!("unexpected closing tag </{}>", name)),
+        }
+      } else {
+        let self_closing = tag.ends_with('/');
+        let name = tag
+          .trim_end_matches('/')
+          .split_whitespace()
+          .next()
+          .unwrap_or_default();
+        if name.is_empty() {
+          return Err("found a tag without a name".into());
+        }
+        if open_elements.is_empty() {
+          if has_root {
+            return Err(format!(
+              "found a second root element <{}>, a document has a single root element",
+              name
+            ));
+          }
+          has_root = true;
+        }
+        if !self_closing {
+          open_elements.push(name);
+        }
+      }
+    }
+  }
+
+  if let Some(open) = open_elements.last() {
+    return Err(format!("the element <{}> isn't closed", open));
+  }
+  if !has_root {
+    return Err("the document doesn't have a root element".into());
+  }
+  Ok(())
+}
+
+/// Returns the rest of the document after the first `delimiter`.
+fn skip_past<'a>(rest: &'a str, delimiter: &str) -> Result<&'a str, String> {
+  rest
+    .find(delimiter)
+    .map(|end| &rest[end + delimiter.len()..])
+    .ok_or_else(|| format!("missing `{}`", delimiter))
+}
+
+/// Returns the index of the `>` closing the tag at the start of `rest`, skipping the quoted attribute values.
+fn tag_end(rest: &str) -> Result<usize, String> {
+  let mut quote = None;
+  for (index, c) in rest.char_indices() {
+    match (quote, c) {
+      (None, '"') | (None, '\'') => quote = Some(c),
+      (Some(open), c) if open == c => quote = None,
+      (None, '>') => return Ok(index),
+      (None, '<') if index > 0 => break,
+      _ => {}
+    }
+  }
+  let tag: String = rest.chars().take(40).collect();
+  Err(format!("the tag {}... isn't terminated", tag.trim()))
+}
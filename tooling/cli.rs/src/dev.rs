@@ -2,11 +2,17 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use crate::helpers::{
-  app_paths::{app_dir, tauri_dir},
-  config::{get as get_config, reload as reload_config},
-  manifest::rewrite_manifest,
-  Logger,
+use crate::{
+  build::rust::{get_cargo_target_dir, parse_runner},
+  helpers::{
+    app_paths::{app_dir, tauri_dir},
+    config::{
+      get as get_config, reload as reload_config, resolve_merge_config_paths,
+      set_cache_dir as set_config_cache_dir, set_cache_enabled as set_config_cache_enabled,
+    },
+    manifest::{rewrite_manifest, ManifestStatus},
+    ColorChoice, Logger,
+  },
 };
 
 use anyhow::Context;
@@ -49,7 +55,8 @@ pub struct Dev {
   runner: Option<String>,
   target: Option<String>,
   exit_on_panic: bool,
-  config: Option<String>,
+  no_config_cache: bool,
+  config: Vec<String>,
   args: Vec<String>,
 }
 
@@ -68,8 +75,8 @@ impl Dev {
     self
   }
 
-  pub fn config(mut self, config: String) -> Self {
-    self.config.replace(config);
+  pub fn config(mut self, config: Vec<String>) -> Self {
+    self.config = config;
     self
   }
 
@@ -78,6 +85,11 @@ impl Dev {
     self
   }
 
+  pub fn no_config_cache(mut self) -> Self {
+    self.no_config_cache = true;
+    self
+  }
+
   pub fn args(mut self, args: Vec<String>) -> Self {
     self.args = args;
     self
@@ -85,10 +97,12 @@ impl Dev {
 
   pub fn run(self) -> crate::Result<()> {
     let logger = Logger::new("tauri:dev");
+    let merge_config = resolve_merge_config_paths(&self.config)?;
     let tauri_path = tauri_dir();
     set_current_dir(&tauri_path).with_context(|| "failed to change current working directory")?;
-    let merge_config = self.config.clone();
-    let config = get_config(merge_config.as_deref())?;
+    set_config_cache_enabled(!self.no_config_cache);
+    set_config_cache_dir(get_cargo_target_dir(None)?);
+    let config = get_config(&merge_config)?;
     let mut process: Arc<SharedChild>;
 
     if let Some(before_dev) = &config
@@ -132,15 +146,18 @@ impl Dev {
       .clone()
       .or(runner_from_config)
       .unwrap_or_else(|| "cargo".to_string());
+    let (runner, runner_args) = parse_runner(&runner)?;
 
     {
       let (tx, rx) = channel();
       let mut watcher = watcher(tx, Duration::from_secs(1)).unwrap();
       watcher.watch(tauri_path.join("Cargo.toml"), RecursiveMode::Recursive)?;
-      rewrite_manifest(config.clone())?;
-      loop {
-        if let Ok(DebouncedEvent::NoticeWrite(_)) = rx.recv() {
-          break;
+      // wait for the watcher to notice the write, if any
+      if let (_, ManifestStatus::Rewritten) = rewrite_manifest(config.clone())? {
+        loop {
+          if let Ok(DebouncedEvent::NoticeWrite(_)) = rx.recv() {
+            break;
+          }
         }
       }
     }
@@ -148,7 +165,7 @@ impl Dev {
     let (child_wait_tx, child_wait_rx) = channel();
     let child_wait_rx = Arc::new(Mutex::new(child_wait_rx));
 
-    process = self.start_app(&runner, child_wait_rx.clone());
+    process = self.start_app(&runner, &runner_args, child_wait_rx.clone());
 
     let (tx, rx) = channel();
 
@@ -168,13 +185,18 @@ impl Dev {
         };
 
         if let Some(event_path) = event_path {
-          if event_path.file_name() == Some(OsStr::new("tauri.conf.json")) {
-            reload_config(merge_config.as_deref())?;
-            rewrite_manifest(config.clone())?;
+          // When tauri.conf.json is changed, rewrite_manifest will be called
+          // which will trigger the watcher again if the Cargo.toml file changes
+          // So the app should only be started here when a file other than tauri.conf.json is changed
+          // or when the manifest was left untouched
+          let restart = if event_path.file_name() == Some(OsStr::new("tauri.conf.json")) {
+            reload_config(&merge_config)?;
+            let (_, status) = rewrite_manifest(config.clone())?;
+            status == ManifestStatus::Unchanged
           } else {
-            // When tauri.conf.json is changed, rewrite_manifest will be called
-            // which will trigger the watcher again
-            // So the app should only be started when a file other than tauri.conf.json is changed
+            true
+          };
+          if restart {
             let _ = child_wait_tx.send(());
             process
               .kill()
@@ -185,16 +207,23 @@ impl Dev {
                 break;
               }
             }
-            process = self.start_app(&runner, child_wait_rx.clone());
+            process = self.start_app(&runner, &runner_args, child_wait_rx.clone());
           }
         }
       }
     }
   }
 
-  fn start_app(&self, runner: &str, child_wait_rx: Arc<Mutex<Receiver<()>>>) -> Arc<SharedChild> {
+  fn start_app(
+    &self,
+    runner: &str,
+    runner_args: &[String],
+    child_wait_rx: Arc<Mutex<Receiver<()>>>,
+  ) -> Arc<SharedChild> {
     let mut command = Command::new(runner);
+    command.args(runner_args);
     command.args(&["run", "--no-default-features"]);
+    command.args(&["--color", ColorChoice::get().cargo_color()]);
     if let Some(target) = &self.target {
       command.args(&["--target", target]);
     }
@@ -7,7 +7,8 @@ use anyhow::Context;
 use heck::KebabCase;
 use json_patch::merge;
 use once_cell::sync::Lazy;
-use serde_json::Value as JsonValue;
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
 
 #[path = "../../config_definition.rs"]
 mod config_definition;
@@ -24,18 +25,38 @@ impl From<WixConfig> for tauri_bundler::WixSettings {
       feature_refs: config.feature_refs,
       merge_refs: config.merge_refs,
       skip_webview_install: config.skip_webview_install,
+      install_scope: config
+        .install_scope
+        .as_deref()
+        .and_then(tauri_bundler::WixInstallScope::from_name)
+        .unwrap_or_default(),
     }
   }
 }
 
 use std::{
-  env::set_var,
-  fs::File,
-  io::BufReader,
+  env::{consts::OS, current_dir, set_var, var},
+  fs::{create_dir_all, read, read_to_string, write},
+  path::{Path, PathBuf},
   process::exit,
-  sync::{Arc, Mutex},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
 };
 
+/// The JSON schema the config is validated against.
+const CONFIG_SCHEMA: &str = include_str!("../../schema.json");
+
+/// The name of the file on the cargo target dir storing the last resolved config.
+const CONFIG_CACHE_FILE_NAME: &str = ".tauri-config-cache.json";
+
+/// Whether the resolved config is stored on and loaded from the cache file.
+static CONFIG_CACHE: AtomicBool = AtomicBool::new(true);
+
+/// The directory of the cache file, see [`set_cache_dir`].
+static CONFIG_CACHE_DIR: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(Default::default);
+
 pub type ConfigHandle = Arc<Mutex<Option<Config>>>;
 
 fn config_handle() -> &'static ConfigHandle {
@@ -44,18 +65,49 @@ fn config_handle() -> &'static ConfigHandle {
 }
 
 /// Gets the static parsed config from `tauri.conf.json`.
-fn get_internal(merge_config: Option<&str>, reload: bool) -> crate::Result<ConfigHandle> {
+///
+/// Each `merge_configs` item is either a JSON string or a path to a JSON file.
+/// They are merged on top of `tauri.conf.json` from left to right following the JSON Merge Patch (RFC 7396) semantics:
+/// objects are merged recursively, arrays and other values replace the previous value and `null` removes the key.
+///
+/// The `${VAR}` tokens of every string value (e.g. `build > distDir`, `build > beforeBuildCommand` or `tauri > bundle > identifier`)
+/// are then replaced with the value of the `VAR` environment variable, or with `default` for the `${VAR:-default}` syntax.
+/// Use `$${` for a literal `${`.
+///
+/// The resolved config is cached on `<target-dir>/.tauri-config-cache.json` and reused while `tauri.conf.json`,
+/// the merge configs and the interpolated environment variables are unchanged, see [`set_cache_enabled`].
+fn get_internal(merge_configs: &[String], reload: bool) -> crate::Result<ConfigHandle> {
   if !reload && config_handle().lock().unwrap().is_some() {
     return Ok(config_handle().clone());
   }
 
   let path = super::app_paths::tauri_dir().join("tauri.conf.json");
-  let file = File::open(path)?;
-  let buf = BufReader::new(file);
+  let config_bytes = read(&path).with_context(|| "failed to read `tauri.conf.json`")?;
+  let mut merge_config_strs = Vec::new();
+  for merge_config in merge_configs {
+    let merge_config_str = if Path::new(merge_config).is_file() {
+      read_to_string(merge_config)
+        .with_context(|| format!("failed to read config file `{}`", merge_config))?
+    } else {
+      merge_config.clone()
+    };
+    merge_config_strs.push(merge_config_str);
+  }
+
+  let use_cache = CONFIG_CACHE.load(Ordering::SeqCst);
+  let cache_key = config_cache_key(&config_bytes, &merge_config_strs);
+  if use_cache {
+    if let Some(config) = read_config_cache(&cache_key) {
+      set_var("TAURI_CONFIG", serde_json::to_string(&config)?);
+      *config_handle().lock().unwrap() = Some(config);
+      return Ok(config_handle().clone());
+    }
+  }
+
   let mut config: JsonValue =
-    serde_json::from_reader(buf).with_context(|| "failed to parse `tauri.conf.json`")?;
+    serde_json::from_slice(&config_bytes).with_context(|| "failed to parse `tauri.conf.json`")?;
 
-  let schema: JsonValue = serde_json::from_str(include_str!("../../schema.json"))?;
+  let schema: JsonValue = serde_json::from_str(CONFIG_SCHEMA)?;
   let mut scope = valico::json_schema::Scope::new();
   let schema = scope.compile_and_return(schema, false).unwrap();
   let state = schema.validate(&config);
@@ -75,12 +127,14 @@ fn get_internal(merge_config: Option<&str>, reload: bool) -> crate::Result<Confi
     exit(1);
   }
 
-  if let Some(merge_config) = merge_config {
-    let merge_config: JsonValue =
-      serde_json::from_str(&merge_config).with_context(|| "failed to parse config to merge")?;
+  for (merge_config, merge_config_str) in merge_configs.iter().zip(&merge_config_strs) {
+    let merge_config: JsonValue = serde_json::from_str(merge_config_str)
+      .with_context(|| format!("failed to parse config to merge `{}`", merge_config))?;
     merge(&mut config, &merge_config);
   }
 
+  interpolate_env_vars(&mut config, "")?;
+
   #[allow(unused_mut)]
   let mut config: Config = serde_json::from_value(config)?;
   #[cfg(target_os = "linux")]
@@ -88,16 +142,196 @@ fn get_internal(merge_config: Option<&str>, reload: bool) -> crate::Result<Confi
     *product_name = product_name.to_kebab_case();
   }
   set_var("TAURI_CONFIG", serde_json::to_string(&config)?);
+  if use_cache {
+    // the cache only speeds up the next run, failing to write it must not fail this one
+    let _ = write_config_cache(&cache_key, &config);
+  }
   *config_handle().lock().unwrap() = Some(config);
 
   Ok(config_handle().clone())
 }
 
-pub fn get(merge_config: Option<&str>) -> crate::Result<ConfigHandle> {
-  get_internal(merge_config, false)
+/// Enables or disables the resolved config cache (`--no-config-cache`), enabled by default.
+pub fn set_cache_enabled(enabled: bool) {
+  CONFIG_CACHE.store(enabled, Ordering::SeqCst);
+}
+
+/// Sets the directory of the cache file, i.e. the resolved cargo target directory.
+///
+/// Defaults to the `target` folder of the tauri directory.
+pub fn set_cache_dir(dir: PathBuf) {
+  CONFIG_CACHE_DIR.lock().unwrap().replace(dir);
+}
+
+fn config_cache_path() -> PathBuf {
+  CONFIG_CACHE_DIR
+    .lock()
+    .unwrap()
+    .clone()
+    .unwrap_or_else(|| super::app_paths::tauri_dir().join("target"))
+    .join(CONFIG_CACHE_FILE_NAME)
+}
+
+/// Hashes the config sources: the CLI version, the target OS, the config schema, `tauri.conf.json`,
+/// the merge configs and the values of the environment variables they reference.
+fn config_cache_key(config: &[u8], merge_configs: &[String]) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+  // the product name is only converted to kebab-case on Linux
+  hasher.update(format!("\0{}\0", OS).as_bytes());
+  hasher.update(Sha256::digest(CONFIG_SCHEMA.as_bytes()));
+  hasher.update(config);
+  let config = String::from_utf8_lossy(config);
+  let mut sources = vec![config.as_ref()];
+  for merge_config in merge_configs {
+    // the separator keeps `["ab"]` and `["a", "b"]` from hashing the same
+    hasher.update(b"\0");
+    hasher.update(merge_config.as_bytes());
+    sources.push(merge_config);
+  }
+  for source in sources {
+    for name in env_var_names(source) {
+      hasher.update(format!("\0{}={:?}", name, var(name).ok()).as_bytes());
+    }
+  }
+  hex::encode(hasher.finalize())
+}
+
+/// The names of the environment variables referenced by the `${VAR}` tokens of the given text.
+fn env_var_names(text: &str) -> Vec<&str> {
+  let mut names = Vec::new();
+  let mut rest = text;
+  while let Some(start) = rest.find("${") {
+    rest = &rest[start + 2..];
+    let token = &rest[..rest.find('}').unwrap_or_else(|| rest.len())];
+    names.push(token.split(":-").next().unwrap_or(token));
+  }
+  names
 }
 
-pub fn reload(merge_config: Option<&str>) -> crate::Result<()> {
-  get_internal(merge_config, true)?;
+/// Loads the cached config if it was resolved from the same sources.
+fn read_config_cache(key: &str) -> Option<Config> {
+  let cache = read_to_string(config_cache_path()).ok()?;
+  let mut cache: JsonValue = serde_json::from_str(&cache).ok()?;
+  if cache["key"].as_str()? != key {
+    return None;
+  }
+  serde_json::from_value(cache["config"].take()).ok()
+}
+
+fn write_config_cache(key: &str, config: &Config) -> crate::Result<()> {
+  let path = config_cache_path();
+  if let Some(parent) = path.parent() {
+    create_dir_all(parent)?;
+  }
+  write(
+    &path,
+    serde_json::to_string(&json!({
+      "key": key,
+      "config": config,
+    }))?,
+  )
+  .with_context(|| format!("failed to write {}", path.display()))?;
+  Ok(())
+}
+
+pub fn get(merge_configs: &[String]) -> crate::Result<ConfigHandle> {
+  get_internal(merge_configs, false)
+}
+
+pub fn reload(merge_configs: &[String]) -> crate::Result<()> {
+  get_internal(merge_configs, true)?;
   Ok(())
 }
+
+/// Expands the environment variables of all string values of the config, recursively.
+///
+/// `path` is the config path of `value`, e.g. `build > distDir`, used on the error messages.
+fn interpolate_env_vars(value: &mut JsonValue, path: &str) -> crate::Result<()> {
+  let child_path = |key: &str| {
+    if path.is_empty() {
+      key.to_string()
+    } else {
+      format!("{} > {}", path, key)
+    }
+  };
+  match value {
+    JsonValue::String(value) => {
+      *value = interpolate(value).with_context(|| {
+        format!(
+          "failed to expand the environment variables of the `{}` config",
+          path
+        )
+      })?;
+    }
+    JsonValue::Array(values) => {
+      for (index, value) in values.iter_mut().enumerate() {
+        interpolate_env_vars(value, &child_path(&index.to_string()))?;
+      }
+    }
+    JsonValue::Object(values) => {
+      for (key, value) in values.iter_mut() {
+        interpolate_env_vars(value, &child_path(key))?;
+      }
+    }
+    _ => {}
+  }
+  Ok(())
+}
+
+/// Replaces the `${VAR}` and `${VAR:-default}` tokens of the given string, keeping `$${` as a literal `${`.
+fn interpolate(value: &str) -> crate::Result<String> {
+  let mut interpolated = String::new();
+  let mut rest = value;
+  while let Some(start) = rest.find("${") {
+    if rest[..start].ends_with('$') {
+      interpolated.push_str(&rest[..start - 1]);
+      interpolated.push_str("${");
+      rest = &rest[start + 2..];
+      continue;
+    }
+    interpolated.push_str(&rest[..start]);
+    let end = start
+      + rest[start..]
+        .find('}')
+        .ok_or_else(|| anyhow::anyhow!("unterminated `${{` token on `{}`", value))?;
+    let token = &rest[start + 2..end];
+    let (name, default) = match token.find(":-") {
+      Some(index) => (&token[..index], Some(&token[index + 2..])),
+      None => (token, None),
+    };
+    match (var(name), default) {
+      (Ok(env_value), _) => interpolated.push_str(&env_value),
+      (Err(_), Some(default)) => interpolated.push_str(default),
+      (Err(_), None) => {
+        return Err(anyhow::anyhow!(
+          "the `{}` environment variable is not set and has no default, use `${{{}:-default}}` to set one",
+          name,
+          name
+        ))
+      }
+    }
+    rest = &rest[end + 1..];
+  }
+  interpolated.push_str(rest);
+  Ok(interpolated)
+}
+
+/// Makes the merge config items that point to existing files absolute,
+/// so they can still be read after changing the current working directory.
+pub fn resolve_merge_config_paths(merge_configs: &[String]) -> crate::Result<Vec<String>> {
+  let cwd = current_dir().with_context(|| "failed to read the current working directory")?;
+  Ok(
+    merge_configs
+      .iter()
+      .map(|merge_config| {
+        let path = cwd.join(merge_config);
+        if path.is_file() {
+          path.display().to_string()
+        } else {
+          merge_config.clone()
+        }
+      })
+      .collect(),
+  )
+}
@@ -2,7 +2,101 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+use crate::console::{
+  set_colors_enabled, set_colors_enabled_stderr, user_attended, user_attended_stderr,
+};
 use colored::Colorize;
+use once_cell::sync::OnceCell;
+
+use std::{
+  env::var_os,
+  str::FromStr,
+  sync::atomic::{AtomicBool, Ordering},
+};
+
+static STDERR_ONLY: AtomicBool = AtomicBool::new(false);
+static COLOR_CHOICE: OnceCell<ColorChoice> = OnceCell::new();
+
+/// When to use colors on the output, set with the global `--color` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+  /// Colors the output when it's a terminal and the `NO_COLOR` environment variable isn't set.
+  Auto,
+  Always,
+  Never,
+}
+
+impl FromStr for ColorChoice {
+  type Err = anyhow::Error;
+
+  fn from_str(s: &str) -> crate::Result<Self> {
+    match s {
+      "auto" => Ok(Self::Auto),
+      "always" => Ok(Self::Always),
+      "never" => Ok(Self::Never),
+      _ => Err(anyhow::anyhow!(
+        "invalid `--color` value: {}, expected `auto`, `always` or `never`",
+        s
+      )),
+    }
+  }
+}
+
+impl ColorChoice {
+  /// Sets the color choice of the log lines, the prompts and the cargo output.
+  pub fn set(self) {
+    let _ = COLOR_CHOICE.set(self);
+    apply_color_choice();
+  }
+
+  /// Gets the color choice, defaulting to [`ColorChoice::Auto`].
+  pub fn get() -> Self {
+    COLOR_CHOICE.get().copied().unwrap_or(Self::Auto)
+  }
+
+  /// The value of cargo's `--color` option when its output is the user terminal.
+  pub fn cargo_color(self) -> &'static str {
+    match self {
+      Self::Always => "always",
+      Self::Never => "never",
+      Self::Auto if var_os("NO_COLOR").is_some() => "never",
+      Self::Auto => "auto",
+    }
+  }
+}
+
+/// Whether the log lines are colored, resolving [`ColorChoice::Auto`] with the log output stream.
+pub fn colors_enabled() -> bool {
+  match ColorChoice::get() {
+    ColorChoice::Always => true,
+    ColorChoice::Never => false,
+    ColorChoice::Auto => {
+      var_os("NO_COLOR").is_none()
+        && if STDERR_ONLY.load(Ordering::Relaxed) {
+          user_attended_stderr()
+        } else {
+          user_attended()
+        }
+    }
+  }
+}
+
+fn apply_color_choice() {
+  let enabled = colors_enabled();
+  colored::control::set_override(enabled);
+  set_colors_enabled(enabled);
+  set_colors_enabled_stderr(enabled);
+}
+
+/// Prints a line to stdout, or to stderr if [`Logger::stderr_only`] has been called.
+pub fn print_line(message: impl AsRef<str>) {
+  super::progress::clear_spinner();
+  if STDERR_ONLY.load(Ordering::Relaxed) {
+    eprintln!("{}", message.as_ref());
+  } else {
+    println!("{}", message.as_ref());
+  }
+}
 
 pub struct Logger<'a> {
   context: &'a str,
@@ -13,27 +107,34 @@ impl<'a> Logger<'a> {
     Self { context }
   }
 
+  /// Routes all log output to stderr, keeping stdout free for machine-readable output.
+  pub fn stderr_only() {
+    STDERR_ONLY.store(true, Ordering::Relaxed);
+    // the automatic colors depend on the output stream
+    apply_color_choice();
+  }
+
   pub fn log(&self, message: impl AsRef<str>) {
-    println!(
+    print_line(format!(
       "{} {}",
       format!("[{}]", self.context).green().bold(),
       message.as_ref()
-    );
+    ));
   }
 
   pub fn warn(&self, message: impl AsRef<str>) {
-    println!(
+    print_line(format!(
       "{} {}",
       format!("[{}]", self.context).yellow().bold(),
       message.as_ref()
-    );
+    ));
   }
 
   pub fn error(&self, message: impl AsRef<str>) {
-    println!(
+    print_line(format!(
       "{} {}",
       format!("[{}]", self.context).red().bold(),
       message.as_ref()
-    );
+    ));
   }
 }
@@ -14,6 +14,17 @@ use std::{
 
 pub struct Manifest {
   pub features: Vec<String>,
+  /// the unified diff of the Cargo.toml changes, if the file was rewritten.
+  pub diff: Option<String>,
+}
+
+/// Whether [`rewrite_manifest`] wrote the Cargo.toml file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestStatus {
+  /// The tauri features changed and the file was rewritten.
+  Rewritten,
+  /// The file already had the expected content and was left untouched.
+  Unchanged,
 }
 
 fn features_to_vec(features: &Array) -> Vec<String> {
@@ -26,7 +37,10 @@ fn features_to_vec(features: &Array) -> Vec<String> {
   string_features
 }
 
-pub fn rewrite_manifest(config: ConfigHandle) -> crate::Result<Manifest> {
+/// Updates the tauri dependency features on the Cargo.toml file to match the config.
+///
+/// The file is only written if its content changes, so cargo's incremental build isn't invalidated by a new mtime.
+pub fn rewrite_manifest(config: ConfigHandle) -> crate::Result<(Manifest, ManifestStatus)> {
   let manifest_path = tauri_dir().join("Cargo.toml");
   let mut manifest_str = String::new();
   let mut manifest_file = File::open(&manifest_path)
@@ -104,26 +118,124 @@ pub fn rewrite_manifest(config: ConfigHandle) -> crate::Result<Manifest> {
       }
     }
   } else {
-    return Ok(Manifest {
+    return Ok((
+      Manifest {
+        features: features_to_vec(&features),
+        diff: None,
+      },
+      ManifestStatus::Unchanged,
+    ));
+  }
+
+  let new_manifest_str = manifest
+    .to_string_in_original_order()
+    // apply some formatting fixes
+    .replace(r#"" ,features =["#, r#"", features = ["#)
+    .replace("]}", "] }")
+    .replace("={", "= {")
+    .replace("=[", "= [");
+
+  let (status, diff) = if new_manifest_str == manifest_str {
+    (ManifestStatus::Unchanged, None)
+  } else {
+    let mut manifest_file =
+      File::create(&manifest_path).with_context(|| "failed to open Cargo.toml for rewrite")?;
+    manifest_file.write_all(new_manifest_str.as_bytes())?;
+    manifest_file.flush()?;
+    (
+      ManifestStatus::Rewritten,
+      Some(unified_diff(&manifest_str, &new_manifest_str)),
+    )
+  };
+
+  Ok((
+    Manifest {
       features: features_to_vec(&features),
-    });
+      diff,
+    },
+    status,
+  ))
+}
+
+enum DiffLine<'a> {
+  Equal(&'a str),
+  Delete(&'a str),
+  Insert(&'a str),
+}
+
+/// Computes the unified diff of the Cargo.toml rewrite, with 3 lines of context around each change.
+fn unified_diff(old: &str, new: &str) -> String {
+  const CONTEXT: usize = 3;
+  let old_lines: Vec<&str> = old.lines().collect();
+  let new_lines: Vec<&str> = new.lines().collect();
+
+  // lcs[i][j] is the length of the longest common subsequence of old_lines[i..] and new_lines[j..]
+  let mut lcs = vec![vec![0usize; new_lines.len() + 1]; old_lines.len() + 1];
+  for i in (0..old_lines.len()).rev() {
+    for j in (0..new_lines.len()).rev() {
+      lcs[i][j] = if old_lines[i] == new_lines[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        lcs[i + 1][j].max(lcs[i][j + 1])
+      };
+    }
+  }
+
+  let mut lines = Vec::new();
+  let (mut i, mut j) = (0, 0);
+  while i < old_lines.len() || j < new_lines.len() {
+    if i < old_lines.len() && j < new_lines.len() && old_lines[i] == new_lines[j] {
+      lines.push(DiffLine::Equal(old_lines[i]));
+      i += 1;
+      j += 1;
+    } else if i < old_lines.len() && (j == new_lines.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+      lines.push(DiffLine::Delete(old_lines[i]));
+      i += 1;
+    } else {
+      lines.push(DiffLine::Insert(new_lines[j]));
+      j += 1;
+    }
   }
 
-  let mut manifest_file =
-    File::create(&manifest_path).with_context(|| "failed to open Cargo.toml for rewrite")?;
-  manifest_file.write_all(
-    manifest
-      .to_string_in_original_order()
-      // apply some formatting fixes
-      .replace(r#"" ,features =["#, r#"", features = ["#)
-      .replace("]}", "] }")
-      .replace("={", "= {")
-      .replace("=[", "= [")
-      .as_bytes(),
-  )?;
-  manifest_file.flush()?;
-
-  Ok(Manifest {
-    features: features_to_vec(&features),
-  })
+  let changes: Vec<usize> = lines
+    .iter()
+    .enumerate()
+    .filter(|(_, line)| !matches!(line, DiffLine::Equal(_)))
+    .map(|(index, _)| index)
+    .collect();
+  let is_old_line = |line: &&DiffLine<'_>| !matches!(line, DiffLine::Insert(_));
+  let is_new_line = |line: &&DiffLine<'_>| !matches!(line, DiffLine::Delete(_));
+
+  let mut diff = String::from("--- Cargo.toml\n+++ Cargo.toml\n");
+  let mut k = 0;
+  while k < changes.len() {
+    // merge the changes whose context overlaps on a single hunk
+    let start = changes[k].saturating_sub(CONTEXT);
+    let mut end = changes[k];
+    while k < changes.len() && changes[k] <= end + 2 * CONTEXT {
+      end = changes[k];
+      k += 1;
+    }
+    let end = (end + CONTEXT + 1).min(lines.len());
+    let hunk = &lines[start..end];
+
+    diff.push_str(&format!(
+      "@@ -{},{} +{},{} @@\n",
+      lines[..start].iter().filter(is_old_line).count() + 1,
+      hunk.iter().filter(is_old_line).count(),
+      lines[..start].iter().filter(is_new_line).count() + 1,
+      hunk.iter().filter(is_new_line).count(),
+    ));
+    for line in hunk {
+      let (prefix, line) = match line {
+        DiffLine::Equal(line) => (' ', line),
+        DiffLine::Delete(line) => ('-', line),
+        DiffLine::Insert(line) => ('+', line),
+      };
+      diff.push(prefix);
+      diff.push_str(line);
+      diff.push('\n');
+    }
+  }
+  diff
 }
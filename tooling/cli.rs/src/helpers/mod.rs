@@ -6,9 +6,10 @@ pub mod app_paths;
 pub mod config;
 mod logger;
 pub mod manifest;
+pub mod progress;
 pub mod updater_signature;
 
-pub use logger::Logger;
+pub use logger::{colors_enabled, print_line, ColorChoice, Logger};
 
 use std::{
   io::{BufRead, BufReader},
@@ -25,7 +26,7 @@ pub fn execute_with_output(cmd: &mut Command) -> crate::Result<()> {
     let reader = BufReader::new(stdout);
 
     for line in reader.lines() {
-      println!("{}", line.expect("Failed to get line"));
+      print_line(line.expect("Failed to get line"));
     }
   }
 
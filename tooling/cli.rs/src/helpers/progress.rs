@@ -0,0 +1,93 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use super::Logger;
+use crate::console::{user_attended_stderr, Term};
+
+use std::{
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread::{sleep, spawn, JoinHandle},
+  time::{Duration, Instant},
+};
+
+/// Whether a spinner is currently drawn on stderr.
+static SPINNER_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+const SPINNER_FRAMES: &[&str] = &["-", "\\", "|", "/"];
+const SPINNER_TICK: Duration = Duration::from_millis(100);
+/// The interval of the plain log lines printed instead of the spinner when stderr isn't a terminal.
+const PLAIN_PROGRESS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Clears the spinner line so a log line can be printed over it.
+pub fn clear_spinner() {
+  if SPINNER_ACTIVE.load(Ordering::Relaxed) {
+    let _ = Term::stderr().clear_line();
+  }
+}
+
+/// Shows activity during a long-running phase of the build.
+///
+/// Draws a spinner on stderr when it's a terminal, otherwise prints a plain log line every 30 seconds (e.g. on CI).
+/// The spinner stops when the value is dropped.
+pub struct Progress {
+  stop: Arc<AtomicBool>,
+  handle: Option<JoinHandle<()>>,
+}
+
+impl Progress {
+  pub fn start(message: impl Into<String>) -> Self {
+    let message = message.into();
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_ = stop.clone();
+    let attended = user_attended_stderr();
+    if attended {
+      SPINNER_ACTIVE.store(true, Ordering::Relaxed);
+    }
+    let handle = spawn(move || {
+      let started = Instant::now();
+      let mut last_log = Instant::now();
+      let term = Term::stderr();
+      let mut frame = 0;
+      while !stop_.load(Ordering::Relaxed) {
+        if attended {
+          let _ = term.write_str(&format!(
+            "\r{} {} ({}s)\r",
+            SPINNER_FRAMES[frame % SPINNER_FRAMES.len()],
+            message,
+            started.elapsed().as_secs()
+          ));
+          frame += 1;
+        } else if last_log.elapsed() >= PLAIN_PROGRESS_INTERVAL {
+          Logger::new("tauri:build").log(format!(
+            "{}... ({}s elapsed)",
+            message,
+            started.elapsed().as_secs()
+          ));
+          last_log = Instant::now();
+        }
+        sleep(SPINNER_TICK);
+      }
+      if attended {
+        let _ = term.clear_line();
+      }
+    });
+    Self {
+      stop,
+      handle: Some(handle),
+    }
+  }
+}
+
+impl Drop for Progress {
+  fn drop(&mut self) {
+    self.stop.store(true, Ordering::Relaxed);
+    if let Some(handle) = self.handle.take() {
+      let _ = handle.join();
+    }
+    SPINNER_ACTIVE.store(false, Ordering::Relaxed);
+  }
+}
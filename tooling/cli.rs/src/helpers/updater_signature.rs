@@ -4,17 +4,26 @@
 
 extern crate minisign;
 
+use super::{print_line, Logger};
+
+use anyhow::Context;
 use base64::{decode, encode};
-use minisign::{sign, KeyPair as KP, SecretKeyBox};
+use minisign::{sign, KeyPair as KP, SecretKey, SecretKeyBox};
 use std::{
   env::var_os,
   fs::{self, File, OpenOptions},
-  io::{BufReader, BufWriter, Write},
+  io::{self, BufReader, BufWriter, Write},
   path::{Path, PathBuf},
   str,
-  time::{SystemTime, UNIX_EPOCH},
+  thread::{sleep, spawn},
+  time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+/// The default number of attempts to sign each updater archive.
+pub const DEFAULT_SIGN_ATTEMPTS: u32 = 3;
+/// The delay before the first signing retry, doubled on each subsequent retry.
+const SIGN_RETRY_DELAY: Duration = Duration::from_millis(500);
+
 /// A key pair (`PublicKey` and `SecretKey`).
 #[derive(Clone, Debug)]
 pub struct KeyPair {
@@ -111,14 +120,31 @@ pub fn sign_file<P>(
 where
   P: AsRef<Path>,
 {
-  let decoded_secret = decode_key(private_key)?;
-  let sk_box = SecretKeyBox::from_string(&decoded_secret).unwrap();
-  let sk = sk_box.into_secret_key(Some(password)).unwrap();
+  let sk = secret_key(private_key, password)?;
+  let signature_path = signature_path(bin_path.as_ref(), 0);
+  sign_file_to(&sk, bin_path, &signature_path, prehashed)
+}
 
-  // We need to append .sig at the end it's where the signature will be stored
-  let signature_path_string = format!("{}.sig", bin_path.as_ref().display());
-  let signature_path = Path::new(&signature_path_string);
+/// Decodes the base64 encoded private key with its password
+fn secret_key(private_key: String, password: String) -> crate::Result<SecretKey> {
+  let decoded_secret = decode_key(private_key)?;
+  let sk_box = SecretKeyBox::from_string(&decoded_secret)
+    .with_context(|| "failed to decode the secret key")?;
+  sk_box
+    .into_secret_key(Some(password))
+    .with_context(|| "incorrect updater private key password")
+}
 
+/// Sign files, storing the signature at `signature_path`
+fn sign_file_to<P>(
+  sk: &SecretKey,
+  bin_path: P,
+  signature_path: &Path,
+  prehashed: bool,
+) -> crate::Result<(PathBuf, String)>
+where
+  P: AsRef<Path>,
+{
   let mut signature_box_writer = create_file(&signature_path)?;
 
   let trusted_comment = format!(
@@ -131,7 +157,7 @@ where
 
   let signature_box = sign(
     None,
-    &sk,
+    sk,
     data_reader,
     prehashed | should_be_prehashed,
     Some(trusted_comment.as_str()),
@@ -144,32 +170,249 @@ where
   Ok((fs::canonicalize(&signature_path)?, encoded_signature))
 }
 
-/// Sign files using the TAURI_KEY_PASSWORD and TAURI_PRIVATE_KEY environment variables
-pub fn sign_file_from_env_variables<P>(path_to_sign: P) -> crate::Result<(PathBuf, String)>
-where
-  P: AsRef<Path>,
-{
+/// The path of the signature of `path` made with the key at `index`.
+///
+/// The first key writes `<path>.sig`, the n-th key writes `<path>.<n>.sig`.
+fn signature_path(path: &Path, index: usize) -> PathBuf {
+  if index == 0 {
+    PathBuf::from(format!("{}.sig", path.display()))
+  } else {
+    PathBuf::from(format!("{}.{}.sig", path.display(), index + 1))
+  }
+}
+
+/// Reads the private key password from the TAURI_KEY_PASSWORD environment variable
+fn password_from_env_variable() -> String {
   // if no password provided we set empty string
-  let password_string = match var_os("TAURI_KEY_PASSWORD") {
+  match var_os("TAURI_KEY_PASSWORD") {
     Some(value) => String::from(value.to_str().unwrap()),
     None => "".into(),
-  };
-  // get the private key
-  if let Some(private_key) = var_os("TAURI_PRIVATE_KEY") {
-    // check if this file exist..
-    let mut private_key_string = String::from(private_key.to_str().unwrap());
-    let pk_dir = Path::new(&private_key_string);
+  }
+}
+
+/// Reads the password of the key at `index`: TAURI_KEY_PASSWORD_<n> for the n-th key if it's set,
+/// otherwise TAURI_KEY_PASSWORD.
+fn key_password(index: usize) -> String {
+  if index > 0 {
+    if let Some(value) = var_os(format!("TAURI_KEY_PASSWORD_{}", index + 1)) {
+      return String::from(value.to_str().unwrap());
+    }
+  }
+  password_from_env_variable()
+}
+
+/// A private key used to sign the updater archives, with its password.
+#[derive(Clone)]
+pub enum SigningKey {
+  /// A private key file.
+  File { path: PathBuf, password: String },
+  /// A base64 encoded private key.
+  Value { key: String, password: String },
+}
+
+impl SigningKey {
+  /// Reads and decodes the private key.
+  fn secret_key(&self) -> crate::Result<SecretKey> {
+    match self {
+      Self::File { path, password } => {
+        let private_key = read_key_from_file(path)
+          .with_context(|| format!("failed to read the private key at {}", path.display()))?;
+        secret_key(private_key, password.clone())
+      }
+      Self::Value { key, password } => secret_key(key.clone(), password.clone()),
+    }
+  }
+}
+
+/// Reads the signing keys from the TAURI_PRIVATE_KEY environment variable, followed by
+/// TAURI_PRIVATE_KEY_2, TAURI_PRIVATE_KEY_3 and so on until one isn't set, e.g. to sign with the old and the new key during a key rotation.
+///
+/// Each variable holds a private key or the path of its file. The n-th key password is read from TAURI_KEY_PASSWORD_<n>,
+/// falling back to TAURI_KEY_PASSWORD.
+pub fn signing_keys_from_env_variables() -> crate::Result<Vec<SigningKey>> {
+  let mut keys = Vec::new();
+  loop {
+    let name = if keys.is_empty() {
+      "TAURI_PRIVATE_KEY".to_string()
+    } else {
+      format!("TAURI_PRIVATE_KEY_{}", keys.len() + 1)
+    };
+    let private_key = match var_os(&name) {
+      Some(private_key) => String::from(private_key.to_str().unwrap()),
+      None => break,
+    };
+    let password = key_password(keys.len());
     // Check if user provided a path or a key
     // We validate if the path exist or no.
-    if pk_dir.exists() {
-      // read file content as use it as private key
-      private_key_string = read_key_from_file(pk_dir)?;
+    let pk_dir = Path::new(&private_key);
+    let key = if pk_dir.exists() {
+      SigningKey::File {
+        path: pk_dir.to_path_buf(),
+        password,
+      }
+    } else {
+      SigningKey::Value {
+        key: private_key,
+        password,
+      }
+    };
+    keys.push(key);
+  }
+  if keys.is_empty() {
+    // reject if we don't have the private key
+    return Err(anyhow::anyhow!("A public key has been found, but no private key. Make sure to set `TAURI_PRIVATE_KEY` environment variable."));
+  }
+  Ok(keys)
+}
+
+/// Gets the signing keys of the private key files at `paths`.
+///
+/// The n-th key password is read from TAURI_KEY_PASSWORD_<n>, falling back to TAURI_KEY_PASSWORD.
+pub fn signing_keys_from_paths(paths: &[PathBuf]) -> Vec<SigningKey> {
+  paths
+    .iter()
+    .enumerate()
+    .map(|(index, path)| SigningKey::File {
+      path: path.clone(),
+      password: key_password(index),
+    })
+    .collect()
+}
+
+/// Signs the given file with the key at `index` of the signing keys,
+/// retrying transient failures with exponential backoff.
+///
+/// Only IO errors such as timeouts or interruptions are retried, up to `attempts` tries in total.
+/// The key is decoded once beforehand, so an invalid key or password fails on the first try.
+fn sign_file_with_retries(
+  path_to_sign: &Path,
+  key: &SigningKey,
+  index: usize,
+  attempts: u32,
+) -> crate::Result<(PathBuf, String)> {
+  let sk = key.secret_key()?;
+  let signature_path = signature_path(path_to_sign, index);
+  let attempts = attempts.max(1);
+  let mut delay = SIGN_RETRY_DELAY;
+  let mut attempt = 1;
+  loop {
+    match sign_file_to(&sk, path_to_sign, &signature_path, false) {
+      Ok(signed) => return Ok(signed),
+      Err(e) if attempt < attempts && is_transient_error(&e) => {
+        Logger::new("tauri:sign").warn(format!(
+          "Failed to sign {}: {}. Retrying in {}ms ({}/{})",
+          path_to_sign.display(),
+          e,
+          delay.as_millis(),
+          attempt,
+          attempts - 1
+        ));
+        sleep(delay);
+        delay *= 2;
+        attempt += 1;
+      }
+      Err(e) => return Err(e),
+    }
+  }
+}
+
+/// Whether the signing error is caused by a transient IO failure and is worth retrying.
+fn is_transient_error(error: &anyhow::Error) -> bool {
+  error.chain().any(|cause| {
+    cause
+      .downcast_ref::<io::Error>()
+      .map(|e| {
+        matches!(
+          e.kind(),
+          io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+        )
+      })
+      .unwrap_or(false)
+  })
+}
+
+/// An updater archive and its signatures, one per signing key.
+pub struct SignedArchive {
+  pub archive: PathBuf,
+  pub signatures: Vec<PathBuf>,
+}
+
+/// Signs the given updater archives with each of the signing keys, using up to one thread per CPU.
+///
+/// Each signature is tried up to `attempts` times when signing fails with a transient error.
+/// The returned archives follow the order of the given archives, and their signatures the order of the keys.
+pub fn sign_updater_archives(
+  paths: Vec<PathBuf>,
+  keys: Vec<SigningKey>,
+  attempts: u32,
+) -> crate::Result<Vec<SignedArchive>> {
+  if paths.is_empty() {
+    return Ok(Vec::new());
+  }
+  let threads = num_cpus::get().max(1);
+  let chunk_size = (paths.len() + threads - 1) / threads;
+  let handles: Vec<_> = paths
+    .chunks(chunk_size)
+    .map(|chunk| {
+      let chunk = chunk.to_vec();
+      let keys = keys.clone();
+      spawn(move || {
+        chunk
+          .into_iter()
+          .map(|path| {
+            let signatures = keys
+              .iter()
+              .enumerate()
+              .map(|(index, key)| {
+                sign_file_with_retries(&path, key, index, attempts)
+                  .map(|(signature_path, _signature)| signature_path)
+              })
+              .collect::<crate::Result<Vec<PathBuf>>>()
+              .with_context(|| format!("failed to sign {}", path.display()))?;
+            Ok(SignedArchive {
+              archive: path,
+              signatures,
+            })
+          })
+          .collect::<crate::Result<Vec<SignedArchive>>>()
+      })
+    })
+    .collect();
+
+  let mut signed_archives = Vec::new();
+  for handle in handles {
+    let mut archives = handle
+      .join()
+      .map_err(|_| anyhow::anyhow!("the signing thread panicked"))??;
+    signed_archives.append(&mut archives);
+  }
+  Ok(signed_archives)
+}
+
+/// Prints the signed updater archives, each followed by the paths of its signatures.
+pub fn print_signed_updater_archive(signed_archives: &[SignedArchive]) -> crate::Result<()> {
+  let pluralised = if signed_archives.len() == 1 {
+    "updater archive"
+  } else {
+    "updater archives"
+  };
+  let msg = format!("{} {}:", signed_archives.len(), pluralised);
+  let logger = Logger::new("Signed");
+  logger.log(&msg);
+  for signed_archive in signed_archives {
+    print_line(format!("        {}", signed_archive.archive.display()));
+    for signature in &signed_archive.signatures {
+      print_line(format!("          {}", signature.display()));
     }
-    // sign our file
-    return sign_file(private_key_string, password_string, path_to_sign, false);
   }
-  // reject if we don't have the private key
-  Err(anyhow::anyhow!("A public key has been found, but no private key. Make sure to set `TAURI_PRIVATE_KEY` environment variable."))
+  Ok(())
 }
 
 fn unix_timestamp() -> u64 {
@@ -185,13 +428,39 @@ where
   P: AsRef<Path>,
 {
   let data_path = data_path.as_ref();
+  // keeps the IO error in the chain, so transient failures are retried
   let file = OpenOptions::new()
     .read(true)
     .open(data_path)
-    .map_err(|e| minisign::PError::new(minisign::ErrorKind::Io, e))?;
+    .with_context(|| format!("failed to open {}", data_path.display()))?;
   let should_be_hashed = match file.metadata() {
     Ok(metadata) => metadata.len() > (1u64 << 30),
     Err(_) => true,
   };
   Ok((BufReader::new(file), should_be_hashed))
 }
+
+#[cfg(test)]
+mod tests {
+  use super::{generate_key, is_transient_error, sign_file_with_retries, SigningKey};
+  use std::fs::write;
+
+  #[test]
+  fn bad_password_is_not_retried() {
+    let key_pair = generate_key(Some("password".into())).expect("failed to generate the key");
+    let dir = tempfile::tempdir().expect("failed to create the temp dir");
+    let archive = dir.path().join("app.tar.gz");
+    write(&archive, "archive").expect("failed to write the archive");
+
+    let key = SigningKey::Value {
+      key: key_pair.sk,
+      password: "wrong password".into(),
+    };
+    let error =
+      sign_file_with_retries(&archive, &key, 0, 3).expect_err("signed with a bad password");
+    assert_eq!(error.to_string(), "incorrect updater private key password");
+    assert!(!is_transient_error(&error));
+    // the key is rejected before the signature file is created
+    assert!(!dir.path().join("app.tar.gz.sig").exists());
+  }
+}
@@ -2,10 +2,15 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use crate::helpers::{
-  app_paths::{app_dir, tauri_dir},
-  config::get as get_config,
+use crate::{
+  build::rust::AppSettings,
+  helpers::{
+    app_paths::{app_dir, tauri_dir},
+    config::get as get_config,
+    manifest::rewrite_manifest,
+  },
 };
+use anyhow::Context;
 use serde::Deserialize;
 
 use std::{
@@ -72,7 +77,9 @@ struct CargoManifest {
 }
 
 #[derive(Default)]
-pub struct Info;
+pub struct Info {
+  bundle: bool,
+}
 
 fn crate_latest_version(name: &str) -> Option<String> {
   let url = format!("https://docs.rs/crate/{}/", name);
@@ -330,6 +337,11 @@ impl Info {
     Default::default()
   }
 
+  pub fn bundle(mut self) -> Self {
+    self.bundle = true;
+    self
+  }
+
   pub fn run(self) -> crate::Result<()> {
     let os_info = os_info::get();
     InfoBlock {
@@ -527,7 +539,7 @@ impl Info {
         .suffix(suffix)
         .display();
 
-      if let Ok(config) = get_config(None) {
+      if let Ok(config) = get_config(&[]) {
         let config_guard = config.lock().unwrap();
         let config = config_guard.as_ref().unwrap();
         InfoBlock::new("build-type")
@@ -590,6 +602,38 @@ impl Info {
       }
     }
 
+    if self.bundle {
+      print_bundle_info()?;
+    }
+
     Ok(())
   }
 }
+
+/// Prints the bundle settings and binaries resolved the same way `tauri build` does, without compiling or bundling the app.
+fn print_bundle_info() -> crate::Result<()> {
+  InfoBlock::new("Bundle").section().display();
+  let config = get_config(&[]).with_context(|| "failed to read the Tauri config")?;
+  let (manifest, _) = rewrite_manifest(config.clone())?;
+  let config_guard = config.lock().unwrap();
+  let config = config_guard.as_ref().unwrap();
+
+  let app_settings = AppSettings::new(config)?;
+  for binary in app_settings.get_binaries(config)? {
+    InfoBlock::new("binary")
+      .value(binary.name().to_string())
+      .suffix(if binary.main() {
+        Some(" (main)".to_string())
+      } else {
+        None
+      })
+      .display();
+  }
+  InfoBlock::new("settings")
+    .value(format!(
+      "{:#?}",
+      app_settings.get_bundle_settings(config, &manifest, None)?
+    ))
+    .display();
+  Ok(())
+}
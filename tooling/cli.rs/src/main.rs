@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
+use anyhow::Context;
 pub use anyhow::Result;
 use clap::{crate_version, load_yaml, App, AppSettings, ArgMatches};
 use dialoguer::Input;
@@ -11,6 +12,7 @@ mod dev;
 mod helpers;
 mod info;
 mod init;
+mod schema;
 mod sign;
 
 // temporary fork from https://github.com/mitsuhiko/console until 0.14.1+ release
@@ -20,6 +22,7 @@ mod console;
 #[allow(dead_code)]
 mod dialoguer;
 
+use helpers::ColorChoice;
 pub use helpers::Logger;
 
 macro_rules! value_or_prompt {
@@ -93,7 +96,8 @@ fn dev_command(matches: &ArgMatches) -> Result<()> {
   let runner = matches.value_of("runner");
   let target = matches.value_of("target");
   let exit_on_panic = matches.is_present("exit-on-panic");
-  let config = matches.value_of("config");
+  let no_config_cache = matches.is_present("no-config-cache");
+  let config = matches.values_of_lossy("config");
   let args: Vec<String> = matches
     .values_of("args")
     .map(|a| a.into_iter().map(|v| v.to_string()).collect())
@@ -108,7 +112,10 @@ fn dev_command(matches: &ArgMatches) -> Result<()> {
     dev_runner = dev_runner.target(target.to_string());
   }
   if let Some(config) = config {
-    dev_runner = dev_runner.config(config.to_string());
+    dev_runner = dev_runner.config(config);
+  }
+  if no_config_cache {
+    dev_runner = dev_runner.no_config_cache();
   }
 
   dev_runner.run()
@@ -116,37 +123,264 @@ fn dev_command(matches: &ArgMatches) -> Result<()> {
 
 fn build_command(matches: &ArgMatches) -> Result<()> {
   let runner = matches.value_of("runner");
-  let target = matches.value_of("target");
+  let targets = matches.values_of_lossy("target");
+  let features = matches.values_of_lossy("features").map(|features| {
+    features
+      .iter()
+      .flat_map(|feature| feature.split(|c| c == ',' || c == ' '))
+      .filter(|feature| !feature.is_empty())
+      .map(ToString::to_string)
+      .collect::<Vec<String>>()
+  });
+  let no_default_features = matches.is_present("no-default-features");
+  let args: Vec<String> = matches
+    .values_of("args")
+    .map(|a| a.into_iter().map(|v| v.to_string()).collect())
+    .unwrap_or_default();
+  let all_features = matches.is_present("all-features");
   let debug = matches.is_present("debug");
-  let verbose = matches.is_present("verbose");
+  let profile = matches.value_of("profile");
+  let bin = matches.value_of("bin");
+  let verbose = matches.occurrences_of("verbose");
   let bundles = matches.values_of_lossy("bundle");
-  let config = matches.value_of("config");
-
-  let mut build_runner = build::Build::new();
+  let config = matches.values_of_lossy("config");
+  let no_bundle = matches.is_present("no-bundle");
+  let json = matches.is_present("json");
+  let out_dir = matches.value_of("out-dir");
+  let target_dir = matches.value_of("target-dir");
+  let timings = matches.is_present("timings");
+  let frozen = matches.is_present("frozen");
+  let offline = matches.is_present("offline");
+  let locked = matches.is_present("locked");
+  let raw_cargo = matches.is_present("raw-cargo");
+  let universal = matches.is_present("universal");
+  let dry_run = matches.is_present("dry-run");
+  let allow_unsigned_updater = matches.is_present("allow-unsigned-updater");
+  let checksums = matches.value_of("checksums");
+  let checksum_files = matches.is_present("checksum-files");
+  let strict = matches.is_present("strict");
+  let strip = matches.is_present("strip");
+  let debug_symbols = matches.is_present("debug-symbols");
+  let include_debug_symbols = matches.is_present("include-debug-symbols");
+  let gatekeeper = matches.is_present("gatekeeper");
+  let bundle_only = matches.is_present("bundle-only");
+  let private_keys = matches.values_of_lossy("private-key");
+  let report = matches.value_of("report");
+  let clean = matches.is_present("clean");
+  let bundle_identifier_suffix = matches.value_of("bundle-identifier-suffix");
+  let force = matches.is_present("force");
+  let product_name = matches.value_of("product-name");
+  let app_version = matches.value_of("app-version");
+  let allow_debug_updater = matches.is_present("allow-debug-updater");
+  let quiet = matches.is_present("quiet");
+  let keep_going = matches.is_present("keep-going");
+  let sbom = matches.is_present("sbom");
+  let watch = matches.is_present("watch");
+  let no_config_cache = matches.is_present("no-config-cache");
+  let since = matches.value_of("since");
+  let reproducible = matches.is_present("reproducible");
+  let explain_target = matches.is_present("explain-target");
+  let print_config = matches.is_present("print-config");
+  let list_bundles = matches.is_present("list-bundles");
+  let sign_retries = matches
+    .value_of("sign-retries")
+    .map(|retries| {
+      retries
+        .parse::<u32>()
+        .with_context(|| format!("invalid `--sign-retries` value: {}", retries))
+    })
+    .transpose()?;
+  let jobs = matches
+    .value_of("jobs")
+    .map(ToString::to_string)
+    .or_else(|| std::env::var("TAURI_CARGO_JOBS").ok())
+    .map(|jobs| match jobs.parse::<u32>() {
+      Ok(jobs) if jobs > 0 => Ok(jobs),
+      _ => Err(anyhow::anyhow!(
+        "invalid `--jobs` value: {}, expected a positive integer",
+        jobs
+      )),
+    })
+    .transpose()?;
+
+  let mut build_runner = build::Build::new().args(args);
   if let Some(runner) = runner {
     build_runner = build_runner.runner(runner.to_string());
   }
-  if let Some(target) = target {
-    build_runner = build_runner.target(target.to_string());
+  if let Some(targets) = targets {
+    build_runner = build_runner.targets(targets);
+  }
+  if let Some(features) = features {
+    build_runner = build_runner.features(features);
+  }
+  if no_default_features {
+    build_runner = build_runner.no_default_features();
+  }
+  if all_features {
+    build_runner = build_runner.all_features();
   }
   if debug {
     build_runner = build_runner.debug();
   }
-  if verbose {
-    build_runner = build_runner.verbose();
+  if let Some(profile) = profile {
+    build_runner = build_runner.profile(profile.to_string());
+  }
+  if let Some(bin) = bin {
+    build_runner = build_runner.bin(bin.to_string());
+  }
+  if let Some(jobs) = jobs {
+    build_runner = build_runner.jobs(jobs);
+  }
+  if verbose > 0 {
+    build_runner = build_runner.verbose(verbose.min(u8::MAX as u64) as u8);
   }
   if let Some(bundles) = bundles {
     build_runner = build_runner.bundles(bundles);
   }
   if let Some(config) = config {
-    build_runner = build_runner.config(config.to_string());
+    build_runner = build_runner.config(config);
+  }
+  if no_bundle {
+    build_runner = build_runner.no_bundle();
+  }
+  if json {
+    build_runner = build_runner.json();
+  }
+  if let Some(out_dir) = out_dir {
+    build_runner = build_runner.out_dir(out_dir.to_string());
+  }
+  if let Some(target_dir) = target_dir {
+    build_runner = build_runner.target_dir(target_dir.to_string());
+  }
+  if timings {
+    build_runner = build_runner.timings();
+  }
+  if frozen {
+    build_runner = build_runner.frozen();
+  }
+  if offline {
+    build_runner = build_runner.offline();
+  }
+  if locked {
+    build_runner = build_runner.locked();
+  }
+  if raw_cargo {
+    build_runner = build_runner.raw_cargo();
+  }
+  if let Some(sign_retries) = sign_retries {
+    build_runner = build_runner.sign_retries(sign_retries);
+  }
+  if universal {
+    build_runner = build_runner.universal();
+  }
+  if dry_run {
+    build_runner = build_runner.dry_run();
+  }
+  if allow_unsigned_updater {
+    build_runner = build_runner.allow_unsigned_updater();
+  }
+  if let Some(checksums) = checksums {
+    build_runner = build_runner.checksums(checksums.to_string());
+  }
+  if checksum_files {
+    build_runner = build_runner.checksum_files();
+  }
+  if strict {
+    build_runner = build_runner.strict();
+  }
+  if strip {
+    build_runner = build_runner.strip();
+  }
+  if debug_symbols {
+    build_runner = build_runner.debug_symbols();
+  }
+  if include_debug_symbols {
+    build_runner = build_runner.include_debug_symbols();
+  }
+  if gatekeeper {
+    build_runner = build_runner.gatekeeper();
+  }
+  if bundle_only {
+    build_runner = build_runner.bundle_only();
+  }
+  for private_key in private_keys.unwrap_or_default() {
+    build_runner = build_runner.private_key(private_key);
+  }
+  if let Some(report) = report {
+    build_runner = build_runner.report(report.to_string());
+  }
+  if clean {
+    build_runner = build_runner.clean();
+  }
+  if let Some(suffix) = bundle_identifier_suffix {
+    build_runner = build_runner.bundle_identifier_suffix(suffix.to_string());
+  }
+  if force {
+    build_runner = build_runner.force();
+  }
+  if let Some(product_name) = product_name {
+    build_runner = build_runner.product_name(product_name.to_string());
+  }
+  if let Some(app_version) = app_version {
+    build_runner = build_runner.app_version(app_version.to_string());
+  }
+  if allow_debug_updater {
+    build_runner = build_runner.allow_debug_updater();
+  }
+  if quiet {
+    build_runner = build_runner.quiet();
+  }
+  if keep_going {
+    build_runner = build_runner.keep_going();
+  }
+  if sbom {
+    build_runner = build_runner.sbom();
+  }
+  if reproducible {
+    build_runner = build_runner.reproducible();
+  }
+  if explain_target {
+    build_runner = build_runner.explain_target();
+  }
+  if print_config {
+    build_runner = build_runner.print_config();
+  }
+  if list_bundles {
+    build_runner = build_runner.list_bundles();
+  }
+  if watch {
+    build_runner = build_runner.watch();
+  }
+  if no_config_cache {
+    build_runner = build_runner.no_config_cache();
+  }
+  if let Some(since) = since {
+    build_runner = build_runner.since(since.to_string());
   }
 
   build_runner.run()
 }
 
-fn info_command() -> Result<()> {
-  info::Info::new().run()
+fn info_command(matches: &ArgMatches) -> Result<()> {
+  let bundle = matches.is_present("bundle");
+
+  let mut info_runner = info::Info::new();
+  if bundle {
+    info_runner = info_runner.bundle();
+  }
+
+  info_runner.run()
+}
+
+fn config_schema_command(matches: &ArgMatches) -> Result<()> {
+  let output = matches.value_of("output");
+
+  let mut schema_runner = schema::Schema::new();
+  if let Some(output) = output {
+    schema_runner = schema_runner.output(output.to_string());
+  }
+
+  schema_runner.run()
 }
 
 fn sign_command(matches: &ArgMatches) -> Result<()> {
@@ -181,6 +415,11 @@ fn sign_command(matches: &ArgMatches) -> Result<()> {
     return keygen_runner.generate_keys();
   }
 
+  // sign updater archives from environment variables
+  if let Some(archives) = matches.values_of_lossy("archives") {
+    return sign::ArchiveSigner::new().archives(archives).run();
+  }
+
   // sign our binary / archive
   let mut sign_runner = sign::Signer::new();
   if let Some(private_key) = private_key {
@@ -206,7 +445,20 @@ fn sign_command(matches: &ArgMatches) -> Result<()> {
   sign_runner.run()
 }
 
-fn main() -> Result<()> {
+fn main() {
+  if let Err(error) = run() {
+    // the same output as returning the error from `main`
+    eprintln!("Error: {:?}", error);
+    // the build failures get a distinct exit code, see `BuildError::exit_code`
+    let exit_code = error
+      .chain()
+      .find_map(|cause| cause.downcast_ref::<build::BuildError>())
+      .map_or(1, build::BuildError::exit_code);
+    std::process::exit(exit_code);
+  }
+}
+
+fn run() -> Result<()> {
   let yaml = load_yaml!("cli.yml");
   let app = App::from(yaml)
     .version(crate_version!())
@@ -216,16 +468,27 @@ fn main() -> Result<()> {
   let app_matches = app.get_matches();
   let matches = app_matches.subcommand_matches("tauri").unwrap();
 
+  // the global option is propagated to the subcommand matches
+  let color = matches
+    .subcommand_name()
+    .and_then(|name| matches.subcommand_matches(name))
+    .and_then(|matches| matches.value_of("color"))
+    .or_else(|| matches.value_of("color"))
+    .unwrap_or("auto");
+  color.parse::<ColorChoice>()?.set();
+
   if let Some(matches) = matches.subcommand_matches("init") {
     init_command(&matches)?;
   } else if let Some(matches) = matches.subcommand_matches("dev") {
     dev_command(&matches)?;
   } else if let Some(matches) = matches.subcommand_matches("build") {
     build_command(&matches)?;
-  } else if matches.subcommand_matches("info").is_some() {
-    info_command()?;
+  } else if let Some(matches) = matches.subcommand_matches("info") {
+    info_command(&matches)?;
   } else if let Some(matches) = matches.subcommand_matches("sign") {
     sign_command(&matches)?;
+  } else if let Some(matches) = matches.subcommand_matches("config-schema") {
+    config_schema_command(&matches)?;
   }
 
   Ok(())
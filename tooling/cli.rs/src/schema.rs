@@ -0,0 +1,42 @@
+// Copyright 2019-2021 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::helpers::Logger;
+use anyhow::Context;
+
+use std::{fs::write, path::PathBuf};
+
+/// The JSON schema of `tauri.conf.json`, generated from the config definition by the build script.
+///
+/// It's the same schema used to validate the config before deserializing it.
+const CONFIG_SCHEMA: &str = include_str!("../schema.json");
+
+#[derive(Default)]
+pub struct Schema {
+  output: Option<PathBuf>,
+}
+
+impl Schema {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn output(mut self, output: String) -> Self {
+    self.output.replace(PathBuf::from(output));
+    self
+  }
+
+  pub fn run(self) -> crate::Result<()> {
+    match self.output {
+      Some(output) => {
+        write(&output, CONFIG_SCHEMA)
+          .with_context(|| format!("failed to write the config schema to {}", output.display()))?;
+        Logger::new("tauri:config-schema")
+          .log(format!("Wrote the config schema to {}", output.display()));
+      }
+      None => println!("{}", CONFIG_SCHEMA),
+    }
+    Ok(())
+  }
+}
@@ -3,7 +3,8 @@
 // SPDX-License-Identifier: MIT
 
 use crate::helpers::updater_signature::{
-  generate_key, read_key_from_file, save_keypair, sign_file,
+  generate_key, print_signed_updater_archive, read_key_from_file, save_keypair, sign_file,
+  sign_updater_archives, signing_keys_from_env_variables, DEFAULT_SIGN_ATTEMPTS,
 };
 use std::path::{Path, PathBuf};
 
@@ -78,6 +79,47 @@ impl Signer {
   }
 }
 
+/// Signs updater archives using the `TAURI_PRIVATE_KEY` and `TAURI_KEY_PASSWORD` environment variables.
+///
+/// Each archive gets one more signature per `TAURI_PRIVATE_KEY_<n>` variable, e.g. during a key rotation.
+#[derive(Default)]
+pub struct ArchiveSigner {
+  archives: Vec<String>,
+}
+
+impl ArchiveSigner {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Sets the archives to sign. Each item is a path or a glob pattern.
+  pub fn archives(mut self, archives: Vec<String>) -> Self {
+    self.archives = archives;
+    self
+  }
+
+  pub fn run(self) -> crate::Result<()> {
+    let mut paths = Vec::new();
+    for pattern in &self.archives {
+      let mut matches = glob::glob(pattern)
+        .with_context(|| format!("invalid glob pattern `{}`", pattern))?
+        .collect::<Result<Vec<PathBuf>, _>>()
+        .with_context(|| format!("failed to read the paths matching `{}`", pattern))?;
+      if matches.is_empty() {
+        return Err(anyhow::anyhow!("No file matches `{}`", pattern));
+      }
+      paths.append(&mut matches);
+    }
+
+    let signed_archives = sign_updater_archives(
+      paths,
+      signing_keys_from_env_variables()?,
+      DEFAULT_SIGN_ATTEMPTS,
+    )?;
+    print_signed_updater_archive(&signed_archives)
+  }
+}
+
 #[derive(Default)]
 pub struct KeyGenerator {
   password: Option<String>,